@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use scsir::command::ata::identify::IdentifySatResponse;
+
+fuzz_target!(|data: &[u8]| {
+    let response = IdentifySatResponse::new(data.to_vec());
+
+    let _ = (
+        response.firmware_rev(),
+        response.model_nr(),
+        response.security_state(),
+        response.time_required_for_normal_erase(),
+        response.time_required_for_enhanced_erase(),
+        response.capacity_bytes(),
+        response.physical_sector_size(),
+    );
+});
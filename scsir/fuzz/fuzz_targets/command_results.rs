@@ -0,0 +1,28 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use scsir_fuzz::Replay;
+
+fuzz_target!(|data: &[u8]| {
+    let scsi = Replay::scsi(data);
+
+    let _ = scsi.get_lba_status().issue();
+    let _ = scsi.get_stream_status().issue();
+    let _ = scsi.log_sense().issue();
+    let _ = scsi.mode_sense().issue_6();
+    let _ = scsi.mode_sense().issue_10();
+    let _ = scsi.persistent_reserve_in().issue();
+    let _ = scsi.read_capacity().issue_10();
+    let _ = scsi.read_capacity().issue_16();
+    let _ = scsi.read_defect_data().issue_10();
+    let _ = scsi.read_defect_data().issue_12();
+    let _ = scsi.receive_diagnostic_results().issue();
+    let _ = scsi.report_identifying_information().issue();
+    let _ = scsi.report_luns().issue();
+    let _ = scsi.report_supported_operation_codes().issue();
+    let _ = scsi.report_supported_task_management_functions().issue();
+    let _ = scsi.report_timestamp().issue();
+    let _ = scsi.report_zones().issue();
+    let _ = scsi.request_sense().issue();
+    let _ = scsi.sat().identify().issue_16();
+});
@@ -0,0 +1,27 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use scsir::shortcut::inquiry::*;
+use scsir_fuzz::Replay;
+
+fuzz_target!(|data: &[u8]| {
+    let scsi = Replay::scsi(data);
+
+    let _ = ascii_information(&mut scsi.inquiry(), 0x81);
+    let _ = block_device_characteristics(&mut scsi.inquiry());
+    let _ = block_device_characteristics_extension(&mut scsi.inquiry());
+    let _ = block_limits(&mut scsi.inquiry());
+    let _ = block_limits_extension(&mut scsi.inquiry());
+    let _ = device_identification(&mut scsi.inquiry());
+    let _ = extended_inquiry_data(&mut scsi.inquiry());
+    let _ = logical_block_provisioning(&mut scsi.inquiry());
+    let _ = mode_page_policy(&mut scsi.inquiry());
+    let _ = power_condition(&mut scsi.inquiry());
+    let _ = power_consumption(&mut scsi.inquiry());
+    let _ = scsi_ports(&mut scsi.inquiry());
+    let _ = standard_inquiry(&mut scsi.inquiry());
+    let _ = supported_vital_product_data_pages(&mut scsi.inquiry());
+    let _ = unit_serial_number(&mut scsi.inquiry());
+    let _ = zoned_block_device_characteristics(&mut scsi.inquiry());
+    let _ = scsir::DeviceProfile::probe(&scsi);
+});
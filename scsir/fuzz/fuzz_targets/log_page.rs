@@ -0,0 +1,36 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use scsir::shortcut::log::*;
+
+macro_rules! parse {
+    ($data:expr, $($parameter:ty),* $(,)?) => {
+        $(
+            let _ = PageWrapper::<$parameter>::from_bytes($data);
+        )*
+    };
+}
+
+fuzz_target!(|data: &[u8]| {
+    parse!(
+        data,
+        ApplicationClientParameter,
+        BackgroundOperationParameter,
+        BackgroundScanParameter,
+        EnvironmentalLimitsParameter,
+        EnvironmentalReportingParameter,
+        GeneralParameter,
+        InformationalExceptionsParameter,
+        LogicalBlockProvisioningParameter,
+        NonMediumErrorParameter,
+        PowerConditionTransitionsParameter,
+        ProtocolSpecificPortParameter,
+        SelfTestResultsParameter,
+        SolidStateMediaParameter,
+        StartStopCycleCounterParameter,
+        SupportedLogPagesAndSubpagesParameter,
+        SupportedLogPagesParameter,
+        TemperatureParameter,
+        UtilizationParameter,
+    );
+});
@@ -0,0 +1,57 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use scsir::shortcut::mode::*;
+
+macro_rules! parse {
+    ($header_type:expr, $descriptor_type:expr, $data:expr, $($page:ty),* $(,)?) => {
+        $(
+            let _ = PageWrapper::<$page>::from_bytes($header_type, $descriptor_type, $data);
+        )*
+    };
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Some((selector, data)) = data.split_first() else {
+        return;
+    };
+    let header_type = match selector & 1 {
+        0 => HeaderType::Short,
+        _ => HeaderType::Long,
+    };
+    let descriptor_type = match selector & 2 {
+        0 => DescriptorType::Short,
+        _ => DescriptorType::Long,
+    };
+
+    parse!(
+        header_type,
+        descriptor_type,
+        data,
+        ApplicationTagPage,
+        BackgroundControlPage,
+        BackgroundOperationControlPage,
+        CachingPage,
+        CommandDurationLimitPage,
+        ControlExtensionPage,
+        ControlPage,
+        DisconnectReconnectFcPage,
+        DisconnectReconnectSasPage,
+        EnhancedPhyControlPage,
+        GeneralPage,
+        InformationalExceptionsControlPage,
+        IoAdviceHintsGroupingPage,
+        LogicalBlockProvisioningPage,
+        LogicalUnitControlFcPage,
+        LogicalUnitControlSasPage,
+        PageHeaderStorage,
+        PhyControlAndDiscoverPage,
+        PowerConditionPage,
+        PowerConsumptionPage,
+        ProtocolSpecificPortFcpage,
+        ProtocolSpecificPortSasPage,
+        ReadWriteErrorRecoveryPage,
+        SharedPortControlPage,
+        VerifyErrorRecoveryPage,
+    );
+});
@@ -0,0 +1,26 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use scsir::command::sense::{SenseData, MAX_SENSE_BUFFER_LENGTH};
+
+fuzz_target!(|data: &[u8]| {
+    let mut raw = [0; MAX_SENSE_BUFFER_LENGTH];
+    let length = data.len().min(MAX_SENSE_BUFFER_LENGTH);
+    raw[..length].copy_from_slice(&data[..length]);
+
+    let sense = SenseData::parse(&raw, data.len());
+    let _ = (
+        sense.sense_key(),
+        sense.asc(),
+        sense.ascq(),
+        sense.information(),
+        sense.command_specific_information(),
+        sense.sense_key_specific(),
+        sense.progress(),
+        sense.is_deferred(),
+        sense.field_replaceable_unit(),
+    );
+    let _ = sense.to_bytes();
+    let _ = sense.to_fixed().to_bytes();
+    let _ = sense.to_descriptor().to_bytes();
+});
@@ -0,0 +1,50 @@
+use std::io;
+
+use scsir::{Scsi, Transport, TransportRequest, TransportResponse};
+
+/// Answers every command with the same data, sense and status, taken from the fuzzer input.
+#[derive(Debug)]
+pub struct Replay {
+    status: u8,
+    sense: Vec<u8>,
+    data: Vec<u8>,
+}
+
+impl Replay {
+    /// The first byte selects GOOD or CHECK CONDITION, the second one the sense length, the
+    /// sense data follows, the rest is the data-in buffer.
+    pub fn scsi(input: &[u8]) -> Scsi {
+        let (header, input) = input.split_at(input.len().min(2));
+        let status = match header.first() {
+            Some(byte) if byte & 1 != 0 => 0x02,
+            _ => 0x00,
+        };
+        let sense_length = usize::min(header.get(1).copied().unwrap_or(0) as usize, input.len());
+        let (sense, data) = input.split_at(sense_length);
+
+        Scsi::with_transport(
+            "fuzz",
+            Replay {
+                status,
+                sense: sense.to_vec(),
+                data: data.to_vec(),
+            },
+        )
+    }
+}
+
+impl Transport for Replay {
+    fn execute(&self, request: TransportRequest) -> io::Result<TransportResponse> {
+        let data_length = self.data.len().min(request.data.len());
+        request.data[..data_length].copy_from_slice(&self.data[..data_length]);
+        let sense_length = self.sense.len().min(request.sense.len());
+        request.sense[..sense_length].copy_from_slice(&self.sense[..sense_length]);
+
+        Ok(TransportResponse {
+            status: self.status,
+            sense_length,
+            residual: request.data.len() - data_length,
+            ..Default::default()
+        })
+    }
+}
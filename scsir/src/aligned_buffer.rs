@@ -0,0 +1,123 @@
+use std::{
+    alloc::{self, Layout},
+    fmt::Debug,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+    slice,
+};
+
+/// A zero filled heap buffer with an aligned start, e.g. for direct I/O, which only bypasses
+/// the kernel buffers if the data buffer is aligned.
+pub struct AlignedBuffer {
+    ptr: NonNull<u8>,
+    layout: Layout,
+}
+
+unsafe impl Send for AlignedBuffer {}
+unsafe impl Sync for AlignedBuffer {}
+
+impl AlignedBuffer {
+    /// `alignment` must be a power of two.
+    pub fn new(length: usize, alignment: usize) -> crate::Result<Self> {
+        let layout = Layout::from_size_align(length, alignment).map_err(|_| {
+            crate::Error::BadArgument(format!(
+                "can't allocate {} bytes aligned to {}, the alignment must be a power of two.",
+                length, alignment
+            ))
+        })?;
+
+        let ptr = if length == 0 {
+            // a dangling, but aligned, pointer is enough for an empty slice
+            NonNull::new(alignment as *mut u8).unwrap()
+        } else {
+            let ptr = unsafe { alloc::alloc_zeroed(layout) };
+            NonNull::new(ptr).unwrap_or_else(|| alloc::handle_alloc_error(layout))
+        };
+
+        Ok(Self { ptr, layout })
+    }
+
+    /// Aligned to the page size of the system.
+    pub fn page_aligned(length: usize) -> crate::Result<Self> {
+        Self::new(length, page_size())
+    }
+
+    pub fn alignment(&self) -> usize {
+        self.layout.align()
+    }
+}
+
+impl Deref for AlignedBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.layout.size()) }
+    }
+}
+
+impl DerefMut for AlignedBuffer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.layout.size()) }
+    }
+}
+
+impl Clone for AlignedBuffer {
+    fn clone(&self) -> Self {
+        let mut buffer = Self::new(self.layout.size(), self.layout.align()).unwrap();
+        buffer.copy_from_slice(self);
+        buffer
+    }
+}
+
+impl Debug for AlignedBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AlignedBuffer")
+            .field("length", &self.layout.size())
+            .field("alignment", &self.layout.align())
+            .finish()
+    }
+}
+
+impl Drop for AlignedBuffer {
+    fn drop(&mut self) {
+        if self.layout.size() != 0 {
+            unsafe { alloc::dealloc(self.ptr.as_ptr(), self.layout) };
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn page_size() -> usize {
+    use nix::libc;
+
+    match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        size if size > 0 => size as usize,
+        _ => 4096,
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn page_size() -> usize {
+    4096
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alignment_test() {
+        for (length, alignment) in [(0, 512), (100, 512), (8192, 4096)] {
+            let buffer = AlignedBuffer::new(length, alignment).unwrap();
+            assert_eq!(buffer.len(), length);
+            assert_eq!(buffer.as_ptr() as usize % alignment, 0);
+            assert!(buffer.iter().all(|&b| b == 0));
+        }
+
+        assert!(AlignedBuffer::new(512, 3).is_err());
+        assert_eq!(
+            AlignedBuffer::page_aligned(1).unwrap().alignment(),
+            page_size()
+        );
+    }
+}
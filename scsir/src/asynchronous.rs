@@ -0,0 +1,455 @@
+use std::{
+    any::Any,
+    collections::HashMap,
+    fs::OpenOptions,
+    future::{poll_fn, Future},
+    io,
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    task::{Poll, Waker},
+    time::Duration,
+};
+
+use nix::libc;
+use tokio::io::{unix::AsyncFd, Interest};
+
+use crate::{
+    command::sense::MAX_SENSE_BUFFER_LENGTH,
+    file_descriptor::FileDescriptor,
+    os::linux::{read_response, sg_version_num, write_request, AsyncResponse},
+    scsi::{command_bytes, data_buffers, transfer_length, SG_DEFAULT_TIMEOUT},
+    transport::{TransportRequest, TransportResponse},
+    Command, Scsi,
+};
+
+/// Issues commands as futures on an sg character device, through the asynchronous
+/// `write()`/`read()` interface of the sg driver. Waiting is driven by the tokio reactor, so
+/// no thread is blocked, and many commands can be in flight at the same time.
+///
+/// Builders hand over their commands with their `prepare_*` methods, e.g.
+/// `scsi.issue(scsi_handle.read().transfer_length(8).prepare_10()?).await`.
+///
+/// Dropping a future abandons its command. The buffers of an abandoned command are kept until
+/// the driver hands it back, or until every clone of the `AsyncScsi` is dropped.
+///
+/// Commands go to the driver directly, there are no interceptors or retry policies.
+#[derive(Clone, Debug)]
+pub struct AsyncScsi {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    path: PathBuf,
+    // declared before slots, the file must be closed before the buffers are freed
+    file: AsyncFd<FileDescriptor>,
+    timeout: Mutex<Duration>,
+    slots: Mutex<Slots>,
+}
+
+/// The commands handed to the driver, matched to its completions by pack id.
+#[derive(Debug, Default)]
+struct Slots {
+    next_pack_id: i32,
+    slots: HashMap<i32, Slot>,
+}
+
+#[derive(Debug)]
+struct Slot {
+    buffers: Box<dyn Any + Send>,
+    response: Option<TransportResponse>,
+    waker: Option<Waker>,
+    abandoned: bool,
+}
+
+struct Buffers<W> {
+    data_buffer: W,
+    sense_buffer: [u8; MAX_SENSE_BUFFER_LENGTH],
+}
+
+impl AsyncScsi {
+    /// Must be called within a tokio runtime.
+    pub fn new<P: AsRef<Path> + ?Sized>(path: &P) -> crate::Result<AsyncScsi> {
+        let mut options = OpenOptions::new();
+        options.read(true).write(true);
+        Self::open(path, options)
+    }
+
+    /// Must be called within a tokio runtime.
+    pub fn new_readonly<P: AsRef<Path> + ?Sized>(path: &P) -> crate::Result<AsyncScsi> {
+        let mut options = OpenOptions::new();
+        options.read(true);
+        Self::open(path, options)
+    }
+
+    fn open<P: AsRef<Path> + ?Sized>(
+        path: &P,
+        mut options: OpenOptions,
+    ) -> crate::Result<AsyncScsi> {
+        options.custom_flags(libc::O_NONBLOCK);
+        let file_descriptor = FileDescriptor::open(path, options)?;
+
+        if !file_descriptor.is_char()? || sg_version_num(&file_descriptor)? < 30000 {
+            return Err(crate::Error::NotScsiDevice(path.as_ref().to_owned()));
+        }
+
+        Ok(AsyncScsi {
+            shared: Arc::new(Shared {
+                path: path.as_ref().to_owned(),
+                file: AsyncFd::with_interest(file_descriptor, Interest::READABLE)?,
+                timeout: Mutex::new(Duration::from_millis(SG_DEFAULT_TIMEOUT)),
+                slots: Mutex::default(),
+            }),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.shared.path
+    }
+
+    pub fn set_timeout(&self, timeout: Duration) {
+        *lock(&self.shared.timeout) = timeout;
+    }
+
+    pub fn timeout(&self) -> Duration {
+        *lock(&self.shared.timeout)
+    }
+
+    /// The asynchronous equivalent of [`Scsi::issue`].
+    pub async fn issue<T>(&self, command: T) -> T::ReturnType
+    where
+        T: Command + 'static,
+        T::DataBufferWrapper: Send,
+    {
+        let size_of_data_buffer = transfer_length(&command);
+        let mut buffers = Box::new(Buffers {
+            data_buffer: command.data(),
+            sense_buffer: [0; MAX_SENSE_BUFFER_LENGTH],
+        });
+        let command_buffer = command.command();
+        let timeout = command.timeout_override().unwrap_or(self.timeout());
+
+        let pack_id = {
+            // submit while locked, so the completion can't be read before the slot exists
+            let mut slots = lock(&self.shared.slots);
+            let pack_id = slots.next_pack_id();
+
+            let Buffers {
+                data_buffer,
+                sense_buffer,
+            } = buffers.as_mut();
+            let result = unsafe {
+                let (data, data_out) = data_buffers(&command, data_buffer);
+                write_request(
+                    self.shared.file.get_ref().raw(),
+                    TransportRequest {
+                        command: command_bytes(&command_buffer),
+                        direction: command.direction(),
+                        data,
+                        data_out,
+                        sense: sense_buffer,
+                        timeout,
+                    },
+                    pack_id,
+                    0,
+                )
+            };
+
+            if let Err(error) = result {
+                drop(slots);
+                return Scsi::process_response(
+                    &command,
+                    command_bytes(&command_buffer),
+                    &mut buffers.data_buffer,
+                    size_of_data_buffer,
+                    &buffers.sense_buffer,
+                    Err(error),
+                );
+            }
+
+            slots.insert(pack_id, buffers);
+
+            pack_id
+        };
+
+        let mut abandon = AbandonOnDrop {
+            slots: &self.shared.slots,
+            pack_id,
+            armed: true,
+        };
+
+        match self.shared.wait(pack_id).await {
+            Ok((buffers, response)) => {
+                abandon.armed = false;
+                let mut buffers = buffers
+                    .downcast::<Buffers<T::DataBufferWrapper>>()
+                    .expect("buffers of the same command");
+                Scsi::process_response(
+                    &command,
+                    command_bytes(&command_buffer),
+                    &mut buffers.data_buffer,
+                    size_of_data_buffer,
+                    &buffers.sense_buffer,
+                    Ok(response),
+                )
+            }
+            // the buffers are still owned by the driver, so report with fresh ones
+            Err(error) => Scsi::process_response(
+                &command,
+                command_bytes(&command_buffer),
+                &mut command.data(),
+                size_of_data_buffer,
+                &[0; MAX_SENSE_BUFFER_LENGTH],
+                Err(error),
+            ),
+        }
+    }
+}
+
+impl Shared {
+    async fn wait(&self, pack_id: i32) -> io::Result<(Box<dyn Any + Send>, TransportResponse)> {
+        let mut readable = Box::pin(self.file.readable());
+
+        poll_fn(|cx| loop {
+            if let Some(completed) = lock(&self.slots).take(pack_id, cx.waker()) {
+                return Poll::Ready(Ok(completed));
+            }
+
+            let mut ready = match readable.as_mut().poll(cx) {
+                Poll::Ready(Ok(ready)) => ready,
+                Poll::Ready(Err(error)) => return Poll::Ready(Err(error)),
+                Poll::Pending => return Poll::Pending,
+            };
+
+            // readiness is cleared once the driver has nothing more to hand back
+            if let Ok(Err(error)) = ready.try_io(|file| self.drain(file.get_ref())) {
+                return Poll::Ready(Err(error));
+            }
+
+            drop(ready);
+            readable.set(self.file.readable());
+        })
+        .await
+    }
+
+    /// Reads every finished command, until the driver would block.
+    fn drain(&self, file: &FileDescriptor) -> io::Result<()> {
+        loop {
+            let mut slots = lock(&self.slots);
+            let AsyncResponse {
+                pack_id, response, ..
+            } = read_response(file.raw())?;
+
+            slots.complete(pack_id, response);
+        }
+    }
+}
+
+impl Slots {
+    /// Pack ids wrap around before they turn negative.
+    fn next_pack_id(&mut self) -> i32 {
+        let pack_id = self.next_pack_id;
+        self.next_pack_id = self.next_pack_id.wrapping_add(1) & i32::MAX;
+        pack_id
+    }
+
+    fn insert(&mut self, pack_id: i32, buffers: Box<dyn Any + Send>) {
+        self.slots.insert(
+            pack_id,
+            Slot {
+                buffers,
+                response: None,
+                waker: None,
+                abandoned: false,
+            },
+        );
+    }
+
+    /// The buffers and response of `pack_id` once the driver handed it back, `waker` is woken
+    /// when it does otherwise.
+    fn take(
+        &mut self,
+        pack_id: i32,
+        waker: &Waker,
+    ) -> Option<(Box<dyn Any + Send>, TransportResponse)> {
+        let slot = self
+            .slots
+            .get_mut(&pack_id)
+            .expect("slot of an outstanding command");
+        match slot.response {
+            Some(response) => {
+                let slot = self.slots.remove(&pack_id).unwrap();
+                Some((slot.buffers, response))
+            }
+            None => {
+                slot.waker = Some(waker.clone());
+                None
+            }
+        }
+    }
+
+    /// Hands a response read from the driver to its command, the buffers of an abandoned one
+    /// are freed.
+    fn complete(&mut self, pack_id: i32, response: TransportResponse) {
+        match self.slots.get_mut(&pack_id) {
+            Some(slot) if slot.abandoned => {
+                self.slots.remove(&pack_id);
+            }
+            Some(slot) => {
+                slot.response = Some(response);
+                if let Some(waker) = slot.waker.take() {
+                    waker.wake();
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// The future of `pack_id` was dropped, its buffers are kept until the driver hands the
+    /// command back.
+    fn abandon(&mut self, pack_id: i32) {
+        match self.slots.get_mut(&pack_id) {
+            Some(slot) if slot.response.is_some() => {
+                self.slots.remove(&pack_id);
+            }
+            Some(slot) => {
+                slot.abandoned = true;
+                slot.waker = None;
+            }
+            None => {}
+        }
+    }
+}
+
+struct AbandonOnDrop<'a> {
+    slots: &'a Mutex<Slots>,
+    pack_id: i32,
+    armed: bool,
+}
+
+impl Drop for AbandonOnDrop<'_> {
+    fn drop(&mut self) {
+        if self.armed {
+            lock(self.slots).abandon(self.pack_id);
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        task::{Context, Wake},
+    };
+
+    use super::*;
+
+    #[test]
+    fn not_sg_device_test() {
+        assert!(AsyncScsi::new("/dev/null").is_err());
+        assert!(AsyncScsi::new("Cargo.toml").is_err());
+    }
+
+    /// Counts how often it was woken.
+    #[derive(Default)]
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn response(residual: usize) -> TransportResponse {
+        TransportResponse {
+            residual,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn slots_test() {
+        let mut slots = Slots::default();
+        let counter = Arc::new(CountingWaker::default());
+        let waker = Waker::from(counter.clone());
+
+        let first = slots.next_pack_id();
+        slots.insert(first, Box::new(1u8));
+        let second = slots.next_pack_id();
+        slots.insert(second, Box::new(2u8));
+        assert_ne!(first, second);
+        assert!(slots.take(first, &waker).is_none());
+        assert!(slots.take(second, &waker).is_none());
+
+        // completions come back in any order, unknown pack ids are ignored
+        slots.complete(second, response(2));
+        slots.complete(i32::MAX, response(0));
+        assert_eq!(counter.0.load(Ordering::Relaxed), 1);
+        let (buffers, completed) = slots.take(second, &waker).unwrap();
+        assert_eq!(buffers.downcast_ref::<u8>(), Some(&2));
+        assert_eq!(completed.residual, 2);
+
+        slots.complete(first, response(1));
+        assert_eq!(counter.0.load(Ordering::Relaxed), 2);
+        let (buffers, _) = slots.take(first, &waker).unwrap();
+        assert_eq!(buffers.downcast_ref::<u8>(), Some(&1));
+        assert!(slots.slots.is_empty());
+
+        slots.next_pack_id = i32::MAX;
+        assert_eq!(slots.next_pack_id(), i32::MAX);
+        assert_eq!(slots.next_pack_id(), 0);
+    }
+
+    #[test]
+    fn dropped_future_test() {
+        let slots = Mutex::new(Slots::default());
+        let buffers = Arc::new(());
+        let pack_id = {
+            let mut slots = lock(&slots);
+            let pack_id = slots.next_pack_id();
+            slots.insert(pack_id, Box::new(buffers.clone()));
+            pack_id
+        };
+
+        let mut future = Box::pin(async {
+            let _abandon = AbandonOnDrop {
+                slots: &slots,
+                pack_id,
+                armed: true,
+            };
+            poll_fn(|cx| match lock(&slots).take(pack_id, cx.waker()) {
+                Some(completed) => Poll::Ready(completed),
+                None => Poll::Pending,
+            })
+            .await
+        });
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(future.as_mut().poll(&mut cx).is_pending());
+        drop(future);
+
+        // the driver may still write into the buffers
+        assert!(lock(&slots).slots[&pack_id].abandoned);
+        assert_eq!(Arc::strong_count(&buffers), 2);
+
+        lock(&slots).complete(pack_id, response(0));
+        assert!(lock(&slots).slots.is_empty());
+        assert_eq!(Arc::strong_count(&buffers), 1);
+
+        // dropped after the driver handed it back, but before it was taken
+        let pack_id = lock(&slots).next_pack_id();
+        lock(&slots).insert(pack_id, Box::new(buffers.clone()));
+        lock(&slots).complete(pack_id, response(0));
+        drop(AbandonOnDrop {
+            slots: &slots,
+            pack_id,
+            armed: true,
+        });
+        assert!(lock(&slots).slots.is_empty());
+        assert_eq!(Arc::strong_count(&buffers), 1);
+    }
+}
@@ -73,10 +73,13 @@ impl IdentifySatResponse {
         &self.bfr
     }
 
-    /// Helper: read a 16‑bit IDENTIFY word (little‑endian)
+    /// Helper: read a 16‑bit IDENTIFY word (little‑endian), 0 if the response is too short
     fn word(&self, index: usize) -> u16 {
         let offset = index * 2;
-        u16::from_le_bytes([self.bfr[offset], self.bfr[offset + 1]])
+        match self.bfr.get(offset..offset + 2) {
+            Some(word) => u16::from_le_bytes([word[0], word[1]]),
+            None => 0,
+        }
     }
 
     /// Decode ATA string fields (word‑swapped ASCII)
@@ -232,20 +235,24 @@ impl IdentifySatResponse {
     }
 
     pub fn capacity_lba(&self) -> u64 {
-        let mut buf = [0u8; 8];
+        let words = |first: usize, count: usize| {
+            (0..count).fold(0u64, |value, i| {
+                value | (self.word(first + i) as u64) << (16 * i)
+            })
+        };
+
         if self.cap_lba48() {
-            buf.copy_from_slice(&self.bfr[100 * 2..100 * 2 + 8]);
-            u64::from_le_bytes(buf)
+            words(100, 4)
         } else if self.cap_lba28() {
-            buf[..4].copy_from_slice(&self.bfr[60 * 2..60 * 2 + 4]);
-            u64::from_le_bytes(buf)
+            words(60, 2)
         } else {
             0
         }
     }
 
     pub fn capacity_bytes(&self) -> u64 {
-        self.capacity_lba() * self.logical_sector_size()
+        self.capacity_lba()
+            .saturating_mul(self.logical_sector_size())
     }
 
     /// Logical sector size in bytes
@@ -258,7 +265,7 @@ impl IdentifySatResponse {
         // Bits 11:0 = exponent offset
         let exponent = (w106 & 0x0FFF) as u32;
         // Logical size = 2^(exponent + 9)
-        1u64 << (exponent + 9)
+        1u64.checked_shl(exponent + 9).unwrap_or(0)
     }
 
     /// Physical sector size in bytes
@@ -271,7 +278,7 @@ impl IdentifySatResponse {
         }
         // Bits 11:0 = number of logical sectors per physical sector (power of two)
         let exponent = (w106 & 0x0FFF) as u32;
-        logical * (1u64 << exponent)
+        logical.saturating_mul(1u64.checked_shl(exponent).unwrap_or(0))
     }
 }
 
@@ -281,3 +288,31 @@ impl ScsiSat<'_> {
         IdentifySatCommand::new(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncated_response_test() {
+        let empty = IdentifySatResponse::new(vec![]);
+        assert_eq!(empty.firmware_rev().len(), 8);
+        assert_eq!(empty.capacity_bytes(), 0);
+        assert_eq!(empty.security_state(), SecurityState::Unknown);
+
+        // LBA48 with 0x1000 logical blocks, cut off before word 106
+        let mut bfr = vec![0; 212];
+        bfr[166..168].copy_from_slice(&(1u16 << 10).to_le_bytes());
+        bfr[200..202].copy_from_slice(&0x1000u16.to_le_bytes());
+        let response = IdentifySatResponse::new(bfr.clone());
+        assert_eq!(response.capacity_lba(), 0x1000);
+        assert_eq!(response.capacity_bytes(), 0x1000 * 512);
+
+        // logical and physical sector size exponents that overflow
+        bfr.extend_from_slice(&0xCFFFu16.to_le_bytes());
+        let response = IdentifySatResponse::new(bfr);
+        assert_eq!(response.logical_sector_size(), 0);
+        assert_eq!(response.physical_sector_size(), 0);
+        assert_eq!(response.capacity_bytes(), 0);
+    }
+}
@@ -3,7 +3,7 @@
 use std::marker::PhantomData;
 
 use crate::{
-    command::sense::SenseData,
+    command::sense::{AtaStatusReturn, Descriptor, SenseData, SenseKey},
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::{ResultData, Status},
     Command, DataDirection, Scsi,
@@ -28,6 +28,21 @@ impl<T> SatResultData<T> {
             sense: self.sense,
         }
     }
+
+    /// The ATA registers returned for a `ck_cond` command.
+    pub fn ata_status_return(&self) -> Option<&AtaStatusReturn> {
+        let SenseData::Descriptor(sense) = &self.sense else {
+            return None;
+        };
+
+        sense
+            .descriptors
+            .iter()
+            .find_map(|descriptor| match descriptor {
+                Descriptor::AtaStatusReturn(registers) => Some(registers),
+                _ => None,
+            })
+    }
 }
 
 pub type SatResult<T> = crate::Result<SatResultData<T>>;
@@ -197,36 +212,39 @@ pub(crate) struct SatCommand<C, D: SatDirection> {
 impl<C: Copy, D: SatDirection> SatCommand<C, D> {
     /// Check for common error conditions
     /// Don't regard SENSE as error, if it was explicitly requested by the SAT command (ck_cond)
+    /// or only reports a RECOVERED ERROR, like [`ResultData::check_common_error`]
     fn check_common_error(&self, result: &ResultData<VecBufferWrapper>) -> crate::Result<()> {
-        let mut err = String::new();
+        let mut is_error = false;
+        let is_sense_expected =
+            self.ck_cond || result.sense_buffer.sense_key() == Some(SenseKey::RecoveredError);
 
         #[cfg(target_os = "linux")]
         {
             use crate::os::linux::DriverStatus;
 
             if !matches!(result.host_status, crate::os::linux::HostStatus::Ok) {
-                err.push_str(&format!("host status: {:?}. ", result.host_status));
+                is_error = true;
             }
 
-            match (self.ck_cond, result.driver_status) {
+            match (is_sense_expected, result.driver_status) {
                 (_, _) if result.driver_status.is_empty() => {}
                 (true, DriverStatus::SENSE) => {}
-                _ => err.push_str(&format!("driver status: {:?}. ", result.driver_status)),
+                _ => is_error = true,
             }
         }
 
         match result.status {
             Status::Good => {}
-            Status::CheckCondition if self.ck_cond => {}
-            _ => err.push_str(&format!("Status: {:?}. ", result.status)),
+            Status::CheckCondition if is_sense_expected => {}
+            _ => is_error = true,
         }
 
-        if result.transfered_sense_length != 0 && !self.ck_cond {
-            err.push_str(&format!("Sense data: {:02X?}", result.sense_buffer));
+        if result.transfered_sense_length != 0 && !is_sense_expected {
+            is_error = true;
         }
 
-        if !err.is_empty() {
-            return Err(crate::Error::Other(err));
+        if is_error {
+            return Err(crate::Error::CheckCondition(result.command_error()));
         }
 
         Ok(())
@@ -2,7 +2,11 @@
 
 use modular_bitfield_msb::prelude::*;
 
-use crate::{command::bitfield_bound_check, result_data::ResultData, Command, DataDirection, Scsi};
+use crate::{
+    command::{bitfield_bound_check, issue_with_info},
+    result_data::ResultData,
+    Command, DataDirection, Scsi,
+};
 
 #[derive(Clone, Debug)]
 pub struct BackgroundControlCommand<'a> {
@@ -65,6 +69,8 @@ impl<'a> BackgroundControlCommand<'a> {
         };
         self.interface.issue(&temp)
     }
+
+    issue_with_info!(issue_with_info, issue(), ());
 }
 
 impl Scsi {
@@ -2,7 +2,7 @@
 
 use modular_bitfield_msb::prelude::*;
 
-use crate::{result_data::ResultData, Command, DataDirection, Scsi};
+use crate::{command::issue_with_info, result_data::ResultData, Command, DataDirection, Scsi};
 
 #[derive(Clone, Debug)]
 pub struct CloseZoneCommand<'a> {
@@ -48,6 +48,8 @@ impl<'a> CloseZoneCommand<'a> {
             timeout: self.timeout,
         })
     }
+
+    issue_with_info!(issue_with_info, issue(), ());
 }
 
 impl Scsi {
@@ -5,7 +5,7 @@ use std::mem::size_of_val;
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
+    command::{bitfield_bound_check, issue_with_info},
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -200,6 +200,8 @@ impl<'a> FormatUnitCommand<'a> {
         };
         self.interface.issue(&temp)
     }
+
+    issue_with_info!(issue_with_info, issue(), ());
 }
 
 impl<'a> ParameterBuilder<'a> {
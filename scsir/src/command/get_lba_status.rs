@@ -5,6 +5,7 @@ use std::mem::size_of;
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
+    command::issue_with_info,
     data_wrapper::{AnyType, FlexibleStruct},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -96,6 +97,8 @@ impl<'a> GetLbaStatusCommand<'a> {
 
         self.interface.issue(&temp)
     }
+
+    issue_with_info!(issue_with_info, issue(), CommandResult);
 }
 
 impl Scsi {
@@ -177,7 +180,7 @@ impl Command for ThisCommand {
 
         let data = result.data;
         let length = unsafe { data.body_as_ref() }.parameter_data_length();
-        let length = (length as usize - size_of::<u32>()) / size_of::<Descriptor>();
+        let length = (length as usize).saturating_sub(size_of::<u32>()) / size_of::<Descriptor>();
 
         let mut lba_status_descriptors = vec![];
 
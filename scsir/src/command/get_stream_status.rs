@@ -5,6 +5,7 @@ use std::mem::size_of;
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
+    command::issue_with_info,
     data_wrapper::{AnyType, FlexibleStruct},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -81,6 +82,8 @@ impl<'a> GetStreamStatusCommand<'a> {
 
         self.interface.issue(&temp)
     }
+
+    issue_with_info!(issue_with_info, issue(), CommandResult);
 }
 
 impl Scsi {
@@ -164,7 +167,7 @@ impl Command for ThisCommand {
 
         let data = result.data;
         let length = unsafe { data.body_as_ref() }.parameter_data_length();
-        let length = (length as usize - size_of::<u64>()) / size_of::<Descriptor>();
+        let length = (length as usize).saturating_sub(size_of::<u64>()) / size_of::<Descriptor>();
 
         let mut stream_identifiers = vec![];
         for item in unsafe { &data.elements_as_slice()[..usize::min(length, data.length())] } {
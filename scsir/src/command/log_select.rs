@@ -5,7 +5,7 @@ use std::{mem::size_of, slice};
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
+    command::{bitfield_bound_check, issue_with_info},
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -97,6 +97,8 @@ impl<'a> LogSelectCommand<'a> {
         Ok(())
     }
 
+    issue_with_info!(issue_with_info, issue(), ());
+
     pub fn issue_generic<T: Copy>(&mut self, parameter: T) -> crate::Result<()> {
         let u8_slice: &[u8] =
             unsafe { slice::from_raw_parts(&parameter as *const _ as *const _, size_of::<T>()) };
@@ -8,7 +8,7 @@ use std::{
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
+    command::{bitfield_bound_check, issue_with_info},
     data_wrapper::{AnyType, FlexibleStruct},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -83,6 +83,8 @@ impl<'a> LogSenseCommand<'a> {
         unsafe { Ok(result.elements_as_slice().to_vec()) }
     }
 
+    issue_with_info!(issue_with_info, issue(), Vec<u8>);
+
     pub fn issue_generic<Body: Copy, Element: Copy>(
         &mut self,
         element_length: usize,
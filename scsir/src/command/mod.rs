@@ -11,6 +11,7 @@ pub mod log_select;
 pub mod log_sense;
 pub mod mode_select;
 pub mod mode_sense;
+pub mod open_zone;
 pub mod persistent_reserve_in;
 pub mod persistent_reserve_out;
 pub mod read;
@@ -20,13 +21,14 @@ pub mod read_defect_data;
 pub mod read_long;
 pub mod reassign_blocks;
 pub mod receive_diagnostic_results;
-pub mod report_zones;
 pub mod report_identifying_information;
 pub mod report_luns;
 pub mod report_supported_operation_codes;
 pub mod report_supported_task_management_functions;
 pub mod report_timestamp;
+pub mod report_zones;
 pub mod request_sense;
+pub mod reset_write_pointer;
 pub mod rezero_unit;
 pub mod sanitize;
 pub mod security_protocol_in;
@@ -34,7 +36,6 @@ pub mod security_protocol_out;
 pub mod seek;
 pub mod send_diagnostic;
 pub mod sense;
-pub mod reset_write_pointer;
 pub mod set_identifying_information;
 pub mod set_timestamp;
 pub mod shortcut;
@@ -51,11 +52,11 @@ pub mod write_buffer;
 pub mod write_long;
 pub mod write_same;
 pub mod write_stream;
-pub mod open_zone;
+pub mod xd_write_read;
 
 use std::{borrow::BorrowMut, mem::size_of, time::Duration};
 
-use crate::{result_data::ResultData, DataDirection};
+use crate::{result_data::ResultData, DataDirection, Scsi};
 
 pub trait Command {
     type CommandBuffer;
@@ -77,6 +78,12 @@ pub trait Command {
         size_of::<Self::DataBuffer>() as u32
     }
 
+    /// data-out buffer of a bidirectional command, `data` is used as the data-in buffer then.
+    /// A `ToDevice` command can also lend its data-out buffer here instead of copying it into `data`
+    fn data_out(&self) -> &[u8] {
+        &[]
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType;
 }
 
@@ -106,3 +113,86 @@ macro_rules! bitfield_bound_check {
 }
 
 pub(crate) use bitfield_bound_check;
+
+/// Defines `$name`, which calls `$issue` and also returns how the command completed.
+macro_rules! issue_with_info {
+    ( $name:ident, $issue:ident ( $( $arg:ident: $arg_type:ty ),* ), $result:ty ) => {
+        #[doc = concat!(
+            "Like [`", stringify!($issue), "`](Self::", stringify!($issue),
+            "), but also returns how the command completed."
+        )]
+        pub fn $name(
+            &mut self,
+            $( $arg: $arg_type ),*
+        ) -> crate::Result<($result, crate::CompletionInfo)> {
+            let interface = self.interface;
+            interface.with_info(|| self.$issue($( $arg ),*))
+        }
+    };
+}
+
+pub(crate) use issue_with_info;
+
+/// A CDB a command builder can be issued with, see `select_cdb_size`.
+pub(crate) struct CdbCandidate {
+    pub size: u8,
+    pub operation_code: u8,
+    pub service_action: Option<u16>,
+    /// Whether the fields set fit into this CDB.
+    pub fits: crate::Result<()>,
+}
+
+/// Picks the smallest CDB the fields fit into among the ones the device supports, see
+/// [`DeviceProfile::supports`](crate::DeviceProfile::supports). If the device doesn't report
+/// its supported operations, only 10, 16 and 32 byte CDBs are picked, 6 and 12 byte ones are
+/// rarely implemented by disks.
+pub(crate) fn select_cdb_size(
+    interface: &Scsi,
+    name: &str,
+    candidates: Vec<CdbCandidate>,
+) -> crate::Result<u8> {
+    let profile = interface.device_profile()?;
+
+    let mut last_error = None;
+    let mut unsupported = vec![];
+    for candidate in candidates {
+        if let Err(e) = candidate.fits {
+            last_error = Some(e);
+            continue;
+        }
+
+        let supported = profile
+            .supports(candidate.operation_code, candidate.service_action)
+            .unwrap_or(matches!(candidate.size, 10 | 16 | 32));
+        if supported {
+            return Ok(candidate.size);
+        }
+        unsupported.push(candidate.size.to_string());
+    }
+
+    match (unsupported.is_empty(), last_error) {
+        (true, Some(e)) => Err(e),
+        _ => Err(crate::Error::BadArgument(format!(
+            "the device doesn't support {} ({}), the only sizes the fields set fit into.",
+            name,
+            unsupported.join(", ")
+        ))),
+    }
+}
+
+/// The logical block length reported by READ CAPACITY, rejected if it is 0, as transfer
+/// lengths are divided by it.
+pub(crate) fn device_logical_block_size(interface: &Scsi) -> crate::Result<u32> {
+    let length = match interface.device_profile()?.logical_block_length {
+        Some(length) => length,
+        None => interface.read_capacity().issue_10()?.block_length_in_bytes,
+    };
+
+    if length == 0 {
+        return Err(crate::Error::Other(
+            "the device reported a logical block length of 0.".to_owned(),
+        ));
+    }
+
+    Ok(length)
+}
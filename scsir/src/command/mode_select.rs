@@ -3,7 +3,7 @@
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
+    command::{bitfield_bound_check, issue_with_info},
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -102,6 +102,8 @@ impl<'a> ModeSelectCommand<'a> {
         self.interface.issue(&temp)
     }
 
+    issue_with_info!(issue_6_with_info, issue_6(), ());
+
     pub fn issue_10(&mut self) -> crate::Result<()> {
         self.error_check(16, false)?;
 
@@ -118,6 +120,8 @@ impl<'a> ModeSelectCommand<'a> {
 
         self.interface.issue(&temp)
     }
+
+    issue_with_info!(issue_10_with_info, issue_10(), ());
 }
 
 impl Scsi {
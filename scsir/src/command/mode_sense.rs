@@ -3,7 +3,7 @@
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
+    command::{bitfield_bound_check, issue_with_info},
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -133,6 +133,8 @@ impl<'a> ModeSenseCommand<'a> {
         self.interface.issue(&temp)
     }
 
+    issue_with_info!(issue_6_with_info, issue_6(), Vec<u8>);
+
     pub fn issue_10(&mut self) -> crate::Result<Vec<u8>> {
         self.error_check(16, true)?;
 
@@ -154,6 +156,8 @@ impl<'a> ModeSenseCommand<'a> {
 
         self.interface.issue(&temp)
     }
+
+    issue_with_info!(issue_10_with_info, issue_10(), Vec<u8>);
 }
 
 impl Scsi {
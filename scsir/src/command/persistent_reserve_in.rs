@@ -5,7 +5,7 @@ use std::mem::size_of;
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::{bitfield_bound_check, get_array},
+    command::{bitfield_bound_check, get_array, issue_with_info},
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -125,6 +125,8 @@ impl<'a> PersistentReserveInCommand<'a> {
         };
         self.interface.issue(&temp)
     }
+
+    issue_with_info!(issue_with_info, issue(), CommandResult);
 }
 
 impl Scsi {
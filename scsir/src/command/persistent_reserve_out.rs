@@ -3,7 +3,7 @@
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
+    command::{bitfield_bound_check, issue_with_info},
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -112,6 +112,8 @@ impl<'a> PersistentReserveOutCommand<'a> {
 
         self.interface.issue(&temp)
     }
+
+    issue_with_info!(issue_with_info, issue(), ());
 }
 
 impl<'a> ParameterBuilder<'a> {
@@ -1,10 +1,15 @@
 #![allow(dead_code)]
 
+use std::io::IoSliceMut;
+
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
-    data_wrapper::{AnyType, VecBufferWrapper},
+    command::{
+        bitfield_bound_check, device_logical_block_size, issue_with_info, select_cdb_size,
+        CdbCandidate,
+    },
+    data_wrapper::{AnyType, SliceBufferWrapper, VecBufferWrapper},
     result_data::ResultData,
     Command, DataDirection, Scsi,
 };
@@ -29,6 +34,7 @@ pub struct ReadCommand<'a> {
     dld_1: bool,
     dld_2: bool,
     logical_block_size: u32,
+    logical_block_size_specified: bool,
 }
 
 #[derive(Clone, Copy)]
@@ -60,6 +66,7 @@ impl<'a> ReadCommand<'a> {
             dld_1: false,
             dld_2: false,
             logical_block_size: 512,
+            logical_block_size_specified: false,
         }
     }
 
@@ -145,8 +152,11 @@ impl<'a> ReadCommand<'a> {
         self
     }
 
+    /// 512 for the `issue_*` methods with a CDB size, the one reported by READ CAPACITY for
+    /// [`issue`](Self::issue) unless set.
     pub fn logical_block_size(&mut self, value: u32) -> &mut Self {
         self.logical_block_size = value;
+        self.logical_block_size_specified = true;
         self
     }
 
@@ -231,7 +241,113 @@ impl<'a> ReadCommand<'a> {
         }
     }
 
+    /// Issues the smallest READ command the fields set fit into and the device supports, see
+    /// [`Scsi::device_profile`].
+    pub fn issue(&mut self) -> crate::Result<Vec<u8>> {
+        match self.select_cdb_size()? {
+            6 => self.issue_6(),
+            10 => self.issue_10(),
+            12 => self.issue_12(),
+            16 => self.issue_16(),
+            _ => self.issue_32(),
+        }
+    }
+
+    issue_with_info!(issue_with_info, issue(), Vec<u8>);
+
+    /// Reads into `buffer` instead of a new `Vec`, returns the number of bytes transferred.
+    pub fn issue_into(&mut self, buffer: &mut [u8]) -> crate::Result<usize> {
+        match self.select_cdb_size()? {
+            6 => self.issue_6_into(buffer),
+            10 => self.issue_10_into(buffer),
+            12 => self.issue_12_into(buffer),
+            16 => self.issue_16_into(buffer),
+            _ => self.issue_32_into(buffer),
+        }
+    }
+
+    issue_with_info!(issue_into_with_info, issue_into(buffer: &mut [u8]), usize);
+
+    fn select_cdb_size(&mut self) -> crate::Result<u8> {
+        if !self.logical_block_size_specified {
+            self.logical_block_size = device_logical_block_size(self.interface)?;
+        }
+
+        // a transfer length of 0 stands for 256 logical blocks in READ (6)
+        let fits_6 = match self.transfer_length {
+            0 => Err(crate::Error::BadArgument(
+                "READ (6) can't transfer 0 logical blocks.".to_owned(),
+            )),
+            _ => self.command_6().map(drop),
+        };
+
+        select_cdb_size(
+            self.interface,
+            "READ",
+            vec![
+                CdbCandidate {
+                    size: 6,
+                    operation_code: OPERATION_CODE_6,
+                    service_action: None,
+                    fits: fits_6,
+                },
+                CdbCandidate {
+                    size: 10,
+                    operation_code: OPERATION_CODE_10,
+                    service_action: None,
+                    fits: self.command_10().map(drop),
+                },
+                CdbCandidate {
+                    size: 12,
+                    operation_code: OPERATION_CODE_12,
+                    service_action: None,
+                    fits: self.command_12().map(drop),
+                },
+                CdbCandidate {
+                    size: 16,
+                    operation_code: OPERATION_CODE_16,
+                    service_action: None,
+                    fits: self.command_16().map(drop),
+                },
+                CdbCandidate {
+                    size: 32,
+                    operation_code: OPERATION_CODE_32,
+                    service_action: Some(SERVICE_ACTION_32),
+                    fits: self.command_32().map(drop),
+                },
+            ],
+        )
+    }
+
     pub fn issue_6(&mut self) -> crate::Result<Vec<u8>> {
+        let command = self.command_6()?;
+        self.interface.issue(&command)
+    }
+
+    issue_with_info!(issue_6_with_info, issue_6(), Vec<u8>);
+
+    /// Reads into `buffer` instead of a new `Vec`, returns the number of bytes transferred.
+    pub fn issue_6_into(&mut self, buffer: &mut [u8]) -> crate::Result<usize> {
+        let command = self.command_6()?.into_buffer(buffer)?;
+        self.interface.issue(&command)
+    }
+
+    issue_with_info!(issue_6_into_with_info, issue_6_into(buffer: &mut [u8]), usize);
+
+    /// Scatters the data over `buffers`, returns the number of bytes transferred.
+    pub fn issue_6_vectored(&mut self, buffers: &mut [IoSliceMut]) -> crate::Result<usize> {
+        let command = self.command_6()?.into_vectored(buffers)?;
+        self.interface.issue_vectored(&command, buffers)
+    }
+
+    issue_with_info!(issue_6_vectored_with_info, issue_6_vectored(buffers: &mut [IoSliceMut]), usize);
+
+    /// Builds the command without issuing it, e.g. to submit it to a `CommandQueue`.
+    pub fn prepare_6(&self) -> crate::Result<impl Command<ReturnType = crate::Result<Vec<u8>>>> {
+        self.command_6()
+    }
+
+    fn command_6(&self) -> crate::Result<ThisCommand<CommandBuffer6>> {
         self.common_check(0, 21, 8, ReadCheckMode::Read6)?;
 
         let allocation_blocks = self.effective_transfer_length(ReadCheckMode::Read6);
@@ -244,7 +360,7 @@ impl<'a> ReadCommand<'a> {
 
         let allocation_length = self.logical_block_size.saturating_mul(allocation_blocks);
 
-        self.interface.issue(&ThisCommand {
+        Ok(ThisCommand {
             command_buffer,
             allocation_length,
             timeout: self.timeout,
@@ -252,6 +368,34 @@ impl<'a> ReadCommand<'a> {
     }
 
     pub fn issue_10(&mut self) -> crate::Result<Vec<u8>> {
+        let command = self.command_10()?;
+        self.interface.issue(&command)
+    }
+
+    issue_with_info!(issue_10_with_info, issue_10(), Vec<u8>);
+
+    /// Reads into `buffer` instead of a new `Vec`, returns the number of bytes transferred.
+    pub fn issue_10_into(&mut self, buffer: &mut [u8]) -> crate::Result<usize> {
+        let command = self.command_10()?.into_buffer(buffer)?;
+        self.interface.issue(&command)
+    }
+
+    issue_with_info!(issue_10_into_with_info, issue_10_into(buffer: &mut [u8]), usize);
+
+    /// Scatters the data over `buffers`, returns the number of bytes transferred.
+    pub fn issue_10_vectored(&mut self, buffers: &mut [IoSliceMut]) -> crate::Result<usize> {
+        let command = self.command_10()?.into_vectored(buffers)?;
+        self.interface.issue_vectored(&command, buffers)
+    }
+
+    issue_with_info!(issue_10_vectored_with_info, issue_10_vectored(buffers: &mut [IoSliceMut]), usize);
+
+    /// Builds the command without issuing it, e.g. to submit it to a `CommandQueue`.
+    pub fn prepare_10(&self) -> crate::Result<impl Command<ReturnType = crate::Result<Vec<u8>>>> {
+        self.command_10()
+    }
+
+    fn command_10(&self) -> crate::Result<ThisCommand<CommandBuffer10>> {
         self.common_check(5, 32, 16, ReadCheckMode::Standard)?;
 
         let command_buffer = CommandBuffer10::new()
@@ -267,7 +411,7 @@ impl<'a> ReadCommand<'a> {
 
         let allocation_length = self.logical_block_size.saturating_mul(self.transfer_length);
 
-        self.interface.issue(&ThisCommand {
+        Ok(ThisCommand {
             command_buffer,
             allocation_length,
             timeout: self.timeout,
@@ -275,6 +419,34 @@ impl<'a> ReadCommand<'a> {
     }
 
     pub fn issue_12(&mut self) -> crate::Result<Vec<u8>> {
+        let command = self.command_12()?;
+        self.interface.issue(&command)
+    }
+
+    issue_with_info!(issue_12_with_info, issue_12(), Vec<u8>);
+
+    /// Reads into `buffer` instead of a new `Vec`, returns the number of bytes transferred.
+    pub fn issue_12_into(&mut self, buffer: &mut [u8]) -> crate::Result<usize> {
+        let command = self.command_12()?.into_buffer(buffer)?;
+        self.interface.issue(&command)
+    }
+
+    issue_with_info!(issue_12_into_with_info, issue_12_into(buffer: &mut [u8]), usize);
+
+    /// Scatters the data over `buffers`, returns the number of bytes transferred.
+    pub fn issue_12_vectored(&mut self, buffers: &mut [IoSliceMut]) -> crate::Result<usize> {
+        let command = self.command_12()?.into_vectored(buffers)?;
+        self.interface.issue_vectored(&command, buffers)
+    }
+
+    issue_with_info!(issue_12_vectored_with_info, issue_12_vectored(buffers: &mut [IoSliceMut]), usize);
+
+    /// Builds the command without issuing it, e.g. to submit it to a `CommandQueue`.
+    pub fn prepare_12(&self) -> crate::Result<impl Command<ReturnType = crate::Result<Vec<u8>>>> {
+        self.command_12()
+    }
+
+    fn command_12(&self) -> crate::Result<ThisCommand<CommandBuffer12>> {
         self.common_check(5, 32, 32, ReadCheckMode::Standard)?;
 
         let command_buffer = CommandBuffer12::new()
@@ -290,7 +462,7 @@ impl<'a> ReadCommand<'a> {
 
         let allocation_length = self.logical_block_size.saturating_mul(self.transfer_length);
 
-        self.interface.issue(&ThisCommand {
+        Ok(ThisCommand {
             command_buffer,
             allocation_length,
             timeout: self.timeout,
@@ -298,6 +470,34 @@ impl<'a> ReadCommand<'a> {
     }
 
     pub fn issue_16(&mut self) -> crate::Result<Vec<u8>> {
+        let command = self.command_16()?;
+        self.interface.issue(&command)
+    }
+
+    issue_with_info!(issue_16_with_info, issue_16(), Vec<u8>);
+
+    /// Reads into `buffer` instead of a new `Vec`, returns the number of bytes transferred.
+    pub fn issue_16_into(&mut self, buffer: &mut [u8]) -> crate::Result<usize> {
+        let command = self.command_16()?.into_buffer(buffer)?;
+        self.interface.issue(&command)
+    }
+
+    issue_with_info!(issue_16_into_with_info, issue_16_into(buffer: &mut [u8]), usize);
+
+    /// Scatters the data over `buffers`, returns the number of bytes transferred.
+    pub fn issue_16_vectored(&mut self, buffers: &mut [IoSliceMut]) -> crate::Result<usize> {
+        let command = self.command_16()?.into_vectored(buffers)?;
+        self.interface.issue_vectored(&command, buffers)
+    }
+
+    issue_with_info!(issue_16_vectored_with_info, issue_16_vectored(buffers: &mut [IoSliceMut]), usize);
+
+    /// Builds the command without issuing it, e.g. to submit it to a `CommandQueue`.
+    pub fn prepare_16(&self) -> crate::Result<impl Command<ReturnType = crate::Result<Vec<u8>>>> {
+        self.command_16()
+    }
+
+    fn command_16(&self) -> crate::Result<ThisCommand<CommandBuffer16>> {
         self.common_check(6, 64, 32, ReadCheckMode::WithDld)?;
 
         let command_buffer = CommandBuffer16::new()
@@ -316,7 +516,7 @@ impl<'a> ReadCommand<'a> {
 
         let allocation_length = self.logical_block_size.saturating_mul(self.transfer_length);
 
-        self.interface.issue(&ThisCommand {
+        Ok(ThisCommand {
             command_buffer,
             allocation_length,
             timeout: self.timeout,
@@ -324,6 +524,34 @@ impl<'a> ReadCommand<'a> {
     }
 
     pub fn issue_32(&mut self) -> crate::Result<Vec<u8>> {
+        let command = self.command_32()?;
+        self.interface.issue(&command)
+    }
+
+    issue_with_info!(issue_32_with_info, issue_32(), Vec<u8>);
+
+    /// Reads into `buffer` instead of a new `Vec`, returns the number of bytes transferred.
+    pub fn issue_32_into(&mut self, buffer: &mut [u8]) -> crate::Result<usize> {
+        let command = self.command_32()?.into_buffer(buffer)?;
+        self.interface.issue(&command)
+    }
+
+    issue_with_info!(issue_32_into_with_info, issue_32_into(buffer: &mut [u8]), usize);
+
+    /// Scatters the data over `buffers`, returns the number of bytes transferred.
+    pub fn issue_32_vectored(&mut self, buffers: &mut [IoSliceMut]) -> crate::Result<usize> {
+        let command = self.command_32()?.into_vectored(buffers)?;
+        self.interface.issue_vectored(&command, buffers)
+    }
+
+    issue_with_info!(issue_32_vectored_with_info, issue_32_vectored(buffers: &mut [IoSliceMut]), usize);
+
+    /// Builds the command without issuing it, e.g. to submit it to a `CommandQueue`.
+    pub fn prepare_32(&self) -> crate::Result<impl Command<ReturnType = crate::Result<Vec<u8>>>> {
+        self.command_32()
+    }
+
+    fn command_32(&self) -> crate::Result<ThisCommand<CommandBuffer32>> {
         self.common_check(5, 64, 32, ReadCheckMode::WithExpectedTags)?;
 
         let command_buffer = CommandBuffer32::new()
@@ -348,7 +576,7 @@ impl<'a> ReadCommand<'a> {
 
         let allocation_length = self.logical_block_size.saturating_mul(self.transfer_length);
 
-        self.interface.issue(&ThisCommand {
+        Ok(ThisCommand {
             command_buffer,
             allocation_length,
             timeout: self.timeout,
@@ -459,6 +687,37 @@ struct ThisCommand<C> {
     timeout: Option<std::time::Duration>,
 }
 
+impl<C> ThisCommand<C> {
+    fn into_buffer(self, buffer: &mut [u8]) -> crate::Result<InPlaceCommand<C>> {
+        self.check_buffer_length(buffer.len())?;
+
+        Ok(InPlaceCommand {
+            inner: self,
+            data_buffer: SliceBufferWrapper::new(buffer),
+        })
+    }
+
+    fn into_vectored(self, buffers: &[IoSliceMut]) -> crate::Result<InPlaceCommand<C>> {
+        self.check_buffer_length(buffers.iter().map(|buffer| buffer.len()).sum())?;
+
+        Ok(InPlaceCommand {
+            inner: self,
+            data_buffer: SliceBufferWrapper::new(&mut []),
+        })
+    }
+
+    fn check_buffer_length(&self, length: usize) -> crate::Result<()> {
+        if length < self.allocation_length as usize {
+            return Err(crate::Error::BadArgument(format!(
+                "buffer is too small, {} bytes are needed, but {} were provided.",
+                self.allocation_length, length
+            )));
+        }
+
+        Ok(())
+    }
+}
+
 impl<C: Copy> Command for ThisCommand<C> {
     type CommandBuffer = C;
 
@@ -496,6 +755,49 @@ impl<C: Copy> Command for ThisCommand<C> {
     }
 }
 
+/// Transfers straight into the caller's memory.
+struct InPlaceCommand<C> {
+    inner: ThisCommand<C>,
+    data_buffer: SliceBufferWrapper,
+}
+
+impl<C: Copy> Command for InPlaceCommand<C> {
+    type CommandBuffer = C;
+
+    type DataBuffer = AnyType;
+
+    type DataBufferWrapper = SliceBufferWrapper;
+
+    type ReturnType = crate::Result<usize>;
+
+    fn direction(&self) -> DataDirection {
+        DataDirection::FromDevice
+    }
+
+    fn command(&self) -> Self::CommandBuffer {
+        self.inner.command_buffer
+    }
+
+    fn timeout_override(&self) -> Option<std::time::Duration> {
+        self.inner.timeout
+    }
+
+    fn data(&self) -> Self::DataBufferWrapper {
+        self.data_buffer
+    }
+
+    fn data_size(&self) -> u32 {
+        self.inner.allocation_length
+    }
+
+    fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
+        result.check_ioctl_error()?;
+        result.check_common_error()?;
+
+        Ok(result.transfered_data_length())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -539,4 +841,58 @@ mod tests {
             concat!("Size of: ", stringify!(CommandBuffer32))
         );
     }
+
+    #[test]
+    fn cdb_selection_test() {
+        use crate::{emulator::EmulatedDisk, trace::TraceRecorder, SupportedOperation};
+
+        let recorder = TraceRecorder::new();
+        let mut scsi = Scsi::with_transport("disk", EmulatedDisk::new(4096, 64).unwrap());
+        scsi.add_interceptor(recorder.clone());
+        let last_operation_code = || recorder.trace().entries.last().unwrap().command[0];
+
+        // the emulator doesn't report its supported operations
+        let data = scsi.read().transfer_length(2).issue().unwrap();
+        assert_eq!(data.len(), 2 * 4096);
+        assert_eq!(last_operation_code(), OPERATION_CODE_10);
+        scsi.read().transfer_length(1).dld_0(true).issue().unwrap();
+        assert_eq!(last_operation_code(), OPERATION_CODE_16);
+        scsi.read()
+            .transfer_length(1)
+            .expected_logical_block_application_tag(1)
+            .issue()
+            .unwrap();
+        assert_eq!(last_operation_code(), OPERATION_CODE_32);
+
+        let mut profile = (*scsi.device_profile().unwrap()).clone();
+        profile.supported_operations = Some(
+            [OPERATION_CODE_6, OPERATION_CODE_16]
+                .into_iter()
+                .map(|operation_code| SupportedOperation {
+                    operation_code,
+                    service_action: None,
+                    cdb_length: 0,
+                })
+                .collect(),
+        );
+        scsi.set_device_profile(Some(profile));
+
+        let mut buffer = [0; 4096];
+        let length = scsi.read().transfer_length(1).issue_into(&mut buffer);
+        assert_eq!(length.unwrap(), 4096);
+        assert_eq!(last_operation_code(), OPERATION_CODE_6);
+        scsi.read()
+            .transfer_length(1)
+            .force_unit_access(true)
+            .issue()
+            .unwrap();
+        assert_eq!(last_operation_code(), OPERATION_CODE_16);
+        assert!(matches!(
+            scsi.read()
+                .transfer_length(1)
+                .expected_logical_block_application_tag(1)
+                .issue(),
+            Err(crate::Error::BadArgument(_))
+        ));
+    }
 }
@@ -3,7 +3,7 @@
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
+    command::{bitfield_bound_check, issue_with_info},
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -110,6 +110,8 @@ impl<'a> ReadBufferCommand<'a> {
         })
     }
 
+    issue_with_info!(issue_10_with_info, issue_10(), Vec<u8>);
+
     pub fn issue_16(&mut self) -> crate::Result<Vec<u8>> {
         self.error_check(64, 32)?;
 
@@ -128,6 +130,8 @@ impl<'a> ReadBufferCommand<'a> {
             timeout: self.timeout,
         })
     }
+
+    issue_with_info!(issue_16_with_info, issue_16(), Vec<u8>);
 }
 
 impl Scsi {
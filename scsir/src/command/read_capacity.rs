@@ -4,7 +4,7 @@ use std::{marker::PhantomData, mem::size_of};
 
 use modular_bitfield_msb::prelude::*;
 
-use crate::{result_data::ResultData, Command, DataDirection, Scsi};
+use crate::{command::issue_with_info, result_data::ResultData, Command, DataDirection, Scsi};
 
 #[derive(Clone, Debug)]
 pub struct ReadCapacityCommand<'a> {
@@ -69,6 +69,8 @@ impl<'a> ReadCapacityCommand<'a> {
         })
     }
 
+    issue_with_info!(issue_10_with_info, issue_10(), ReadCapacity10Result);
+
     pub fn issue_16(&mut self) -> crate::Result<ReadCapacity16Result> {
         let command_buffer = CommandBuffer16::new()
             .with_operation_code(OPERATION_CODE_16)
@@ -99,6 +101,8 @@ impl<'a> ReadCapacityCommand<'a> {
             lowest_aligned_logical_block_address: result.lowest_aligned_logical_block_address(),
         })
     }
+
+    issue_with_info!(issue_16_with_info, issue_16(), ReadCapacity16Result);
 }
 
 impl Scsi {
@@ -5,7 +5,7 @@ use std::{marker::PhantomData, mem::size_of};
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::{bitfield_bound_check, get_array},
+    command::{bitfield_bound_check, get_array, issue_with_info},
     data_wrapper::{AnyType, FlexibleStruct},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -204,6 +204,8 @@ impl<'a> ReadDefectDataCommand<'a> {
         })
     }
 
+    issue_with_info!(issue_10_with_info, issue_10(), CommandResult);
+
     pub fn issue_12(&mut self) -> crate::Result<CommandResult> {
         let extra_allocation_length =
             self.descriptor_length as usize * self.get_defect_list_item_size();
@@ -236,6 +238,8 @@ impl<'a> ReadDefectDataCommand<'a> {
             descriptors: defect_list,
         })
     }
+
+    issue_with_info!(issue_12_with_info, issue_12(), CommandResult);
 }
 
 impl Scsi {
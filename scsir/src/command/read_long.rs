@@ -3,7 +3,7 @@
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
+    command::{bitfield_bound_check, issue_with_info},
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -81,6 +81,8 @@ impl<'a> ReadLongCommand<'a> {
         self.interface.issue(&temp)
     }
 
+    issue_with_info!(issue_10_with_info, issue_10(), Vec<u8>);
+
     pub fn issue_16(&mut self) -> crate::Result<Vec<u8>> {
         let temp = ThisCommand {
             command_buffer: CommandBuffer16::new()
@@ -97,6 +99,8 @@ impl<'a> ReadLongCommand<'a> {
 
         self.interface.issue(&temp)
     }
+
+    issue_with_info!(issue_16_with_info, issue_16(), Vec<u8>);
 }
 
 impl Scsi {
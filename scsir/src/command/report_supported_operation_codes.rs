@@ -5,7 +5,7 @@ use std::mem::size_of;
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::{bitfield_bound_check, get_array},
+    command::{bitfield_bound_check, get_array, issue_with_info},
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -113,6 +113,8 @@ impl<'a> ReportSupportedOperationCodesCommand<'a> {
             timeout: self.timeout,
         })
     }
+
+    issue_with_info!(issue_with_info, issue(), CommandResult);
 }
 
 impl Scsi {
@@ -256,9 +258,11 @@ impl Command for ThisCommand {
                 })
             }
 
+            const HEADER_LENGTH: u32 = size_of::<AllCommandsParameterDataHeader>() as u32;
             Ok(CommandResult::AllCommands(AllCommands {
-                required_allocation_length: header.command_data_length()
-                    + size_of::<AllCommandsParameterDataHeader>() as u32,
+                required_allocation_length: header
+                    .command_data_length()
+                    .saturating_add(HEADER_LENGTH),
                 descriptors,
             }))
         } else if self.command_buffer.reporting_options() < 0b100 {
@@ -4,7 +4,7 @@ use std::mem::size_of;
 
 use modular_bitfield_msb::prelude::*;
 
-use crate::{result_data::ResultData, Command, DataDirection, Scsi};
+use crate::{command::issue_with_info, result_data::ResultData, Command, DataDirection, Scsi};
 
 #[derive(Clone, Debug)]
 pub struct ReportSupportedTaskManagementFunctionsCommand<'a> {
@@ -13,6 +13,7 @@ pub struct ReportSupportedTaskManagementFunctionsCommand<'a> {
     command_buffer: CommandBuffer,
 }
 
+/// On Linux, `os::linux::ResetLevel::is_supported_by` tells which reset levels these allow.
 #[derive(Clone, Copy, Debug)]
 pub struct CommandResult {
     pub abort_task_supported: bool,
@@ -74,6 +75,8 @@ impl<'a> ReportSupportedTaskManagementFunctionsCommand<'a> {
             timeout: self.timeout,
         })
     }
+
+    issue_with_info!(issue_with_info, issue(), CommandResult);
 }
 
 impl Scsi {
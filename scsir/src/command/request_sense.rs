@@ -3,7 +3,10 @@
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::sense::{SenseData, MAX_SENSE_BUFFER_LENGTH},
+    command::{
+        issue_with_info,
+        sense::{SenseData, MAX_SENSE_BUFFER_LENGTH},
+    },
     result_data::ResultData,
     Command, DataDirection, Scsi,
 };
@@ -47,6 +50,8 @@ impl<'a> RequestSenseCommand<'a> {
             timeout: self.timeout,
         })
     }
+
+    issue_with_info!(issue_with_info, issue(), SenseData);
 }
 
 impl Scsi {
@@ -106,7 +111,12 @@ impl Command for ThisCommand {
         result.check_ioctl_error()?;
         result.check_common_error()?;
 
-        Ok(SenseData::parse(result.data, result.transfered_data_length))
+        // SATLs and USB bridges often don't report a residual, the additional sense length
+        // tells where the sense data ends then
+        let length = result
+            .transfered_data_length
+            .min(result.data[7] as usize + 8);
+        Ok(SenseData::parse(result.data, length))
     }
 }
 
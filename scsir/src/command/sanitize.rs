@@ -3,7 +3,7 @@
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
+    command::{bitfield_bound_check, issue_with_info},
     data_wrapper::{AnyType, FlexibleStruct},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -97,6 +97,8 @@ impl<'a> SanitizeCommand<'a> {
             timeout: self.timeout,
         })
     }
+
+    issue_with_info!(issue_with_info, issue(), ());
 }
 
 impl<'a> OverwriteParameterListBuilder<'a> {
@@ -257,5 +259,4 @@ mod tests {
             concat!("Size of: ", stringify!(OverwriteParameterListHeader))
         );
     }
-
 }
@@ -3,6 +3,7 @@
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
+    command::issue_with_info,
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -60,6 +61,8 @@ impl<'a> SecurityProtocolInCommand<'a> {
             timeout: self.timeout,
         })
     }
+
+    issue_with_info!(issue_with_info, issue(), Vec<u8>);
 }
 
 impl Scsi {
@@ -2,7 +2,11 @@
 
 use modular_bitfield_msb::prelude::*;
 
-use crate::{command::bitfield_bound_check, result_data::ResultData, Command, DataDirection, Scsi};
+use crate::{
+    command::{bitfield_bound_check, issue_with_info},
+    result_data::ResultData,
+    Command, DataDirection, Scsi,
+};
 
 #[derive(Clone, Debug)]
 pub struct SeekCommand<'a> {
@@ -60,6 +64,8 @@ impl<'a> SeekCommand<'a> {
         self.interface.issue(&temp)
     }
 
+    issue_with_info!(issue_6_with_info, issue_6(), ());
+
     pub fn issue_10(&mut self) -> crate::Result<()> {
         bitfield_bound_check!(self.lun, 3, "lun")?;
 
@@ -74,6 +80,8 @@ impl<'a> SeekCommand<'a> {
 
         self.interface.issue(&temp)
     }
+
+    issue_with_info!(issue_10_with_info, issue_10(), ());
 }
 
 impl Scsi {
@@ -14,7 +14,10 @@ pub enum SenseData {
 }
 
 impl SenseData {
+    /// Never panics, sense data that doesn't add up is returned as `Raw`, descriptors that don't
+    /// as `Descriptor::Unknown`.
     pub fn parse(raw: &[u8; MAX_SENSE_BUFFER_LENGTH], sense_length: usize) -> Self {
+        let sense_length = sense_length.min(MAX_SENSE_BUFFER_LENGTH);
         let response_code = raw[0] & 0b01111111;
         match response_code {
             0 => Self::None,
@@ -38,7 +41,10 @@ impl SenseData {
                     field_replaceable_unit_code: raw[14],
                     is_sense_key_specific_valid: (raw[15] & 0b10000000) != 0,
                     sense_key_specific: SenseKeySpecific::parse(&raw[15..=17], sense_key),
-                    additional_sense_bytes: Vec::from(&raw[18..sense_length]),
+                    additional_sense_bytes: Vec::from(
+                        raw.get(FIXED_SENSE_LENGTH..sense_length)
+                            .unwrap_or_default(),
+                    ),
                 };
 
                 Self::Fixed(sense)
@@ -53,10 +59,17 @@ impl SenseData {
 
                 let mut descriptors = vec![];
                 while descriptor_index < sense_length {
-                    let descriptor = Descriptor::parse(&raw[descriptor_index..], sense_key);
-                    descriptors.push(descriptor);
-                    descriptor_index +=
-                        DESCRIPTOR_HEADER_LENGTH + raw[descriptor_index + 1] as usize;
+                    let remaining = &raw[descriptor_index..sense_length];
+                    // a truncated descriptor takes what is left
+                    let length = match remaining.get(1) {
+                        Some(additional_length) => usize::min(
+                            DESCRIPTOR_HEADER_LENGTH + *additional_length as usize,
+                            remaining.len(),
+                        ),
+                        None => remaining.len(),
+                    };
+                    descriptors.push(Descriptor::parse(&remaining[..length], sense_key));
+                    descriptor_index += length;
                 }
 
                 let sense = DescriptorSenseData {
@@ -71,6 +84,193 @@ impl SenseData {
             _ => Self::Raw(Vec::from(&raw[..sense_length])),
         }
     }
+
+    pub fn sense_key(&self) -> Option<SenseKey> {
+        match self {
+            Self::Fixed(sense) => Some(sense.sense_key),
+            Self::Descriptor(sense) => Some(sense.sense_key),
+            Self::None | Self::Raw(_) => None,
+        }
+    }
+
+    pub fn additional_sense_code(&self) -> Option<&AdditionalSenseCode> {
+        match self {
+            Self::Fixed(sense) => Some(&sense.additional_sense_code),
+            Self::Descriptor(sense) => Some(&sense.additional_sense_code),
+            Self::None | Self::Raw(_) => None,
+        }
+    }
+
+    pub fn asc(&self) -> Option<u8> {
+        self.additional_sense_code().map(|code| (**code >> 8) as u8)
+    }
+
+    pub fn ascq(&self) -> Option<u8> {
+        self.additional_sense_code().map(|code| **code as u8)
+    }
+
+    /// The information field if it is valid, e.g. the first failing LBA of a medium error.
+    pub fn information(&self) -> Option<u64> {
+        match self {
+            Self::Fixed(sense) => sense
+                .is_valid
+                .then(|| u32::from_be_bytes(sense.information) as u64),
+            Self::Descriptor(sense) => sense.information(),
+            Self::None | Self::Raw(_) => None,
+        }
+    }
+
+    /// `None` if there is none, which the fixed format reports as zero.
+    pub fn command_specific_information(&self) -> Option<u64> {
+        match self {
+            Self::Fixed(sense) => {
+                let information = u32::from_be_bytes(sense.command_specific_information);
+                (information != 0).then_some(information as u64)
+            }
+            Self::Descriptor(sense) => sense.command_specific_information(),
+            Self::None | Self::Raw(_) => None,
+        }
+    }
+
+    /// The sense key specific field if it is valid.
+    pub fn sense_key_specific(&self) -> Option<&SenseKeySpecific> {
+        match self {
+            Self::Fixed(sense) => sense
+                .is_sense_key_specific_valid
+                .then_some(&sense.sense_key_specific),
+            Self::Descriptor(sense) => sense.sense_key_specific(),
+            Self::None | Self::Raw(_) => None,
+        }
+    }
+
+    /// The progress of e.g. a FORMAT UNIT or SANITIZE, in 65536ths.
+    pub fn progress(&self) -> Option<u16> {
+        match self.sense_key_specific()? {
+            SenseKeySpecific::NoSense {
+                progress_indication,
+            } => Some(*progress_indication),
+            _ => None,
+        }
+    }
+
+    pub fn is_deferred(&self) -> bool {
+        match self {
+            Self::Fixed(sense) => matches!(sense.response_code, ErrorType::Deferred),
+            Self::Descriptor(sense) => matches!(sense.response_code, ErrorType::Deferred),
+            Self::None | Self::Raw(_) => false,
+        }
+    }
+
+    /// `None` if there is none, which is reported as zero.
+    pub fn field_replaceable_unit(&self) -> Option<u8> {
+        let code = match self {
+            Self::Fixed(sense) => sense.field_replaceable_unit_code,
+            Self::Descriptor(sense) => sense.field_replaceable_unit_code()?,
+            Self::None | Self::Raw(_) => return None,
+        };
+
+        (code != 0).then_some(code)
+    }
+
+    /// The same sense data in fixed format, `None` and `Raw` stay as they are.
+    pub fn to_fixed(&self) -> Self {
+        match self {
+            Self::Descriptor(sense) => Self::Fixed(FixedSenseData::from(sense)),
+            other => other.clone(),
+        }
+    }
+
+    /// The same sense data in descriptor format, `None` and `Raw` stay as they are.
+    pub fn to_descriptor(&self) -> Self {
+        match self {
+            Self::Fixed(sense) => Self::Descriptor(DescriptorSenseData::from(sense)),
+            other => other.clone(),
+        }
+    }
+
+    /// The wire format, empty for `None`.
+    pub fn to_bytes(&self) -> crate::Result<Vec<u8>> {
+        match self {
+            Self::None => Ok(vec![]),
+            Self::Fixed(sense) => sense.to_bytes(),
+            Self::Descriptor(sense) => sense.to_bytes(),
+            Self::Raw(raw) => Ok(raw.clone()),
+        }
+    }
+}
+
+/// Assembles sense data, e.g. for an emulated device or a test fixture.
+#[derive(Clone, Debug)]
+pub struct SenseDataBuilder {
+    sense: DescriptorSenseData,
+}
+
+impl SenseDataBuilder {
+    pub fn new(sense_key: SenseKey, asc: u8, ascq: u8) -> Self {
+        Self {
+            sense: DescriptorSenseData {
+                response_code: ErrorType::Current,
+                sense_key,
+                additional_sense_code: AdditionalSenseCode::from((asc, ascq)),
+                descriptors: vec![],
+            },
+        }
+    }
+
+    pub fn deferred(&mut self, value: bool) -> &mut Self {
+        self.sense.response_code = match value {
+            true => ErrorType::Deferred,
+            false => ErrorType::Current,
+        };
+        self
+    }
+
+    pub fn information(&mut self, value: u64) -> &mut Self {
+        self.descriptor(Descriptor::Information {
+            is_valid: true,
+            information: value.to_be_bytes(),
+        })
+    }
+
+    pub fn command_specific_information(&mut self, value: u64) -> &mut Self {
+        self.descriptor(Descriptor::CommandSpecificInformation {
+            command_specific_information: value.to_be_bytes(),
+        })
+    }
+
+    pub fn sense_key_specific(&mut self, value: SenseKeySpecific) -> &mut Self {
+        self.descriptor(Descriptor::SenseKeySpecific {
+            is_sense_key_specific_valid: true,
+            sense_key_specific: value,
+        })
+    }
+
+    pub fn field_replaceable_unit(&mut self, value: u8) -> &mut Self {
+        self.descriptor(Descriptor::FieldReplaceableUnit {
+            field_replaceable_unit_code: value,
+        })
+    }
+
+    pub fn descriptor(&mut self, value: Descriptor) -> &mut Self {
+        self.sense.descriptors.push(value);
+        self
+    }
+
+    pub fn build_descriptor(&self) -> DescriptorSenseData {
+        self.sense.clone()
+    }
+
+    /// Keeps what the fixed format can hold, see [`FixedSenseData::from`].
+    pub fn build_fixed(&self) -> FixedSenseData {
+        FixedSenseData::from(&self.sense)
+    }
+
+    pub fn to_bytes(&self, descriptor_format: bool) -> crate::Result<Vec<u8>> {
+        match descriptor_format {
+            true => self.sense.to_bytes(),
+            false => self.build_fixed().to_bytes(),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -90,6 +290,33 @@ pub struct FixedSenseData {
     pub additional_sense_bytes: Vec<u8>,
 }
 
+impl FixedSenseData {
+    /// The wire format, at most [`MAX_SENSE_BUFFER_LENGTH`] bytes.
+    pub fn to_bytes(&self) -> crate::Result<Vec<u8>> {
+        let mut raw = vec![0; FIXED_SENSE_LENGTH];
+        raw[0] = ((self.is_valid as u8) << 7) | self.response_code.value(0x70);
+        raw[2] = ((self.is_filemark as u8) << 7)
+            | ((self.is_end_of_medium as u8) << 6)
+            | ((self.is_incorrect_length_indicator as u8) << 5)
+            | self.sense_key as u8;
+        raw[3..=6].copy_from_slice(&self.information);
+        raw[8..=11].copy_from_slice(&self.command_specific_information);
+        (raw[12], raw[13]) = self.additional_sense_code.clone().into();
+        raw[14] = self.field_replaceable_unit_code;
+        raw[15..=17].copy_from_slice(
+            &self
+                .sense_key_specific
+                .to_bytes(self.is_sense_key_specific_valid),
+        );
+        raw.extend_from_slice(&self.additional_sense_bytes);
+
+        check_sense_length(raw.len())?;
+        raw[7] = (raw.len() - 8) as u8;
+
+        Ok(raw)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct DescriptorSenseData {
     pub response_code: ErrorType,
@@ -98,6 +325,196 @@ pub struct DescriptorSenseData {
     pub descriptors: Vec<Descriptor>,
 }
 
+impl DescriptorSenseData {
+    /// From the Information or the Direct-access block device descriptor, if valid.
+    pub fn information(&self) -> Option<u64> {
+        self.descriptors
+            .iter()
+            .find_map(|descriptor| match descriptor {
+                Descriptor::Information {
+                    is_valid: true,
+                    information,
+                }
+                | Descriptor::DirectAccessBlockDevice {
+                    is_valid: true,
+                    information,
+                    ..
+                } => Some(u64::from_be_bytes(*information)),
+                _ => None,
+            })
+    }
+
+    pub fn command_specific_information(&self) -> Option<u64> {
+        self.descriptors
+            .iter()
+            .find_map(|descriptor| match descriptor {
+                Descriptor::CommandSpecificInformation {
+                    command_specific_information,
+                }
+                | Descriptor::DirectAccessBlockDevice {
+                    command_specific_information,
+                    ..
+                } => Some(u64::from_be_bytes(*command_specific_information)),
+                _ => None,
+            })
+    }
+
+    pub fn sense_key_specific(&self) -> Option<&SenseKeySpecific> {
+        self.descriptors
+            .iter()
+            .find_map(|descriptor| match descriptor {
+                Descriptor::SenseKeySpecific {
+                    is_sense_key_specific_valid: true,
+                    sense_key_specific,
+                }
+                | Descriptor::DirectAccessBlockDevice {
+                    is_sense_key_specific_valid: true,
+                    sense_key_specific,
+                    ..
+                } => Some(sense_key_specific),
+                _ => None,
+            })
+    }
+
+    pub fn field_replaceable_unit_code(&self) -> Option<u8> {
+        self.descriptors
+            .iter()
+            .find_map(|descriptor| match descriptor {
+                Descriptor::FieldReplaceableUnit {
+                    field_replaceable_unit_code,
+                }
+                | Descriptor::DirectAccessBlockDevice {
+                    field_replaceable_unit_code,
+                    ..
+                } => Some(*field_replaceable_unit_code),
+                _ => None,
+            })
+    }
+
+    /// The wire format, at most [`MAX_SENSE_BUFFER_LENGTH`] bytes.
+    pub fn to_bytes(&self) -> crate::Result<Vec<u8>> {
+        let mut raw = vec![0; 8];
+        raw[0] = self.response_code.value(0x72);
+        raw[1] = self.sense_key as u8;
+        (raw[2], raw[3]) = self.additional_sense_code.clone().into();
+        for descriptor in &self.descriptors {
+            raw.extend_from_slice(&descriptor.to_bytes()?);
+        }
+
+        check_sense_length(raw.len())?;
+        raw[7] = (raw.len() - 8) as u8;
+
+        Ok(raw)
+    }
+}
+
+/// Keeps what both formats can hold. Information that doesn't fit into 4 bytes is marked as
+/// invalid, as SPC requires.
+impl From<&DescriptorSenseData> for FixedSenseData {
+    fn from(value: &DescriptorSenseData) -> Self {
+        let information = value
+            .information()
+            .and_then(|information| u32::try_from(information).ok());
+        let command_specific_information = value
+            .command_specific_information()
+            .and_then(|information| u32::try_from(information).ok())
+            .unwrap_or_default();
+
+        let mut fixed = FixedSenseData {
+            is_valid: information.is_some(),
+            response_code: value.response_code.clone(),
+            is_filemark: false,
+            is_end_of_medium: false,
+            is_incorrect_length_indicator: false,
+            sense_key: value.sense_key,
+            information: information.unwrap_or_default().to_be_bytes(),
+            command_specific_information: command_specific_information.to_be_bytes(),
+            additional_sense_code: value.additional_sense_code.clone(),
+            field_replaceable_unit_code: value.field_replaceable_unit_code().unwrap_or_default(),
+            is_sense_key_specific_valid: false,
+            sense_key_specific: SenseKeySpecific::InvalidSenseKey,
+            additional_sense_bytes: vec![],
+        };
+        if let Some(sense_key_specific) = value.sense_key_specific() {
+            fixed.is_sense_key_specific_valid = true;
+            fixed.sense_key_specific = sense_key_specific.clone();
+        }
+
+        for descriptor in &value.descriptors {
+            match *descriptor {
+                Descriptor::StreamCommands {
+                    is_filemark,
+                    is_end_of_medium,
+                    is_incorrect_length_indicator,
+                } => {
+                    fixed.is_filemark |= is_filemark;
+                    fixed.is_end_of_medium |= is_end_of_medium;
+                    fixed.is_incorrect_length_indicator |= is_incorrect_length_indicator;
+                }
+                Descriptor::BlockCommands {
+                    is_incorrect_length_indicator,
+                }
+                | Descriptor::DirectAccessBlockDevice {
+                    is_incorrect_length_indicator,
+                    ..
+                } => fixed.is_incorrect_length_indicator |= is_incorrect_length_indicator,
+                _ => {}
+            }
+        }
+
+        fixed
+    }
+}
+
+/// Additional sense bytes have no place in the descriptor format and are dropped.
+impl From<&FixedSenseData> for DescriptorSenseData {
+    fn from(value: &FixedSenseData) -> Self {
+        let mut descriptors = vec![];
+
+        if value.is_valid {
+            descriptors.push(Descriptor::Information {
+                is_valid: true,
+                information: (u32::from_be_bytes(value.information) as u64).to_be_bytes(),
+            });
+        }
+        let command_specific_information = u32::from_be_bytes(value.command_specific_information);
+        if command_specific_information != 0 {
+            descriptors.push(Descriptor::CommandSpecificInformation {
+                command_specific_information: (command_specific_information as u64).to_be_bytes(),
+            });
+        }
+        if value.is_sense_key_specific_valid {
+            descriptors.push(Descriptor::SenseKeySpecific {
+                is_sense_key_specific_valid: true,
+                sense_key_specific: value.sense_key_specific.clone(),
+            });
+        }
+        if value.field_replaceable_unit_code != 0 {
+            descriptors.push(Descriptor::FieldReplaceableUnit {
+                field_replaceable_unit_code: value.field_replaceable_unit_code,
+            });
+        }
+        if value.is_filemark || value.is_end_of_medium {
+            descriptors.push(Descriptor::StreamCommands {
+                is_filemark: value.is_filemark,
+                is_end_of_medium: value.is_end_of_medium,
+                is_incorrect_length_indicator: value.is_incorrect_length_indicator,
+            });
+        } else if value.is_incorrect_length_indicator {
+            descriptors.push(Descriptor::BlockCommands {
+                is_incorrect_length_indicator: true,
+            });
+        }
+
+        DescriptorSenseData {
+            response_code: value.response_code.clone(),
+            sense_key: value.sense_key,
+            additional_sense_code: value.additional_sense_code.clone(),
+            descriptors,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum ErrorType {
     Current,
@@ -105,6 +522,17 @@ pub enum ErrorType {
     Unknown(u8),
 }
 
+impl ErrorType {
+    /// The response code, `current` or the deferred one after it.
+    fn value(&self, current: u8) -> u8 {
+        match self {
+            Self::Current => current,
+            Self::Deferred => current + 1,
+            Self::Unknown(value) => *value & 0b01111111,
+        }
+    }
+}
+
 impl From<u8> for ErrorType {
     fn from(value: u8) -> Self {
         match value {
@@ -116,7 +544,7 @@ impl From<u8> for ErrorType {
 }
 
 #[repr(u8)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum SenseKey {
     NoSense,
     RecoveredError,
@@ -138,7 +566,7 @@ pub enum SenseKey {
 
 impl From<u8> for SenseKey {
     fn from(value: u8) -> Self {
-        match value {
+        match value & 0b00001111 {
             0x0 => Self::NoSense,
             0x1 => Self::RecoveredError,
             0x2 => Self::NotReady,
@@ -154,8 +582,7 @@ impl From<u8> for SenseKey {
             0xC => Self::Reserved,
             0xD => Self::VolumeOverflow,
             0xE => Self::Miscompare,
-            0xF => Self::Completed,
-            _ => panic!("Not reachable"),
+            _ => Self::Completed,
         }
     }
 }
@@ -176,24 +603,55 @@ pub enum Descriptor {
     FieldReplaceableUnit {
         field_replaceable_unit_code: u8,
     },
-    StreamCommands(Vec<u8>),
-    BlockCommands(Vec<u8>),
+    StreamCommands {
+        is_filemark: bool,
+        is_end_of_medium: bool,
+        is_incorrect_length_indicator: bool,
+    },
+    BlockCommands {
+        is_incorrect_length_indicator: bool,
+    },
     OsdObjectIdentification(Vec<u8>),
     OsdResponseIntegrityCheckValue(Vec<u8>),
     OsdAttributeIdentification(Vec<u8>),
-    AtaReturn(Vec<u8>),
+    AtaStatusReturn(AtaStatusReturn),
     AnotherProgressIndication {
         sense_key: SenseKey,
         additional_sense_code: AdditionalSenseCode,
         progress_indication: u16,
     },
+    UserDataSegmentReferral {
+        is_not_all_referrals: bool,
+        user_data_segments: Vec<UserDataSegment>,
+    },
+    ForwardedSenseData {
+        is_truncated: bool,
+        sense_data_source: u8,
+        status: u8,
+        sense_data: Box<SenseData>,
+    },
+    DirectAccessBlockDevice {
+        is_valid: bool,
+        is_incorrect_length_indicator: bool,
+        is_sense_key_specific_valid: bool,
+        sense_key_specific: SenseKeySpecific,
+        field_replaceable_unit_code: u8,
+        information: [u8; 8],
+        command_specific_information: [u8; 8],
+    },
     Unknown(Vec<u8>),
 }
 
 impl Descriptor {
+    /// `raw` is the whole descriptor, anything else is `Unknown`.
     fn parse(raw: &[u8], sense_key: SenseKey) -> Self {
-        let additional_length = raw[1] as usize;
-        let raw = &raw[..DESCRIPTOR_HEADER_LENGTH + additional_length];
+        let additional_length = match raw.get(1) {
+            Some(length) if DESCRIPTOR_HEADER_LENGTH + *length as usize == raw.len() => {
+                *length as usize
+            }
+            _ => return Self::Unknown(Vec::from(raw)),
+        };
+
         match raw[0] {
             0x00 => {
                 if additional_length != 0x0A {
@@ -233,26 +691,331 @@ impl Descriptor {
                     field_replaceable_unit_code: raw[3],
                 }
             }
-            0x04 => Self::StreamCommands(Vec::from(raw)),
-            0x05 => Self::BlockCommands(Vec::from(raw)),
+            0x04 => {
+                if additional_length != 0x02 {
+                    return Self::Unknown(Vec::from(raw));
+                }
+
+                Self::StreamCommands {
+                    is_filemark: (raw[3] & 0b10000000) != 0,
+                    is_end_of_medium: (raw[3] & 0b01000000) != 0,
+                    is_incorrect_length_indicator: (raw[3] & 0b00100000) != 0,
+                }
+            }
+            0x05 => {
+                if additional_length != 0x02 {
+                    return Self::Unknown(Vec::from(raw));
+                }
+
+                Self::BlockCommands {
+                    is_incorrect_length_indicator: (raw[3] & 0b00100000) != 0,
+                }
+            }
             0x06 => Self::OsdObjectIdentification(Vec::from(raw)),
             0x07 => Self::OsdResponseIntegrityCheckValue(Vec::from(raw)),
             0x08 => Self::OsdAttributeIdentification(Vec::from(raw)),
-            0x09 => Self::AtaReturn(Vec::from(raw)),
+            0x09 => {
+                if additional_length != 0x0C {
+                    return Self::Unknown(Vec::from(raw));
+                }
+
+                Self::AtaStatusReturn(AtaStatusReturn::parse(raw))
+            }
             0x0A => {
                 if additional_length != 0x06 {
                     return Self::Unknown(Vec::from(raw));
                 }
 
                 Self::AnotherProgressIndication {
-                    sense_key: SenseKey::from(raw[2]),
+                    sense_key: SenseKey::from(raw[2] & 0b00001111),
                     additional_sense_code: AdditionalSenseCode::from((raw[3], raw[4])),
                     progress_indication: u16::from_be_bytes(raw[6..=7].try_into().unwrap()),
                 }
             }
+            0x0B => {
+                if additional_length < 0x02 {
+                    return Self::Unknown(Vec::from(raw));
+                }
+
+                let Some(user_data_segments) = UserDataSegment::parse_all(&raw[4..]) else {
+                    return Self::Unknown(Vec::from(raw));
+                };
+
+                Self::UserDataSegmentReferral {
+                    is_not_all_referrals: (raw[2] & 0b00000001) != 0,
+                    user_data_segments,
+                }
+            }
+            0x0C => {
+                if additional_length < 0x02 {
+                    return Self::Unknown(Vec::from(raw));
+                }
+
+                let sense_length = usize::min(raw.len() - 4, MAX_SENSE_BUFFER_LENGTH);
+                let mut sense_data = [0; MAX_SENSE_BUFFER_LENGTH];
+                sense_data[..sense_length].copy_from_slice(&raw[4..4 + sense_length]);
+
+                Self::ForwardedSenseData {
+                    is_truncated: (raw[2] & 0b10000000) != 0,
+                    sense_data_source: raw[2] & 0b00001111,
+                    status: raw[3],
+                    sense_data: Box::new(SenseData::parse(&sense_data, sense_length)),
+                }
+            }
+            0x0D => {
+                if additional_length != 0x1E {
+                    return Self::Unknown(Vec::from(raw));
+                }
+
+                Self::DirectAccessBlockDevice {
+                    is_valid: (raw[2] & 0b10000000) != 0,
+                    is_incorrect_length_indicator: (raw[2] & 0b00100000) != 0,
+                    is_sense_key_specific_valid: (raw[4] & 0b10000000) != 0,
+                    sense_key_specific: SenseKeySpecific::parse(&raw[4..=6], sense_key),
+                    field_replaceable_unit_code: raw[7],
+                    information: raw[8..=15].try_into().unwrap(),
+                    command_specific_information: raw[16..=23].try_into().unwrap(),
+                }
+            }
             _ => Self::Unknown(Vec::from(raw)),
         }
     }
+
+    /// The wire format, including the header.
+    pub fn to_bytes(&self) -> crate::Result<Vec<u8>> {
+        let mut raw = match self {
+            Self::Information {
+                is_valid,
+                information,
+            } => {
+                let mut raw = vec![0x00, 0x0A, (*is_valid as u8) << 7, 0];
+                raw.extend_from_slice(information);
+                raw
+            }
+            Self::CommandSpecificInformation {
+                command_specific_information,
+            } => {
+                let mut raw = vec![0x01, 0x0A, 0, 0];
+                raw.extend_from_slice(command_specific_information);
+                raw
+            }
+            Self::SenseKeySpecific {
+                is_sense_key_specific_valid,
+                sense_key_specific,
+            } => {
+                let mut raw = vec![0x02, 0x06, 0, 0];
+                raw.extend_from_slice(&sense_key_specific.to_bytes(*is_sense_key_specific_valid));
+                raw.push(0);
+                raw
+            }
+            Self::FieldReplaceableUnit {
+                field_replaceable_unit_code,
+            } => vec![0x03, 0x02, 0, *field_replaceable_unit_code],
+            Self::StreamCommands {
+                is_filemark,
+                is_end_of_medium,
+                is_incorrect_length_indicator,
+            } => vec![
+                0x04,
+                0x02,
+                0,
+                ((*is_filemark as u8) << 7)
+                    | ((*is_end_of_medium as u8) << 6)
+                    | ((*is_incorrect_length_indicator as u8) << 5),
+            ],
+            Self::BlockCommands {
+                is_incorrect_length_indicator,
+            } => vec![0x05, 0x02, 0, (*is_incorrect_length_indicator as u8) << 5],
+            Self::AtaStatusReturn(registers) => registers.to_bytes(),
+            Self::AnotherProgressIndication {
+                sense_key,
+                additional_sense_code,
+                progress_indication,
+            } => {
+                let (asc, ascq) = additional_sense_code.clone().into();
+                let mut raw = vec![0x0A, 0x06, *sense_key as u8, asc, ascq, 0];
+                raw.extend_from_slice(&progress_indication.to_be_bytes());
+                raw
+            }
+            Self::UserDataSegmentReferral {
+                is_not_all_referrals,
+                user_data_segments,
+            } => {
+                let mut raw = vec![0x0B, 0, *is_not_all_referrals as u8, 0];
+                for segment in user_data_segments {
+                    raw.extend_from_slice(&segment.to_bytes()?);
+                }
+                raw
+            }
+            Self::ForwardedSenseData {
+                is_truncated,
+                sense_data_source,
+                status,
+                sense_data,
+            } => {
+                let mut raw = vec![
+                    0x0C,
+                    0,
+                    ((*is_truncated as u8) << 7) | (sense_data_source & 0b00001111),
+                    *status,
+                ];
+                raw.extend_from_slice(&sense_data.to_bytes()?);
+                raw
+            }
+            Self::DirectAccessBlockDevice {
+                is_valid,
+                is_incorrect_length_indicator,
+                is_sense_key_specific_valid,
+                sense_key_specific,
+                field_replaceable_unit_code,
+                information,
+                command_specific_information,
+            } => {
+                let mut raw = vec![
+                    0x0D,
+                    0x1E,
+                    ((*is_valid as u8) << 7) | ((*is_incorrect_length_indicator as u8) << 5),
+                    0,
+                ];
+                raw.extend_from_slice(&sense_key_specific.to_bytes(*is_sense_key_specific_valid));
+                raw.push(*field_replaceable_unit_code);
+                raw.extend_from_slice(information);
+                raw.extend_from_slice(command_specific_information);
+                raw.resize(DESCRIPTOR_HEADER_LENGTH + 0x1E, 0);
+                raw
+            }
+            Self::OsdObjectIdentification(raw)
+            | Self::OsdResponseIntegrityCheckValue(raw)
+            | Self::OsdAttributeIdentification(raw)
+            | Self::Unknown(raw) => return Ok(raw.clone()),
+        };
+
+        let additional_length = raw.len() - DESCRIPTOR_HEADER_LENGTH;
+        raw[1] = u8::try_from(additional_length).map_err(|_| {
+            crate::Error::ArgumentOutOfBounds(format!(
+                "descriptor 0x{:02X} is {} bytes long, at most 255 fit.",
+                raw[0], additional_length
+            ))
+        })?;
+
+        Ok(raw)
+    }
+}
+
+/// The ATA registers after a command, see SAT. The upper bytes of the count and the LBA are
+/// only valid if `is_extend` is set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AtaStatusReturn {
+    pub is_extend: bool,
+    pub error: u8,
+    pub count: u16,
+    /// 48 bits.
+    pub lba: u64,
+    pub device: u8,
+    pub status: u8,
+}
+
+impl AtaStatusReturn {
+    fn parse(raw: &[u8]) -> Self {
+        Self {
+            is_extend: (raw[2] & 0b00000001) != 0,
+            error: raw[3],
+            count: u16::from_be_bytes([raw[4], raw[5]]),
+            lba: u64::from_be_bytes([0, 0, raw[10], raw[8], raw[6], raw[11], raw[9], raw[7]]),
+            device: raw[12],
+            status: raw[13],
+        }
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        let [_, _, lba_47_40, lba_39_32, lba_31_24, lba_23_16, lba_15_8, lba_7_0] =
+            self.lba.to_be_bytes();
+        let [count_15_8, count_7_0] = self.count.to_be_bytes();
+
+        vec![
+            0x09,
+            0x0C,
+            self.is_extend as u8,
+            self.error,
+            count_15_8,
+            count_7_0,
+            lba_31_24,
+            lba_7_0,
+            lba_39_32,
+            lba_15_8,
+            lba_47_40,
+            lba_23_16,
+            self.device,
+            self.status,
+        ]
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserDataSegment {
+    pub first_logical_block_address: u64,
+    pub last_logical_block_address: u64,
+    pub target_port_groups: Vec<TargetPortGroup>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TargetPortGroup {
+    pub asymmetric_access_state: u8,
+    pub target_port_group: u16,
+}
+
+impl UserDataSegment {
+    const HEADER_LENGTH: usize = 20;
+    const TARGET_PORT_GROUP_LENGTH: usize = 4;
+
+    /// `None` if the last descriptor is truncated.
+    fn parse_all(mut raw: &[u8]) -> Option<Vec<Self>> {
+        let mut segments = vec![];
+        while !raw.is_empty() {
+            if raw.len() < Self::HEADER_LENGTH {
+                return None;
+            }
+
+            let length = Self::HEADER_LENGTH + raw[3] as usize * Self::TARGET_PORT_GROUP_LENGTH;
+            if raw.len() < length {
+                return None;
+            }
+
+            segments.push(Self {
+                first_logical_block_address: u64::from_be_bytes(raw[4..12].try_into().unwrap()),
+                last_logical_block_address: u64::from_be_bytes(raw[12..20].try_into().unwrap()),
+                target_port_groups: raw[Self::HEADER_LENGTH..length]
+                    .chunks_exact(Self::TARGET_PORT_GROUP_LENGTH)
+                    .map(|group| TargetPortGroup {
+                        asymmetric_access_state: group[0] & 0b00001111,
+                        target_port_group: u16::from_be_bytes([group[2], group[3]]),
+                    })
+                    .collect(),
+            });
+            raw = &raw[length..];
+        }
+
+        Some(segments)
+    }
+
+    fn to_bytes(&self) -> crate::Result<Vec<u8>> {
+        let count = u8::try_from(self.target_port_groups.len()).map_err(|_| {
+            crate::Error::ArgumentOutOfBounds(format!(
+                "{} target port groups, at most 255 fit into a user data segment.",
+                self.target_port_groups.len()
+            ))
+        })?;
+
+        let mut raw = vec![0, 0, 0, count];
+        raw.extend_from_slice(&self.first_logical_block_address.to_be_bytes());
+        raw.extend_from_slice(&self.last_logical_block_address.to_be_bytes());
+        for group in &self.target_port_groups {
+            raw.extend_from_slice(&[group.asymmetric_access_state & 0b00001111, 0]);
+            raw.extend_from_slice(&group.target_port_group.to_be_bytes());
+        }
+
+        Ok(raw)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -310,6 +1073,43 @@ impl SenseKeySpecific {
             _ => Self::InvalidSenseKey,
         }
     }
+
+    /// The 3 bytes of the field, with the SKSV bit.
+    fn to_bytes(&self, is_valid: bool) -> [u8; 3] {
+        let (flags, value) = match *self {
+            Self::IllegalRequest {
+                is_command_data,
+                is_bit_pointer_valid,
+                bit_pointer,
+                field_pointer,
+            } => (
+                ((is_command_data as u8) << 6)
+                    | ((is_bit_pointer_valid as u8) << 3)
+                    | (bit_pointer & 0b00000111),
+                field_pointer,
+            ),
+            Self::HardwareError { actual_retry_count } => (0, actual_retry_count),
+            Self::NoSense {
+                progress_indication,
+            } => (0, progress_indication),
+            Self::CopyAborted {
+                is_segment_descriptor,
+                is_bit_pointer_valid,
+                bit_pointer,
+                field_pointer,
+            } => (
+                ((is_segment_descriptor as u8) << 5)
+                    | ((is_bit_pointer_valid as u8) << 3)
+                    | (bit_pointer & 0b00000111),
+                field_pointer,
+            ),
+            Self::UnitAttention { is_overflow } => (is_overflow as u8, 0),
+            Self::InvalidSenseKey => (0, 0),
+        };
+
+        let [value_15_8, value_7_0] = value.to_be_bytes();
+        [((is_valid as u8) << 7) | flags, value_15_8, value_7_0]
+    }
 }
 
 #[repr(transparent)]
@@ -1126,3 +1926,348 @@ impl From<AdditionalSenseCode> for (u8, u8) {
 }
 
 const DESCRIPTOR_HEADER_LENGTH: usize = 2;
+const FIXED_SENSE_LENGTH: usize = 18;
+
+fn check_sense_length(length: usize) -> crate::Result<()> {
+    if length > MAX_SENSE_BUFFER_LENGTH {
+        return Err(crate::Error::ArgumentOutOfBounds(format!(
+            "sense data is {} bytes long, at most {} fit.",
+            length, MAX_SENSE_BUFFER_LENGTH
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(bytes: &[u8]) -> SenseData {
+        let mut raw = [0; MAX_SENSE_BUFFER_LENGTH];
+        raw[..bytes.len()].copy_from_slice(bytes);
+        SenseData::parse(&raw, bytes.len())
+    }
+
+    fn descriptors(bytes: &[u8]) -> Vec<Descriptor> {
+        let mut sense = vec![0x72, 0x01, 0x00, 0x1D, 0, 0, 0, bytes.len() as u8];
+        sense.extend_from_slice(bytes);
+
+        match parse(&sense) {
+            SenseData::Descriptor(sense) => sense.descriptors,
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn ata_status_return_test() {
+        let descriptors = descriptors(&[
+            0x09, 0x0C, 0x01, 0x04, 0x12, 0x34, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x40, 0x51,
+        ]);
+
+        let Descriptor::AtaStatusReturn(registers) = &descriptors[0] else {
+            panic!("{:?}", descriptors);
+        };
+        assert_eq!(
+            *registers,
+            AtaStatusReturn {
+                is_extend: true,
+                error: 0x04,
+                count: 0x1234,
+                lba: 0x05_03_01_06_04_02,
+                device: 0x40,
+                status: 0x51,
+            }
+        );
+    }
+
+    #[test]
+    fn command_set_descriptors_test() {
+        let descriptors = descriptors(&[
+            0x04, 0x02, 0x00, 0xA0, // stream commands
+            0x05, 0x02, 0x00, 0x20, // block commands
+            0x0B, 0x1A, 0x01, 0x00, // user data segment referral
+            0x00, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0, 0, 0x10, 0, 0, 0, 0, 0, 0, 0, 0x1F, 0x01,
+            0x00, 0x00, 0x02,
+        ]);
+
+        assert!(matches!(
+            descriptors[0],
+            Descriptor::StreamCommands {
+                is_filemark: true,
+                is_end_of_medium: false,
+                is_incorrect_length_indicator: true,
+            }
+        ));
+        assert!(matches!(
+            descriptors[1],
+            Descriptor::BlockCommands {
+                is_incorrect_length_indicator: true,
+            }
+        ));
+        let Descriptor::UserDataSegmentReferral {
+            is_not_all_referrals,
+            user_data_segments,
+        } = &descriptors[2]
+        else {
+            panic!("{:?}", descriptors);
+        };
+        assert!(is_not_all_referrals);
+        assert_eq!(
+            *user_data_segments,
+            vec![UserDataSegment {
+                first_logical_block_address: 0x10,
+                last_logical_block_address: 0x1F,
+                target_port_groups: vec![TargetPortGroup {
+                    asymmetric_access_state: 0x1,
+                    target_port_group: 0x2,
+                }],
+            }]
+        );
+    }
+
+    #[test]
+    fn direct_access_and_forwarded_test() {
+        let mut bytes = vec![0x0D, 0x1E, 0xA0, 0x00, 0x80, 0x00, 0x05, 0x00];
+        bytes.extend_from_slice(&0x1234u64.to_be_bytes());
+        bytes.extend_from_slice(&[0; 16]);
+        bytes.extend_from_slice(&[0x0C, 0x0A, 0x81, 0x02]);
+        bytes.extend_from_slice(&[0x72, 0x03, 0x11, 0x00, 0, 0, 0, 0]);
+        let descriptors = descriptors(&bytes);
+
+        let Descriptor::DirectAccessBlockDevice {
+            is_valid: true,
+            is_incorrect_length_indicator: true,
+            is_sense_key_specific_valid: true,
+            sense_key_specific:
+                SenseKeySpecific::HardwareError {
+                    actual_retry_count: 5,
+                },
+            information,
+            ..
+        } = &descriptors[0]
+        else {
+            panic!("{:?}", descriptors);
+        };
+        assert_eq!(u64::from_be_bytes(*information), 0x1234);
+
+        let Descriptor::ForwardedSenseData {
+            is_truncated: true,
+            sense_data_source: 1,
+            status: 2,
+            sense_data,
+        } = &descriptors[1]
+        else {
+            panic!("{:?}", descriptors);
+        };
+        let SenseData::Descriptor(forwarded) = sense_data.as_ref() else {
+            panic!("{:?}", sense_data);
+        };
+        assert_eq!(forwarded.sense_key, SenseKey::MediumError);
+        assert_eq!(*forwarded.additional_sense_code, 0x1100);
+    }
+
+    #[test]
+    fn malformed_test() {
+        // the sense length is larger than the buffer
+        let mut raw = [0xFF; MAX_SENSE_BUFFER_LENGTH];
+        raw[0] = 0x72;
+        assert!(matches!(SenseData::parse(&raw, 1000), SenseData::Raw(_)));
+
+        // the additional sense length doesn't match
+        assert!(matches!(
+            parse(&[0x70, 0, 0x05, 0, 0, 0, 0, 0xFF]),
+            SenseData::Raw(_)
+        ));
+        assert!(matches!(
+            parse(&[0x72, 0, 0, 0, 0, 0, 0, 2]),
+            SenseData::Raw(_)
+        ));
+
+        // a truncated descriptor, a descriptor with the wrong length and an undefined sense key
+        let sense = parse(&[
+            0x72, 0xFF, 0, 0, 0, 0, 0, 8, 0x00, 0x02, 0x80, 0, 0x02, 0x06, 0x80, 0x00,
+        ]);
+        assert_eq!(sense.sense_key(), Some(SenseKey::Completed));
+        let SenseData::Descriptor(sense) = sense else {
+            panic!("{:?}", sense);
+        };
+        assert!(matches!(&sense.descriptors[..], [
+            Descriptor::Unknown(first),
+            Descriptor::Unknown(second),
+        ] if first.len() == 4 && second.len() == 4));
+        assert_eq!(sense.information(), None);
+    }
+
+    #[test]
+    fn format_independent_test() {
+        // MEDIUM ERROR at LBA 0x1234, 3 retries, in both formats
+        let fixed = parse(&[
+            0xF0, 0x00, 0x03, 0x00, 0x00, 0x12, 0x34, 0x0A, 0, 0, 0, 0, 0x11, 0x00, 0x07, 0x80,
+            0x00, 0x03,
+        ]);
+        let descriptor = parse(&[
+            0x72, 0x03, 0x11, 0x00, 0, 0, 0, 0x18, 0x00, 0x0A, 0x80, 0x00, 0, 0, 0, 0, 0, 0, 0x12,
+            0x34, 0x02, 0x06, 0x00, 0x00, 0x80, 0x00, 0x03, 0x00, 0x03, 0x02, 0x00, 0x07,
+        ]);
+
+        for sense in [
+            &fixed,
+            &descriptor,
+            &fixed.to_descriptor(),
+            &descriptor.to_fixed(),
+        ] {
+            assert_eq!(sense.sense_key(), Some(SenseKey::MediumError));
+            assert_eq!((sense.asc(), sense.ascq()), (Some(0x11), Some(0x00)));
+            assert_eq!(sense.information(), Some(0x1234));
+            assert_eq!(sense.command_specific_information(), None);
+            assert!(matches!(
+                sense.sense_key_specific(),
+                Some(SenseKeySpecific::HardwareError {
+                    actual_retry_count: 3
+                })
+            ));
+            assert_eq!(sense.progress(), None);
+            assert!(!sense.is_deferred());
+            assert_eq!(sense.field_replaceable_unit(), Some(0x07));
+        }
+        assert!(matches!(fixed.to_descriptor(), SenseData::Descriptor(_)));
+        assert!(matches!(descriptor.to_fixed(), SenseData::Fixed(_)));
+
+        // deferred NOT READY, FORMAT IN PROGRESS at 50%, with an LBA beyond 32 bits
+        let mut sense = vec![0x73, 0x02, 0x04, 0x04, 0, 0, 0, 0x14];
+        sense.extend_from_slice(&[0x00, 0x0A, 0x80, 0x00]);
+        sense.extend_from_slice(&0x1_0000_0000u64.to_be_bytes());
+        sense.extend_from_slice(&[0x02, 0x06, 0x00, 0x00, 0x80, 0x80, 0x00, 0x00]);
+        let sense = parse(&sense);
+        assert!(sense.is_deferred());
+        assert_eq!(sense.progress(), Some(0x8000));
+        assert_eq!(sense.information(), Some(0x1_0000_0000));
+        assert_eq!(sense.to_fixed().information(), None);
+        assert_eq!(sense.to_fixed().progress(), Some(0x8000));
+
+        assert_eq!(SenseData::None.sense_key(), None);
+        assert_eq!(SenseData::Raw(vec![0x7F]).information(), None);
+    }
+
+    fn round_trip(sense: &SenseData) -> Vec<u8> {
+        let bytes = sense.to_bytes().unwrap();
+        assert_eq!(parse(&bytes).to_bytes().unwrap(), bytes);
+        bytes
+    }
+
+    #[test]
+    fn encode_test() {
+        let forwarded = SenseDataBuilder::new(SenseKey::UnitAttention, 0x29, 0x00)
+            .sense_key_specific(SenseKeySpecific::UnitAttention { is_overflow: true })
+            .build_descriptor();
+        let mut builder = SenseDataBuilder::new(SenseKey::IllegalRequest, 0x24, 0x00);
+        builder
+            .deferred(true)
+            .information(0x1_2345_6789)
+            .command_specific_information(0x42)
+            .sense_key_specific(SenseKeySpecific::IllegalRequest {
+                is_command_data: true,
+                is_bit_pointer_valid: true,
+                bit_pointer: 5,
+                field_pointer: 2,
+            })
+            .field_replaceable_unit(0x07)
+            .descriptor(Descriptor::StreamCommands {
+                is_filemark: true,
+                is_end_of_medium: true,
+                is_incorrect_length_indicator: false,
+            })
+            .descriptor(Descriptor::BlockCommands {
+                is_incorrect_length_indicator: true,
+            })
+            .descriptor(Descriptor::OsdObjectIdentification(vec![
+                0x06, 0x02, 0xAA, 0xBB,
+            ]))
+            .descriptor(Descriptor::AtaStatusReturn(AtaStatusReturn {
+                is_extend: true,
+                error: 0x04,
+                count: 0x0102,
+                lba: 0xA1A2_A3A4_A5A6,
+                device: 0x40,
+                status: 0x51,
+            }))
+            .descriptor(Descriptor::AnotherProgressIndication {
+                sense_key: SenseKey::NotReady,
+                additional_sense_code: AdditionalSenseCode::from((0x04, 0x04)),
+                progress_indication: 0x1234,
+            })
+            .descriptor(Descriptor::UserDataSegmentReferral {
+                is_not_all_referrals: true,
+                user_data_segments: vec![UserDataSegment {
+                    first_logical_block_address: 0,
+                    last_logical_block_address: 0xFF,
+                    target_port_groups: vec![TargetPortGroup {
+                        asymmetric_access_state: 0x2,
+                        target_port_group: 0x10,
+                    }],
+                }],
+            })
+            .descriptor(Descriptor::ForwardedSenseData {
+                is_truncated: false,
+                sense_data_source: 0x1,
+                status: 0x02,
+                sense_data: Box::new(SenseData::Descriptor(forwarded)),
+            })
+            .descriptor(Descriptor::DirectAccessBlockDevice {
+                is_valid: true,
+                is_incorrect_length_indicator: false,
+                is_sense_key_specific_valid: true,
+                sense_key_specific: SenseKeySpecific::IllegalRequest {
+                    is_command_data: false,
+                    is_bit_pointer_valid: false,
+                    bit_pointer: 0,
+                    field_pointer: 0x0100,
+                },
+                field_replaceable_unit_code: 0x08,
+                information: 0x99u64.to_be_bytes(),
+                command_specific_information: [0; 8],
+            })
+            .descriptor(Descriptor::Unknown(vec![0x80, 0x01, 0xCC]));
+
+        let bytes = round_trip(&SenseData::Descriptor(builder.build_descriptor()));
+        assert_eq!(
+            bytes[..8],
+            [0x73, 0x05, 0x24, 0x00, 0, 0, 0, bytes.len() as u8 - 8]
+        );
+        assert_eq!(bytes, builder.to_bytes(true).unwrap());
+        let SenseData::Descriptor(sense) = parse(&bytes) else {
+            panic!("{:02X?}", bytes);
+        };
+        assert_eq!(sense.descriptors.len(), 13);
+        assert!(matches!(
+            &sense.descriptors[11],
+            Descriptor::DirectAccessBlockDevice { information, .. }
+                if u64::from_be_bytes(*information) == 0x99
+        ));
+
+        // the information doesn't fit into the fixed format
+        let bytes = builder.to_bytes(false).unwrap();
+        assert_eq!(bytes.len(), FIXED_SENSE_LENGTH);
+        let sense = parse(&bytes);
+        assert_eq!(sense.information(), None);
+        assert_eq!(sense.command_specific_information(), Some(0x42));
+        assert_eq!(sense.field_replaceable_unit(), Some(0x07));
+        assert!(sense.is_deferred());
+        let SenseData::Fixed(mut fixed) = sense else {
+            panic!("{:02X?}", bytes);
+        };
+        assert!(fixed.is_filemark && fixed.is_end_of_medium && fixed.is_incorrect_length_indicator);
+        fixed.additional_sense_bytes = vec![1, 2, 3];
+        assert_eq!(
+            round_trip(&SenseData::Fixed(fixed)).len(),
+            FIXED_SENSE_LENGTH + 3
+        );
+
+        let mut too_long = SenseDataBuilder::new(SenseKey::NoSense, 0, 0);
+        for _ in 0..21 {
+            too_long.information(0);
+        }
+        assert!(too_long.to_bytes(true).is_err());
+    }
+}
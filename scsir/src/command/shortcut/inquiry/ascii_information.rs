@@ -23,7 +23,7 @@ pub fn ascii_information(
     this.page_code(Some(page_code));
 
     let result: FlexibleStruct<PageHeader, u8> = this.issue_flex(0)?;
-    let remaining = result.get_body().page_length() - 1;
+    let remaining = result.get_body().page_length().saturating_sub(1);
     let result = if remaining == 0 {
         result
     } else {
@@ -32,17 +32,17 @@ pub fn ascii_information(
 
     let body = result.get_body();
 
-    unsafe {
-        Ok(AsciiInformation {
-            ascii_information: result.elements_as_slice()[..body.ascii_length() as usize]
-                .split(|c| *c == 0)
-                .map(|s| String::from_utf8_lossy(s).to_string())
-                .filter(|s| !s.is_empty())
-                .collect(),
-            vendor_information: result.elements_as_slice()[body.ascii_length() as usize..]
-                .to_owned(),
-        })
-    }
+    let elements = unsafe { result.elements_as_slice() };
+    let ascii_length = usize::min(body.ascii_length() as usize, elements.len());
+
+    Ok(AsciiInformation {
+        ascii_information: elements[..ascii_length]
+            .split(|c| *c == 0)
+            .map(|s| String::from_utf8_lossy(s).to_string())
+            .filter(|s| !s.is_empty())
+            .collect(),
+        vendor_information: elements[ascii_length..].to_owned(),
+    })
 }
 
 #[bitfield]
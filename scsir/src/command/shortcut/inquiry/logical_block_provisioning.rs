@@ -32,7 +32,12 @@ pub fn logical_block_provisioning(
     let descriptor_present = body.descriptor_present() != 0;
 
     let descriptors = if descriptor_present {
-        unsafe { Vec::from(&result.elements_as_slice()[..=body.page_length() as usize - 4]) }
+        let elements = unsafe { result.elements_as_slice() };
+        let length = usize::min(
+            (body.page_length() as usize).saturating_sub(4),
+            elements.len(),
+        );
+        Vec::from(&elements[..length])
     } else {
         vec![]
     };
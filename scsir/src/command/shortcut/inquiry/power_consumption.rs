@@ -42,7 +42,8 @@ pub fn power_consumption(this: &mut InquiryCommand) -> crate::Result<DeviceIdent
 
         let descriptor = PowerConsumptionDescriptor {
             power_consumption_identifier: item.power_consumption_identifier(),
-            power_consumption_in_microwatts: item.power_consumption_value() as u64 * multiplier,
+            power_consumption_in_microwatts: (item.power_consumption_value() as u64)
+                .saturating_mul(multiplier),
         };
 
         descriptors.push(descriptor);
@@ -8,4 +8,4 @@ pub enum ProtocolIdentifier {
     InternetScsi,
     SasSerialScsiProtocol,
     Other(u8),
-}
\ No newline at end of file
+}
@@ -36,12 +36,12 @@ pub fn standard_inquiry(this: &mut InquiryCommand) -> crate::Result<StandardInqu
     this.page_code(None);
 
     let result: FlexibleStruct<PageHeader, u8> = this.issue_flex(0)?;
-    let remaining =
-        (result.get_body().additional_length() + 5).saturating_sub(size_of::<PageHeader>() as u8);
+    let remaining = (result.get_body().additional_length() as usize + 5)
+        .saturating_sub(size_of::<PageHeader>());
     let result = if remaining == 0 {
         result
     } else {
-        this.issue_flex(remaining as usize)?
+        this.issue_flex(remaining)?
     };
 
     let body = result.get_body();
@@ -62,8 +62,8 @@ impl ModePage for EnhancedPhyControlPage {
 
         let header = EnhancedPhyControlPageHeader::from_bytes(array);
         let descriptor_count = (header.page_length() as usize
-            + size_of::<page_header::CommomSubpageHeader>()
-            - size_of::<EnhancedPhyControlPageHeader>())
+            + size_of::<page_header::CommomSubpageHeader>())
+        .saturating_sub(size_of::<EnhancedPhyControlPageHeader>())
             / size_of::<EnhancedPhyControlPageDescriptor>();
 
         let mut descriptors = vec![];
@@ -55,8 +55,8 @@ impl ModePage for LogicalBlockProvisioningPage {
 
         let header = LogicalBlockProvisioningPageHeader::from_bytes(array);
         let descriptor_count = (header.page_length() as usize
-            + size_of::<page_header::CommomSubpageHeader>()
-            - size_of::<LogicalBlockProvisioningPageHeader>())
+            + size_of::<page_header::CommomSubpageHeader>())
+        .saturating_sub(size_of::<LogicalBlockProvisioningPageHeader>())
             / size_of::<LogicalBlockProvisioningPageDescriptor>();
 
         let mut descriptors = vec![];
@@ -79,8 +79,8 @@ impl ModePage for PhyControlAndDiscoverPage {
 
         let header = PhyControlAndDiscoverPageHeader::from_bytes(array);
         let descriptor_count = (header.page_length() as usize
-            + size_of::<page_header::CommomSubpageHeader>()
-            - size_of::<PhyControlAndDiscoverPageHeader>())
+            + size_of::<page_header::CommomSubpageHeader>())
+        .saturating_sub(size_of::<PhyControlAndDiscoverPageHeader>())
             / size_of::<PhyControlAndDiscoverPageDescriptor>();
 
         let mut descriptors = vec![];
@@ -4,7 +4,11 @@ use std::mem::size_of;
 
 use modular_bitfield_msb::prelude::*;
 
-use crate::{command::bitfield_bound_check, result_data::ResultData, Command, DataDirection, Scsi};
+use crate::{
+    command::{bitfield_bound_check, issue_with_info},
+    result_data::ResultData,
+    Command, DataDirection, Scsi,
+};
 
 #[derive(Clone, Debug)]
 pub struct StreamControlCommand<'a> {
@@ -68,6 +72,8 @@ impl<'a> StreamControlCommand<'a> {
             timeout: self.timeout,
         })
     }
+
+    issue_with_info!(issue_with_info, issue(), ());
 }
 
 impl<'a> ParameterBuilder<'a> {
@@ -2,7 +2,11 @@
 
 use modular_bitfield_msb::prelude::*;
 
-use crate::{command::bitfield_bound_check, result_data::ResultData, Command, DataDirection, Scsi};
+use crate::{
+    command::{bitfield_bound_check, issue_with_info},
+    result_data::ResultData,
+    Command, DataDirection, Scsi,
+};
 
 #[derive(Clone, Debug)]
 pub struct SynchronizeCacheCommand<'a> {
@@ -96,6 +100,8 @@ impl<'a> SynchronizeCacheCommand<'a> {
         })
     }
 
+    issue_with_info!(issue_10_with_info, issue_10(), ());
+
     pub fn issue_16(&mut self) -> crate::Result<()> {
         self.error_check(64, 32)?;
 
@@ -112,6 +118,8 @@ impl<'a> SynchronizeCacheCommand<'a> {
             timeout: self.timeout,
         })
     }
+
+    issue_with_info!(issue_16_with_info, issue_16(), ());
 }
 
 impl Scsi {
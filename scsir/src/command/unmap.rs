@@ -5,7 +5,7 @@ use std::mem;
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
+    command::{bitfield_bound_check, issue_with_info},
     data_wrapper::{AnyType, FlexibleStruct},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -73,6 +73,8 @@ impl<'a> UnmapCommand<'a> {
         };
         self.interface.issue(&temp)
     }
+
+    issue_with_info!(issue_with_info, issue(), ());
 }
 
 impl<'a> ParameterBuilder<'a> {
@@ -261,5 +263,4 @@ mod tests {
             concat!("UnmapBlockDescriptor 2 comparation")
         );
     }
-
 }
@@ -1,10 +1,14 @@
 #![allow(dead_code)]
 
+use std::borrow::Cow;
+
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
-    data_wrapper::{AnyType, VecBufferWrapper},
+    command::{
+        bitfield_bound_check, device_logical_block_size, issue_with_info, select_cdb_size,
+        CdbCandidate,
+    },
     result_data::ResultData,
     Command, DataDirection, Scsi,
 };
@@ -25,7 +29,8 @@ pub struct VerifyCommand<'a> {
     expected_logical_block_application_tag: u16,
     logical_block_application_tag_mask: u16,
     logical_block_size: u32,
-    data_buffer: Vec<u8>,
+    logical_block_size_specified: bool,
+    data_buffer: Cow<'a, [u8]>,
 }
 
 impl<'a> VerifyCommand<'a> {
@@ -45,7 +50,8 @@ impl<'a> VerifyCommand<'a> {
             expected_logical_block_application_tag: 0,
             logical_block_application_tag_mask: 0,
             logical_block_size: 512,
-            data_buffer: vec![],
+            logical_block_size_specified: false,
+            data_buffer: Cow::Borrowed(&[]),
         }
     }
 
@@ -108,14 +114,24 @@ impl<'a> VerifyCommand<'a> {
         self
     }
 
+    /// 512 for the `issue_*` methods with a CDB size, the one reported by READ CAPACITY for
+    /// [`issue`](Self::issue) unless set.
     pub fn logical_block_size(&mut self, value: u32) -> &mut Self {
         self.logical_block_size = value;
+        self.logical_block_size_specified = true;
         self
     }
 
     pub fn parameter(&mut self, value: &[u8]) -> &mut Self {
-        self.data_buffer.clear();
-        self.data_buffer.extend_from_slice(value);
+        let data_buffer = self.data_buffer.to_mut();
+        data_buffer.clear();
+        data_buffer.extend_from_slice(value);
+        self
+    }
+
+    /// Like `parameter`, but `value` is transferred in place instead of being copied.
+    pub fn parameter_borrowed(&mut self, value: &'a [u8]) -> &mut Self {
+        self.data_buffer = Cow::Borrowed(value);
         self
     }
 
@@ -171,6 +187,54 @@ impl<'a> VerifyCommand<'a> {
         Ok(())
     }
 
+    /// Issues the smallest VERIFY command the fields set fit into and the device supports, see
+    /// [`Scsi::device_profile`].
+    pub fn issue(&mut self) -> crate::Result<()> {
+        if !self.logical_block_size_specified && !self.data_buffer.is_empty() {
+            self.logical_block_size = device_logical_block_size(self.interface)?;
+        }
+
+        let size = select_cdb_size(
+            self.interface,
+            "VERIFY",
+            vec![
+                CdbCandidate {
+                    size: 10,
+                    operation_code: OPERATION_CODE_10,
+                    service_action: None,
+                    fits: self.error_check(32, 16, false),
+                },
+                CdbCandidate {
+                    size: 12,
+                    operation_code: OPERATION_CODE_12,
+                    service_action: None,
+                    fits: self.error_check(32, 32, false),
+                },
+                CdbCandidate {
+                    size: 16,
+                    operation_code: OPERATION_CODE_16,
+                    service_action: None,
+                    fits: self.error_check(64, 32, false),
+                },
+                CdbCandidate {
+                    size: 32,
+                    operation_code: OPERATION_CODE_32,
+                    service_action: Some(SERVICE_ACTION_32),
+                    fits: self.error_check(64, 32, true),
+                },
+            ],
+        )?;
+
+        match size {
+            10 => self.issue_10(),
+            12 => self.issue_12(),
+            16 => self.issue_16(),
+            _ => self.issue_32(),
+        }
+    }
+
+    issue_with_info!(issue_with_info, issue(), ());
+
     pub fn issue_10(&mut self) -> crate::Result<()> {
         self.error_check(32, 16, false)?;
 
@@ -192,11 +256,13 @@ impl<'a> VerifyCommand<'a> {
 
         self.interface.issue(&ThisCommand {
             command_buffer,
-            data_buffer: self.data_buffer.clone().into(),
+            data_buffer: &self.data_buffer,
             timeout: self.timeout,
         })
     }
 
+    issue_with_info!(issue_10_with_info, issue_10(), ());
+
     pub fn issue_12(&mut self) -> crate::Result<()> {
         self.error_check(32, 32, false)?;
 
@@ -218,11 +284,13 @@ impl<'a> VerifyCommand<'a> {
 
         self.interface.issue(&ThisCommand {
             command_buffer,
-            data_buffer: self.data_buffer.clone().into(),
+            data_buffer: &self.data_buffer,
             timeout: self.timeout,
         })
     }
 
+    issue_with_info!(issue_12_with_info, issue_12(), ());
+
     pub fn issue_16(&mut self) -> crate::Result<()> {
         self.error_check(64, 32, false)?;
 
@@ -244,11 +312,13 @@ impl<'a> VerifyCommand<'a> {
 
         self.interface.issue(&ThisCommand {
             command_buffer,
-            data_buffer: self.data_buffer.clone().into(),
+            data_buffer: &self.data_buffer,
             timeout: self.timeout,
         })
     }
 
+    issue_with_info!(issue_16_with_info, issue_16(), ());
+
     pub fn issue_32(&mut self) -> crate::Result<()> {
         self.error_check(64, 32, true)?;
 
@@ -279,10 +349,12 @@ impl<'a> VerifyCommand<'a> {
 
         self.interface.issue(&ThisCommand {
             command_buffer,
-            data_buffer: self.data_buffer.clone().into(),
+            data_buffer: &self.data_buffer,
             timeout: self.timeout,
         })
     }
+
+    issue_with_info!(issue_32_with_info, issue_32(), ());
 }
 
 impl Scsi {
@@ -368,18 +440,18 @@ struct CommandBuffer32 {
     verification_length: B32,
 }
 
-struct ThisCommand<C> {
+struct ThisCommand<'b, C> {
     command_buffer: C,
-    data_buffer: VecBufferWrapper,
+    data_buffer: &'b [u8],
     timeout: Option<std::time::Duration>,
 }
 
-impl<C: Copy> Command for ThisCommand<C> {
+impl<C: Copy> Command for ThisCommand<'_, C> {
     type CommandBuffer = C;
 
-    type DataBuffer = AnyType;
+    type DataBuffer = ();
 
-    type DataBufferWrapper = VecBufferWrapper;
+    type DataBufferWrapper = ();
 
     type ReturnType = crate::Result<()>;
 
@@ -391,16 +463,18 @@ impl<C: Copy> Command for ThisCommand<C> {
         self.command_buffer
     }
 
-    fn data(&self) -> Self::DataBufferWrapper {
-        self.data_buffer.clone()
-    }
+    fn data(&self) -> Self::DataBufferWrapper {}
 
     fn timeout_override(&self) -> Option<std::time::Duration> {
         self.timeout
     }
 
     fn data_size(&self) -> u32 {
-        self.data_buffer.len() as u32
+        0
+    }
+
+    fn data_out(&self) -> &[u8] {
+        self.data_buffer
     }
 
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
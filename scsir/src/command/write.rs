@@ -1,10 +1,14 @@
 #![allow(dead_code)]
 
+use std::borrow::Cow;
+
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
-    data_wrapper::{AnyType, VecBufferWrapper},
+    command::{
+        bitfield_bound_check, device_logical_block_size, issue_with_info, select_cdb_size,
+        CdbCandidate,
+    },
     result_data::ResultData,
     Command, DataDirection, Scsi,
 };
@@ -26,7 +30,8 @@ pub struct WriteCommand<'a> {
     dld_1: bool,
     dld_2: bool,
     logical_block_size: u32,
-    data_buffer: Vec<u8>,
+    logical_block_size_specified: bool,
+    data_buffer: Cow<'a, [u8]>,
 }
 
 enum WriteCheckMode {
@@ -54,7 +59,8 @@ impl<'a> WriteCommand<'a> {
             dld_1: false,
             dld_2: false,
             logical_block_size: 512,
-            data_buffer: vec![],
+            logical_block_size_specified: false,
+            data_buffer: Cow::Borrowed(&[]),
         }
     }
 
@@ -120,8 +126,11 @@ impl<'a> WriteCommand<'a> {
         self
     }
 
+    /// 512 for the `issue_*` methods with a CDB size, the one reported by READ CAPACITY for
+    /// [`issue`](Self::issue) unless set.
     pub fn logical_block_size(&mut self, value: u32) -> &mut Self {
         self.logical_block_size = value;
+        self.logical_block_size_specified = true;
         self
     }
 
@@ -131,8 +140,15 @@ impl<'a> WriteCommand<'a> {
     }
 
     pub fn parameter(&mut self, value: &[u8]) -> &mut Self {
-        self.data_buffer.clear();
-        self.data_buffer.extend_from_slice(value);
+        let data_buffer = self.data_buffer.to_mut();
+        data_buffer.clear();
+        data_buffer.extend_from_slice(value);
+        self
+    }
+
+    /// Like `parameter`, but `value` is transferred in place instead of being copied.
+    pub fn parameter_borrowed(&mut self, value: &'a [u8]) -> &mut Self {
+        self.data_buffer = Cow::Borrowed(value);
         self
     }
 
@@ -230,7 +246,74 @@ impl<'a> WriteCommand<'a> {
         self.data_buffer.len() / self.logical_block_size as usize
     }
 
+    /// Issues the smallest WRITE command the fields set fit into and the device supports, see
+    /// [`Scsi::device_profile`].
+    pub fn issue(&mut self) -> crate::Result<()> {
+        if !self.logical_block_size_specified && !self.data_buffer.is_empty() {
+            self.logical_block_size = device_logical_block_size(self.interface)?;
+        }
+
+        let size = select_cdb_size(
+            self.interface,
+            "WRITE",
+            vec![
+                CdbCandidate {
+                    size: 6,
+                    operation_code: OPERATION_CODE_6,
+                    service_action: None,
+                    fits: self.command_6().map(drop),
+                },
+                CdbCandidate {
+                    size: 10,
+                    operation_code: OPERATION_CODE_10,
+                    service_action: None,
+                    fits: self.command_10().map(drop),
+                },
+                CdbCandidate {
+                    size: 12,
+                    operation_code: OPERATION_CODE_12,
+                    service_action: None,
+                    fits: self.command_12().map(drop),
+                },
+                CdbCandidate {
+                    size: 16,
+                    operation_code: OPERATION_CODE_16,
+                    service_action: None,
+                    fits: self.command_16().map(drop),
+                },
+                CdbCandidate {
+                    size: 32,
+                    operation_code: OPERATION_CODE_32,
+                    service_action: Some(SERVICE_ACTION_32),
+                    fits: self.command_32().map(drop),
+                },
+            ],
+        )?;
+
+        match size {
+            6 => self.issue_6(),
+            10 => self.issue_10(),
+            12 => self.issue_12(),
+            16 => self.issue_16(),
+            _ => self.issue_32(),
+        }
+    }
+
+    issue_with_info!(issue_with_info, issue(), ());
+
     pub fn issue_6(&mut self) -> crate::Result<()> {
+        let command = self.command_6()?;
+        self.interface.issue(&command)
+    }
+
+    issue_with_info!(issue_6_with_info, issue_6(), ());
+
+    /// Builds the command without issuing it, e.g. to submit it to a `CommandQueue`.
+    pub fn prepare_6(&self) -> crate::Result<impl Command<ReturnType = crate::Result<()>>> {
+        self.command_6().map(ThisCommand::into_owned)
+    }
+
+    fn command_6(&self) -> crate::Result<ThisCommand<'_, CommandBuffer6>> {
         self.error_check(0, 21, 8, WriteCheckMode::Write6)?;
 
         let transfer_length = self.data_transfer_length();
@@ -246,14 +329,26 @@ impl<'a> WriteCommand<'a> {
             .with_transfer_length(encoded_transfer_length)
             .with_control(self.control);
 
-        self.interface.issue(&ThisCommand {
+        Ok(ThisCommand {
             command_buffer,
-            data_buffer: self.data_buffer.clone().into(),
+            data_buffer: Cow::Borrowed(&self.data_buffer),
             timeout: self.timeout,
         })
     }
 
     pub fn issue_10(&mut self) -> crate::Result<()> {
+        let command = self.command_10()?;
+        self.interface.issue(&command)
+    }
+
+    issue_with_info!(issue_10_with_info, issue_10(), ());
+
+    /// Builds the command without issuing it, e.g. to submit it to a `CommandQueue`.
+    pub fn prepare_10(&self) -> crate::Result<impl Command<ReturnType = crate::Result<()>>> {
+        self.command_10().map(ThisCommand::into_owned)
+    }
+
+    fn command_10(&self) -> crate::Result<ThisCommand<'_, CommandBuffer10>> {
         self.error_check(5, 32, 16, WriteCheckMode::Standard)?;
 
         let command_buffer = CommandBuffer10::new()
@@ -266,14 +361,26 @@ impl<'a> WriteCommand<'a> {
             .with_transfer_length(self.data_transfer_length() as u16)
             .with_control(self.control);
 
-        self.interface.issue(&ThisCommand {
+        Ok(ThisCommand {
             command_buffer,
-            data_buffer: self.data_buffer.clone().into(),
+            data_buffer: Cow::Borrowed(&self.data_buffer),
             timeout: self.timeout,
         })
     }
 
     pub fn issue_12(&mut self) -> crate::Result<()> {
+        let command = self.command_12()?;
+        self.interface.issue(&command)
+    }
+
+    issue_with_info!(issue_12_with_info, issue_12(), ());
+
+    /// Builds the command without issuing it, e.g. to submit it to a `CommandQueue`.
+    pub fn prepare_12(&self) -> crate::Result<impl Command<ReturnType = crate::Result<()>>> {
+        self.command_12().map(ThisCommand::into_owned)
+    }
+
+    fn command_12(&self) -> crate::Result<ThisCommand<'_, CommandBuffer12>> {
         self.error_check(5, 32, 32, WriteCheckMode::Standard)?;
 
         let command_buffer = CommandBuffer12::new()
@@ -286,14 +393,26 @@ impl<'a> WriteCommand<'a> {
             .with_group_number(self.group_number)
             .with_control(self.control);
 
-        self.interface.issue(&ThisCommand {
+        Ok(ThisCommand {
             command_buffer,
-            data_buffer: self.data_buffer.clone().into(),
+            data_buffer: Cow::Borrowed(&self.data_buffer),
             timeout: self.timeout,
         })
     }
 
     pub fn issue_16(&mut self) -> crate::Result<()> {
+        let command = self.command_16()?;
+        self.interface.issue(&command)
+    }
+
+    issue_with_info!(issue_16_with_info, issue_16(), ());
+
+    /// Builds the command without issuing it, e.g. to submit it to a `CommandQueue`.
+    pub fn prepare_16(&self) -> crate::Result<impl Command<ReturnType = crate::Result<()>>> {
+        self.command_16().map(ThisCommand::into_owned)
+    }
+
+    fn command_16(&self) -> crate::Result<ThisCommand<'_, CommandBuffer16>> {
         self.error_check(6, 64, 32, WriteCheckMode::WithDld)?;
 
         let command_buffer = CommandBuffer16::new()
@@ -309,14 +428,26 @@ impl<'a> WriteCommand<'a> {
             .with_group_number(self.group_number)
             .with_control(self.control);
 
-        self.interface.issue(&ThisCommand {
+        Ok(ThisCommand {
             command_buffer,
-            data_buffer: self.data_buffer.clone().into(),
+            data_buffer: Cow::Borrowed(&self.data_buffer),
             timeout: self.timeout,
         })
     }
 
     pub fn issue_32(&mut self) -> crate::Result<()> {
+        let command = self.command_32()?;
+        self.interface.issue(&command)
+    }
+
+    issue_with_info!(issue_32_with_info, issue_32(), ());
+
+    /// Builds the command without issuing it, e.g. to submit it to a `CommandQueue`.
+    pub fn prepare_32(&self) -> crate::Result<impl Command<ReturnType = crate::Result<()>>> {
+        self.command_32().map(ThisCommand::into_owned)
+    }
+
+    fn command_32(&self) -> crate::Result<ThisCommand<'_, CommandBuffer32>> {
         self.error_check(5, 64, 32, WriteCheckMode::WithExpectedTags)?;
 
         let command_buffer = CommandBuffer32::new()
@@ -338,9 +469,9 @@ impl<'a> WriteCommand<'a> {
             .with_logical_block_application_tag_mask(self.logical_block_application_tag_mask)
             .with_transfer_length(self.data_transfer_length() as u32);
 
-        self.interface.issue(&ThisCommand {
+        Ok(ThisCommand {
             command_buffer,
-            data_buffer: self.data_buffer.clone().into(),
+            data_buffer: Cow::Borrowed(&self.data_buffer),
             timeout: self.timeout,
         })
     }
@@ -443,18 +574,28 @@ struct CommandBuffer32 {
     transfer_length: B32,
 }
 
-struct ThisCommand<C> {
+struct ThisCommand<'b, C> {
     command_buffer: C,
-    data_buffer: VecBufferWrapper,
+    data_buffer: Cow<'b, [u8]>,
     timeout: Option<std::time::Duration>,
 }
 
-impl<C: Copy> Command for ThisCommand<C> {
+impl<C> ThisCommand<'_, C> {
+    fn into_owned(self) -> ThisCommand<'static, C> {
+        ThisCommand {
+            command_buffer: self.command_buffer,
+            data_buffer: Cow::Owned(self.data_buffer.into_owned()),
+            timeout: self.timeout,
+        }
+    }
+}
+
+impl<C: Copy> Command for ThisCommand<'_, C> {
     type CommandBuffer = C;
 
-    type DataBuffer = AnyType;
+    type DataBuffer = ();
 
-    type DataBufferWrapper = VecBufferWrapper;
+    type DataBufferWrapper = ();
 
     type ReturnType = crate::Result<()>;
 
@@ -466,16 +607,18 @@ impl<C: Copy> Command for ThisCommand<C> {
         self.command_buffer
     }
 
-    fn data(&self) -> Self::DataBufferWrapper {
-        self.data_buffer.clone()
-    }
+    fn data(&self) -> Self::DataBufferWrapper {}
 
     fn timeout_override(&self) -> Option<std::time::Duration> {
         self.timeout
     }
 
     fn data_size(&self) -> u32 {
-        self.data_buffer.len() as u32
+        0
+    }
+
+    fn data_out(&self) -> &[u8] {
+        &self.data_buffer
     }
 
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
@@ -529,4 +672,39 @@ mod tests {
             concat!("Size of: ", stringify!(CommandBuffer32))
         );
     }
+
+    #[test]
+    fn logical_block_size_test() {
+        let scsi =
+            Scsi::with_transport("4kn", crate::emulator::EmulatedDisk::new(4096, 64).unwrap());
+
+        let data = vec![0xA5; 2 * 4096];
+        scsi.write()
+            .logical_block_address(3)
+            .parameter(&data)
+            .issue()
+            .unwrap();
+        let read = scsi
+            .read()
+            .logical_block_address(3)
+            .transfer_length(2)
+            .issue()
+            .unwrap();
+        assert_eq!(read, data);
+
+        // the caller's choice wins
+        assert!(scsi
+            .write()
+            .logical_block_size(512)
+            .parameter(&data[..512])
+            .issue()
+            .is_err());
+
+        // e.g. a drive formatted with 520 byte sectors
+        let scsi =
+            Scsi::with_transport("520", crate::emulator::EmulatedDisk::new(520, 64).unwrap());
+        let data = vec![0x5A; 2 * 520];
+        scsi.write().parameter(&data).issue().unwrap();
+        assert_eq!(scsi.read().transfer_length(2).issue().unwrap(), data);
+    }
 }
@@ -1,10 +1,11 @@
 #![allow(dead_code)]
 
+use std::borrow::Cow;
+
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
-    data_wrapper::{AnyType, VecBufferWrapper},
+    command::{bitfield_bound_check, issue_with_info},
     result_data::ResultData,
     Command, DataDirection, Scsi,
 };
@@ -23,7 +24,7 @@ pub struct WriteAndVerifyCommand<'a> {
     expected_logical_block_application_tag: u16,
     logical_block_application_tag_mask: u16,
     logical_block_size: u32,
-    data_buffer: Vec<u8>,
+    data_buffer: Cow<'a, [u8]>,
 }
 
 impl<'a> WriteAndVerifyCommand<'a> {
@@ -41,7 +42,7 @@ impl<'a> WriteAndVerifyCommand<'a> {
             expected_logical_block_application_tag: 0,
             logical_block_application_tag_mask: 0,
             logical_block_size: 512,
-            data_buffer: vec![],
+            data_buffer: Cow::Borrowed(&[]),
         }
     }
 
@@ -104,8 +105,15 @@ impl<'a> WriteAndVerifyCommand<'a> {
     }
 
     pub fn parameter(&mut self, value: &[u8]) -> &mut Self {
-        self.data_buffer.clear();
-        self.data_buffer.extend_from_slice(value);
+        let data_buffer = self.data_buffer.to_mut();
+        data_buffer.clear();
+        data_buffer.extend_from_slice(value);
+        self
+    }
+
+    /// Like `parameter`, but `value` is transferred in place instead of being copied.
+    pub fn parameter_borrowed(&mut self, value: &'a [u8]) -> &mut Self {
+        self.data_buffer = Cow::Borrowed(value);
         self
     }
 
@@ -172,11 +180,13 @@ impl<'a> WriteAndVerifyCommand<'a> {
 
         self.interface.issue(&ThisCommand {
             command_buffer,
-            data_buffer: self.data_buffer.clone().into(),
+            data_buffer: &self.data_buffer,
             timeout: self.timeout,
         })
     }
 
+    issue_with_info!(issue_10_with_info, issue_10(), ());
+
     pub fn issue_12(&mut self) -> crate::Result<()> {
         self.error_check(32, 32, false)?;
 
@@ -194,11 +204,13 @@ impl<'a> WriteAndVerifyCommand<'a> {
 
         self.interface.issue(&ThisCommand {
             command_buffer,
-            data_buffer: self.data_buffer.clone().into(),
+            data_buffer: &self.data_buffer,
             timeout: self.timeout,
         })
     }
 
+    issue_with_info!(issue_12_with_info, issue_12(), ());
+
     pub fn issue_16(&mut self) -> crate::Result<()> {
         self.error_check(64, 32, false)?;
 
@@ -216,11 +228,13 @@ impl<'a> WriteAndVerifyCommand<'a> {
 
         self.interface.issue(&ThisCommand {
             command_buffer,
-            data_buffer: self.data_buffer.clone().into(),
+            data_buffer: &self.data_buffer,
             timeout: self.timeout,
         })
     }
 
+    issue_with_info!(issue_16_with_info, issue_16(), ());
+
     pub fn issue_32(&mut self) -> crate::Result<()> {
         self.error_check(64, 32, true)?;
 
@@ -247,10 +261,12 @@ impl<'a> WriteAndVerifyCommand<'a> {
 
         self.interface.issue(&ThisCommand {
             command_buffer,
-            data_buffer: self.data_buffer.clone().into(),
+            data_buffer: &self.data_buffer,
             timeout: self.timeout,
         })
     }
+
+    issue_with_info!(issue_32_with_info, issue_32(), ());
 }
 
 impl Scsi {
@@ -336,18 +352,18 @@ struct CommandBuffer32 {
     transfer_length: B32,
 }
 
-struct ThisCommand<C> {
+struct ThisCommand<'b, C> {
     command_buffer: C,
-    data_buffer: VecBufferWrapper,
+    data_buffer: &'b [u8],
     timeout: Option<std::time::Duration>,
 }
 
-impl<C: Copy> Command for ThisCommand<C> {
+impl<C: Copy> Command for ThisCommand<'_, C> {
     type CommandBuffer = C;
 
-    type DataBuffer = AnyType;
+    type DataBuffer = ();
 
-    type DataBufferWrapper = VecBufferWrapper;
+    type DataBufferWrapper = ();
 
     type ReturnType = crate::Result<()>;
 
@@ -359,16 +375,18 @@ impl<C: Copy> Command for ThisCommand<C> {
         self.command_buffer
     }
 
-    fn data(&self) -> Self::DataBufferWrapper {
-        self.data_buffer.clone()
-    }
+    fn data(&self) -> Self::DataBufferWrapper {}
 
     fn timeout_override(&self) -> Option<std::time::Duration> {
         self.timeout
     }
 
     fn data_size(&self) -> u32 {
-        self.data_buffer.len() as u32
+        0
+    }
+
+    fn data_out(&self) -> &[u8] {
+        self.data_buffer
     }
 
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
@@ -3,7 +3,7 @@
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
+    command::{bitfield_bound_check, issue_with_info},
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -75,6 +75,8 @@ impl<'a> WriteLongCommand<'a> {
         })
     }
 
+    issue_with_info!(issue_10_with_info, issue_10(), ());
+
     pub fn issue_16(&mut self) -> crate::Result<()> {
         bitfield_bound_check!(self.data_buffer.len(), 16, "parameter length")?;
 
@@ -92,6 +94,8 @@ impl<'a> WriteLongCommand<'a> {
             timeout: self.timeout,
         })
     }
+
+    issue_with_info!(issue_16_with_info, issue_16(), ());
 }
 
 impl Scsi {
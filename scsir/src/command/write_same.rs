@@ -3,7 +3,7 @@
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
+    command::{bitfield_bound_check, issue_with_info, select_cdb_size, CdbCandidate},
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -154,6 +154,43 @@ impl<'a> WriteSameCommand<'a> {
         Ok(())
     }
 
+    /// Issues the smallest WRITE SAME command the fields set fit into and the device supports,
+    /// see [`Scsi::device_profile`].
+    pub fn issue(&mut self) -> crate::Result<()> {
+        let size = select_cdb_size(
+            self.interface,
+            "WRITE SAME",
+            vec![
+                CdbCandidate {
+                    size: 10,
+                    operation_code: OPERATION_CODE_10,
+                    service_action: None,
+                    fits: self.error_check(32, 16, false, false),
+                },
+                CdbCandidate {
+                    size: 16,
+                    operation_code: OPERATION_CODE_16,
+                    service_action: None,
+                    fits: self.error_check(64, 32, true, false),
+                },
+                CdbCandidate {
+                    size: 32,
+                    operation_code: OPERATION_CODE_32,
+                    service_action: Some(SERVICE_ACTION_32),
+                    fits: self.error_check(64, 32, true, true),
+                },
+            ],
+        )?;
+
+        match size {
+            10 => self.issue_10(),
+            16 => self.issue_16(),
+            _ => self.issue_32(),
+        }
+    }
+
+    issue_with_info!(issue_with_info, issue(), ());
+
     pub fn issue_10(&mut self) -> crate::Result<()> {
         self.error_check(32, 16, false, false)?;
 
@@ -174,6 +211,8 @@ impl<'a> WriteSameCommand<'a> {
         })
     }
 
+    issue_with_info!(issue_10_with_info, issue_10(), ());
+
     pub fn issue_16(&mut self) -> crate::Result<()> {
         self.error_check(64, 32, true, false)?;
 
@@ -195,6 +234,8 @@ impl<'a> WriteSameCommand<'a> {
         })
     }
 
+    issue_with_info!(issue_16_with_info, issue_16(), ());
+
     pub fn issue_32(&mut self) -> crate::Result<()> {
         self.error_check(64, 32, true, true)?;
 
@@ -224,6 +265,8 @@ impl<'a> WriteSameCommand<'a> {
             timeout: self.timeout,
         })
     }
+
+    issue_with_info!(issue_32_with_info, issue_32(), ());
 }
 
 impl Scsi {
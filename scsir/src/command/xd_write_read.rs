@@ -0,0 +1,332 @@
+#![allow(dead_code)]
+
+use modular_bitfield_msb::prelude::*;
+
+use crate::{
+    command::{bitfield_bound_check, issue_with_info},
+    data_wrapper::{AnyType, VecBufferWrapper},
+    result_data::ResultData,
+    Command, DataDirection, Scsi,
+};
+
+/// XDWRITEREAD is bidirectional, it needs a transport supporting separate data-in and data-out
+/// buffers, e.g. sg v4 or bsg on Linux. The sg driver of mainline kernels is version 3, so on
+/// Linux the device has to be opened by its bsg node, e.g. `/dev/bsg/0:0:0:0`, see
+/// `DeviceInfo::bsg_path`.
+#[derive(Clone, Debug)]
+pub struct XdWriteReadCommand<'a> {
+    interface: &'a Scsi,
+    timeout: Option<std::time::Duration>,
+    control: u8,
+    group_number: u8,
+    write_protect: u8,
+    disable_page_out: bool,
+    force_unit_access: bool,
+    disable_write: bool,
+    xor_protection_information: bool,
+    logical_block_address: u64,
+    logical_block_size: u32,
+    data_buffer: Vec<u8>,
+}
+
+impl<'a> XdWriteReadCommand<'a> {
+    fn new(interface: &'a Scsi) -> Self {
+        Self {
+            interface,
+            timeout: None,
+            control: 0,
+            group_number: 0,
+            write_protect: 0,
+            disable_page_out: false,
+            force_unit_access: false,
+            disable_write: false,
+            xor_protection_information: false,
+            logical_block_address: 0,
+            logical_block_size: 512,
+            data_buffer: vec![],
+        }
+    }
+
+    pub fn timeout(&mut self, timeout: std::time::Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn control(&mut self, value: u8) -> &mut Self {
+        self.control = value;
+        self
+    }
+
+    pub fn group_number(&mut self, value: u8) -> &mut Self {
+        self.group_number = value;
+        self
+    }
+
+    pub fn write_protect(&mut self, value: u8) -> &mut Self {
+        self.write_protect = value;
+        self
+    }
+
+    pub fn disable_page_out(&mut self, value: bool) -> &mut Self {
+        self.disable_page_out = value;
+        self
+    }
+
+    pub fn force_unit_access(&mut self, value: bool) -> &mut Self {
+        self.force_unit_access = value;
+        self
+    }
+
+    pub fn disable_write(&mut self, value: bool) -> &mut Self {
+        self.disable_write = value;
+        self
+    }
+
+    pub fn xor_protection_information(&mut self, value: bool) -> &mut Self {
+        self.xor_protection_information = value;
+        self
+    }
+
+    pub fn logical_block_address(&mut self, value: u64) -> &mut Self {
+        self.logical_block_address = value;
+        self
+    }
+
+    pub fn logical_block_size(&mut self, value: u32) -> &mut Self {
+        self.logical_block_size = value;
+        self
+    }
+
+    pub fn parameter(&mut self, value: &[u8]) -> &mut Self {
+        self.data_buffer.clear();
+        self.data_buffer.extend_from_slice(value);
+        self
+    }
+
+    fn error_check(
+        &self,
+        logical_block_address_bits: u32,
+        transfer_length_bits: u32,
+    ) -> crate::Result<()> {
+        bitfield_bound_check!(self.group_number, 5, "group number")?;
+        bitfield_bound_check!(self.write_protect, 3, "write protect")?;
+        bitfield_bound_check!(
+            self.logical_block_address,
+            logical_block_address_bits,
+            "logical block address"
+        )?;
+
+        if self.logical_block_size == 0 {
+            return Err(crate::Error::BadArgument(
+                "logical block size can't be 0.".to_owned(),
+            ));
+        }
+
+        if !self
+            .data_buffer
+            .len()
+            .is_multiple_of(self.logical_block_size as usize)
+        {
+            return Err(crate::Error::BadArgument(format!(
+                "parameter length should be a multiple of logical block size, which is {}.",
+                self.logical_block_size
+            )));
+        }
+
+        if self.data_buffer.len() > u32::MAX as usize {
+            return Err(crate::Error::ArgumentOutOfBounds(format!(
+                "parameter length is out of bounds. The maximum transport byte count is {}, but {} was provided.",
+                u32::MAX,
+                self.data_buffer.len()
+            )));
+        }
+
+        let transfer_length = self.data_transfer_length();
+        if transfer_length.wrapping_shr(transfer_length_bits) != 0 {
+            return Err(crate::Error::ArgumentOutOfBounds(format!(
+                "parameter length is out of bounds. The maximum possible value is {}, but {} was provided.",
+                1u128.wrapping_shl(transfer_length_bits) * self.logical_block_size as u128,
+                self.data_buffer.len()
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn data_transfer_length(&self) -> usize {
+        self.data_buffer.len() / self.logical_block_size as usize
+    }
+
+    /// Returns the XOR data
+    pub fn issue_10(&mut self) -> crate::Result<Vec<u8>> {
+        self.error_check(32, 16)?;
+
+        let command_buffer = CommandBuffer10::new()
+            .with_operation_code(OPERATION_CODE_10)
+            .with_write_protect(self.write_protect)
+            .with_disable_page_out(self.disable_page_out.into())
+            .with_force_unit_access(self.force_unit_access.into())
+            .with_disable_write(self.disable_write.into())
+            .with_xor_protection_information(self.xor_protection_information.into())
+            .with_logical_block_address(self.logical_block_address as u32)
+            .with_group_number(self.group_number)
+            .with_transfer_length(self.data_transfer_length() as u16)
+            .with_control(self.control);
+
+        self.interface.issue(&ThisCommand {
+            command_buffer,
+            data_buffer: self.data_buffer.clone(),
+            timeout: self.timeout,
+        })
+    }
+
+    issue_with_info!(issue_10_with_info, issue_10(), Vec<u8>);
+
+    /// Returns the XOR data
+    pub fn issue_32(&mut self) -> crate::Result<Vec<u8>> {
+        self.error_check(64, 32)?;
+
+        let command_buffer = CommandBuffer32::new()
+            .with_operation_code(OPERATION_CODE_32)
+            .with_control(self.control)
+            .with_group_number(self.group_number)
+            .with_additional_cdb_length(0x18)
+            .with_service_action(SERVICE_ACTION_32)
+            .with_write_protect(self.write_protect)
+            .with_disable_page_out(self.disable_page_out.into())
+            .with_force_unit_access(self.force_unit_access.into())
+            .with_disable_write(self.disable_write.into())
+            .with_xor_protection_information(self.xor_protection_information.into())
+            .with_logical_block_address(self.logical_block_address)
+            .with_transfer_length(self.data_transfer_length() as u32);
+
+        self.interface.issue(&ThisCommand {
+            command_buffer,
+            data_buffer: self.data_buffer.clone(),
+            timeout: self.timeout,
+        })
+    }
+
+    issue_with_info!(issue_32_with_info, issue_32(), Vec<u8>);
+}
+
+impl Scsi {
+    /// Needs a bsg node on Linux, see [`XdWriteReadCommand`].
+    pub fn xd_write_read(&self) -> XdWriteReadCommand<'_> {
+        XdWriteReadCommand::new(self)
+    }
+}
+
+const OPERATION_CODE_10: u8 = 0x53;
+const OPERATION_CODE_32: u8 = 0x7F;
+const SERVICE_ACTION_32: u16 = 0x0007;
+
+#[bitfield]
+#[derive(Clone, Copy)]
+struct CommandBuffer10 {
+    operation_code: B8,
+    write_protect: B3,
+    disable_page_out: B1,
+    force_unit_access: B1,
+    disable_write: B1,
+    obsolete: B1,
+    xor_protection_information: B1,
+    logical_block_address: B32,
+    reserved: B3,
+    group_number: B5,
+    transfer_length: B16,
+    control: B8,
+}
+
+#[bitfield]
+#[derive(Clone, Copy)]
+struct CommandBuffer32 {
+    operation_code: B8,
+    control: B8,
+    reserved_0: B32,
+    reserved_1: B3,
+    group_number: B5,
+    additional_cdb_length: B8,
+    service_action: B16,
+    write_protect: B3,
+    disable_page_out: B1,
+    force_unit_access: B1,
+    disable_write: B1,
+    obsolete: B1,
+    xor_protection_information: B1,
+    reserved_2: B8,
+    logical_block_address: B64,
+    reserved_3: B64,
+    transfer_length: B32,
+}
+
+struct ThisCommand<C> {
+    command_buffer: C,
+    data_buffer: Vec<u8>,
+    timeout: Option<std::time::Duration>,
+}
+
+impl<C: Copy> Command for ThisCommand<C> {
+    type CommandBuffer = C;
+
+    type DataBuffer = AnyType;
+
+    type DataBufferWrapper = VecBufferWrapper;
+
+    type ReturnType = crate::Result<Vec<u8>>;
+
+    fn direction(&self) -> DataDirection {
+        DataDirection::ToFromDevice
+    }
+
+    fn command(&self) -> Self::CommandBuffer {
+        self.command_buffer
+    }
+
+    fn data(&self) -> Self::DataBufferWrapper {
+        VecBufferWrapper(vec![0; self.data_buffer.len()])
+    }
+
+    fn data_out(&self) -> &[u8] {
+        &self.data_buffer
+    }
+
+    fn timeout_override(&self) -> Option<std::time::Duration> {
+        self.timeout
+    }
+
+    fn data_size(&self) -> u32 {
+        self.data_buffer.len() as u32
+    }
+
+    fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
+        result.check_ioctl_error()?;
+        result.check_common_error()?;
+
+        Ok(std::mem::take(result.data).0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::size_of;
+
+    const COMMAND_LENGTH_10: usize = 10;
+    const COMMAND_LENGTH_32: usize = 32;
+
+    #[test]
+    fn layout_test() {
+        assert_eq!(
+            size_of::<CommandBuffer10>(),
+            COMMAND_LENGTH_10,
+            concat!("Size of: ", stringify!(CommandBuffer10))
+        );
+
+        assert_eq!(
+            size_of::<CommandBuffer32>(),
+            COMMAND_LENGTH_32,
+            concat!("Size of: ", stringify!(CommandBuffer32))
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use std::{cell::RefCell, time::Duration};
+
+use crate::{
+    command::sense::{SenseData, SenseKey},
+    Retries, Status,
+};
+
+/// How a command completed besides its payload, see [`Scsi::with_info`](crate::Scsi::with_info)
+/// and the `issue_*_with_info` methods of the command builders.
+#[derive(Clone, Debug)]
+pub struct CompletionInfo {
+    pub(crate) status: Status,
+    pub(crate) duration: Option<Duration>,
+    pub(crate) transferred_length: usize,
+    pub(crate) residual: usize,
+    pub(crate) is_direct_io: bool,
+    pub(crate) sense: SenseData,
+    pub(crate) retries: Retries,
+}
+
+impl CompletionInfo {
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// As measured by the driver, `None` if the transport doesn't tell.
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    pub fn transferred_length(&self) -> usize {
+        self.transferred_length
+    }
+
+    /// How many bytes the device transferred less than requested, non-zero for short transfers.
+    pub fn residual(&self) -> usize {
+        self.residual
+    }
+
+    /// Whether the sg driver transferred the data directly from or to the user buffer.
+    pub fn is_direct_io(&self) -> bool {
+        self.is_direct_io
+    }
+
+    pub fn sense(&self) -> &SenseData {
+        &self.sense
+    }
+
+    /// The sense data if the command succeeded, but the device had to recover from an error.
+    pub fn recovered_error(&self) -> Option<&SenseData> {
+        match self.sense.sense_key()? {
+            SenseKey::RecoveredError => Some(&self.sense),
+            _ => None,
+        }
+    }
+
+    pub fn retries(&self) -> Retries {
+        self.retries
+    }
+}
+
+thread_local! {
+    /// The device being watched by `capture` on this thread, and what it saw last.
+    static CAPTURE: RefCell<Option<(usize, Option<CompletionInfo>)>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` and returns how the last command it issued to `device` on this thread completed.
+pub(crate) fn capture<R>(device: usize, f: impl FnOnce() -> R) -> (R, Option<CompletionInfo>) {
+    let outer = CAPTURE.with(|capture| capture.replace(Some((device, None))));
+    let result = f();
+    let captured = CAPTURE.with(|capture| capture.replace(outer));
+
+    (result, captured.and_then(|(_, info)| info))
+}
+
+pub(crate) fn is_capturing(device: usize) -> bool {
+    CAPTURE.with(|capture| matches!(&*capture.borrow(), Some((watched, _)) if *watched == device))
+}
+
+pub(crate) fn record(device: usize, info: CompletionInfo) {
+    CAPTURE.with(|capture| {
+        if let Some((watched, captured)) = &mut *capture.borrow_mut() {
+            if *watched == device {
+                *captured = Some(info);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+    use crate::{Scsi, Transport, TransportRequest, TransportResponse};
+
+    /// Transfers half of the data and reports a RECOVERED ERROR with the driver status held.
+    #[derive(Debug)]
+    struct RecoveringTransport(u16);
+
+    impl Transport for RecoveringTransport {
+        fn execute(&self, request: TransportRequest) -> io::Result<TransportResponse> {
+            let sense = [
+                0x70, 0, 0x01, 0, 0, 0, 0, 10, 0, 0, 0, 0, 0x17, 0x01, 0, 0, 0, 0,
+            ];
+            request.sense[..sense.len()].copy_from_slice(&sense);
+
+            Ok(TransportResponse {
+                status: 0x02,
+                sense_length: sense.len(),
+                driver_status: self.0,
+                residual: request.data.len() / 2,
+                duration: Some(Duration::from_millis(5)),
+                ..Default::default()
+            })
+        }
+    }
+
+    #[test]
+    fn completion_info_test() {
+        let scsi = Scsi::with_transport("recovering", RecoveringTransport(0));
+
+        let (_, info) = scsi.read().transfer_length(2).issue_10_with_info().unwrap();
+        assert_eq!(info.status(), Status::CheckCondition);
+        assert_eq!(info.duration(), Some(Duration::from_millis(5)));
+        assert_eq!(info.residual(), 512);
+        assert_eq!(info.transferred_length(), 512);
+        assert!(!info.is_direct_io());
+        assert!(info.recovered_error().is_some());
+        assert_eq!(info.retries().total(), 0);
+
+        let other = Scsi::with_transport("other", RecoveringTransport(0));
+        let result = scsi.with_info(|| other.test_unit_ready().issue());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn recovered_error_test() {
+        let scsi = Scsi::with_transport("recovering", RecoveringTransport(0x08));
+
+        // SCSI and SAT commands agree that the command completed
+        assert_eq!(
+            scsi.read().transfer_length(2).issue_10().unwrap().len(),
+            1024
+        );
+        let result = scsi.sat().flush_cache().issue_16().unwrap();
+        assert_eq!(result.sense.sense_key(), Some(SenseKey::RecoveredError));
+    }
+
+    #[test]
+    fn driver_sense_test() {
+        // DRIVER_SENSE, as sg reports along with any sense data
+        let scsi = Scsi::with_transport("recovering", RecoveringTransport(0x08));
+
+        let (data, info) = scsi.read().transfer_length(2).issue_10_with_info().unwrap();
+        assert_eq!(data.len(), 1024);
+        assert!(info.recovered_error().is_some());
+
+        // DRIVER_SENSE | DRIVER_TIMEOUT
+        let scsi = Scsi::with_transport("timeout", RecoveringTransport(0x0E));
+        assert!(scsi.read().transfer_length(2).issue_10().is_err());
+    }
+}
@@ -1,7 +1,7 @@
 use std::ffi::c_int;
 
 #[repr(i32)]
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DataDirection {
     /// e.g. a SCSI Test Unit Ready command
     None = -1,
@@ -378,6 +378,42 @@ impl From<Vec<u8>> for VecBufferWrapper {
     }
 }
 
+/// Points at memory owned by someone else, e.g. a caller's buffer, so data is transferred in
+/// place. The owner must keep the memory alive while the wrapper is in use.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SliceBufferWrapper {
+    ptr: *mut u8,
+    len: usize,
+}
+
+unsafe impl Send for SliceBufferWrapper {}
+unsafe impl Sync for SliceBufferWrapper {}
+
+impl SliceBufferWrapper {
+    pub fn new(value: &mut [u8]) -> Self {
+        Self {
+            ptr: value.as_mut_ptr(),
+            len: value.len(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl Borrow<AnyType> for SliceBufferWrapper {
+    fn borrow(&self) -> &AnyType {
+        unsafe { &*self.ptr.cast() }
+    }
+}
+
+impl BorrowMut<AnyType> for SliceBufferWrapper {
+    fn borrow_mut(&mut self) -> &mut AnyType {
+        unsafe { &mut *self.ptr.cast() }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
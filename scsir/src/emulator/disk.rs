@@ -0,0 +1,1113 @@
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom, Write},
+    sync::{Mutex, MutexGuard},
+};
+
+use crate::{
+    transport::{Transport, TransportRequest, TransportResponse},
+    DataDirection,
+};
+
+use super::{
+    pages::{self, ModePages, ALL_PAGES},
+    sense::{
+        CheckCondition, SENSE_KEY_MEDIUM_ERROR, SENSE_KEY_MISCOMPARE, STATUS_CHECK_CONDITION,
+        STATUS_GOOD,
+    },
+};
+
+pub(super) const PERIPHERAL_DEVICE_TYPE: u8 = 0x00;
+
+pub(super) struct BlockLimits {
+    pub optimal_transfer_length_granularity: u16,
+    pub maximum_transfer_length: u32,
+    pub optimal_transfer_length: u32,
+    pub maximum_unmap_lba_count: u32,
+    pub maximum_unmap_block_descriptor_count: u32,
+    pub maximum_write_same_length: u64,
+}
+
+pub(super) const BLOCK_LIMITS: BlockLimits = BlockLimits {
+    optimal_transfer_length_granularity: 8,
+    maximum_transfer_length: 0x1_0000,
+    optimal_transfer_length: 0x800,
+    maximum_unmap_lba_count: 0x10_0000,
+    maximum_unmap_block_descriptor_count: 0x100,
+    maximum_write_same_length: 0x10_0000,
+};
+
+#[derive(Clone, Debug)]
+pub(super) struct Identity {
+    pub vendor_identification: String,
+    pub product_identification: String,
+    pub product_revision_level: String,
+    pub unit_serial_number: String,
+    pub logical_unit_name: u64,
+}
+
+#[derive(Debug)]
+enum Medium {
+    Memory(Vec<u8>),
+    File(File),
+}
+
+impl Medium {
+    fn read(&mut self, offset: u64, buffer: &mut [u8]) -> io::Result<()> {
+        match self {
+            Medium::Memory(memory) => {
+                let offset = offset as usize;
+                buffer.copy_from_slice(&memory[offset..offset + buffer.len()]);
+                Ok(())
+            }
+            Medium::File(file) => {
+                file.seek(SeekFrom::Start(offset))?;
+                file.read_exact(buffer)
+            }
+        }
+    }
+
+    fn write(&mut self, offset: u64, buffer: &[u8]) -> io::Result<()> {
+        match self {
+            Medium::Memory(memory) => {
+                let offset = offset as usize;
+                memory[offset..offset + buffer.len()].copy_from_slice(buffer);
+                Ok(())
+            }
+            Medium::File(file) => {
+                file.seek(SeekFrom::Start(offset))?;
+                file.write_all(buffer)
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Medium::Memory(_) => Ok(()),
+            Medium::File(file) => file.sync_data(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    medium: Medium,
+    /// One bit per logical block, set if the block is mapped.
+    mapped: Vec<u64>,
+    mode_pages: ModePages,
+}
+
+impl State {
+    fn is_mapped(&self, lba: u64) -> bool {
+        self.mapped[(lba / 64) as usize] & (1 << (lba % 64)) != 0
+    }
+
+    fn set_mapped(&mut self, lba: u64, count: u64, mapped: bool) {
+        for lba in lba..lba + count {
+            let word = &mut self.mapped[(lba / 64) as usize];
+            match mapped {
+                true => *word |= 1 << (lba % 64),
+                false => *word &= !(1 << (lba % 64)),
+            }
+        }
+    }
+}
+
+/// An emulated direct-access block device (SBC), backed by memory or by an image file.
+///
+/// ```
+/// let disk = scsir::emulator::EmulatedDisk::new(512, 2048)?;
+/// let scsi = scsir::Scsi::with_transport("emulated", disk);
+///
+/// scsi.write().logical_block_address(1).parameter(&[0xAA; 512]).issue_10()?;
+/// let data = scsi.read().logical_block_address(1).transfer_length(1).issue_10()?;
+/// assert_eq!(data, [0xAA; 512]);
+/// # Ok::<(), scsir::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct EmulatedDisk {
+    identity: Identity,
+    logical_block_size: u32,
+    logical_block_count: u64,
+    thin_provisioning: bool,
+    state: Mutex<State>,
+}
+
+impl EmulatedDisk {
+    /// Creates a zero filled disk in memory.
+    pub fn new(logical_block_size: u32, logical_block_count: u64) -> crate::Result<Self> {
+        let size = Self::check_geometry(logical_block_size, logical_block_count)?;
+        let size = usize::try_from(size).map_err(|_| {
+            crate::Error::ArgumentOutOfBounds(format!("{} bytes can't fit in memory.", size))
+        })?;
+
+        Ok(Self::with_medium(
+            Medium::Memory(vec![0; size]),
+            logical_block_size,
+            logical_block_count,
+        ))
+    }
+
+    /// Uses an image file as the medium, the capacity is rounded down to whole logical blocks.
+    pub fn from_file(file: File, logical_block_size: u32) -> crate::Result<Self> {
+        let file_size = file.metadata()?.len();
+        let logical_block_count = file_size / logical_block_size.max(1) as u64;
+        Self::check_geometry(logical_block_size, logical_block_count)?;
+
+        Ok(Self::with_medium(
+            Medium::File(file),
+            logical_block_size,
+            logical_block_count,
+        ))
+    }
+
+    pub fn vendor_identification(&mut self, value: &str) -> &mut Self {
+        self.identity.vendor_identification = value.to_owned();
+        self
+    }
+
+    pub fn product_identification(&mut self, value: &str) -> &mut Self {
+        self.identity.product_identification = value.to_owned();
+        self
+    }
+
+    pub fn product_revision_level(&mut self, value: &str) -> &mut Self {
+        self.identity.product_revision_level = value.to_owned();
+        self
+    }
+
+    pub fn unit_serial_number(&mut self, value: &str) -> &mut Self {
+        self.identity.unit_serial_number = value.to_owned();
+        self
+    }
+
+    /// The NAA designator reported in the Device Identification VPD page.
+    pub fn logical_unit_name(&mut self, value: u64) -> &mut Self {
+        self.identity.logical_unit_name = value;
+        self
+    }
+
+    /// Enables UNMAP, WRITE SAME with UNMAP and GET LBA STATUS. Every logical block of an in
+    /// memory disk starts deallocated, and every logical block of an image file starts mapped.
+    pub fn thin_provisioning(&mut self, value: bool) -> &mut Self {
+        self.thin_provisioning = value;
+        let state = self.state.get_mut().unwrap_or_else(|e| e.into_inner());
+        let fill = match (&state.medium, value) {
+            (Medium::Memory(_), true) => 0,
+            _ => u64::MAX,
+        };
+        state.mapped.fill(fill);
+        self
+    }
+
+    pub fn logical_block_size(&self) -> u32 {
+        self.logical_block_size
+    }
+
+    pub fn logical_block_count(&self) -> u64 {
+        self.logical_block_count
+    }
+
+    fn check_geometry(logical_block_size: u32, logical_block_count: u64) -> crate::Result<u64> {
+        if logical_block_size == 0 {
+            return Err(crate::Error::BadArgument(
+                "logical block size can't be 0.".to_owned(),
+            ));
+        }
+
+        if logical_block_count == 0 {
+            return Err(crate::Error::BadArgument(
+                "logical block count can't be 0.".to_owned(),
+            ));
+        }
+
+        (logical_block_size as u64)
+            .checked_mul(logical_block_count)
+            .ok_or_else(|| {
+                crate::Error::ArgumentOutOfBounds(format!(
+                    "{} logical blocks of {} bytes is too large.",
+                    logical_block_count, logical_block_size
+                ))
+            })
+    }
+
+    fn with_medium(medium: Medium, logical_block_size: u32, logical_block_count: u64) -> Self {
+        Self {
+            identity: Identity {
+                vendor_identification: "SCSIR".to_owned(),
+                product_identification: "EMULATED DISK".to_owned(),
+                product_revision_level: "0001".to_owned(),
+                unit_serial_number: "00000001".to_owned(),
+                logical_unit_name: 0x6000_0000_0000_0001,
+            },
+            logical_block_size,
+            logical_block_count,
+            thin_provisioning: false,
+            state: Mutex::new(State {
+                medium,
+                mapped: vec![u64::MAX; logical_block_count.div_ceil(64) as usize],
+                mode_pages: ModePages::new(),
+            }),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Executes a command, returns the number of transferred bytes.
+    fn dispatch(
+        &self,
+        state: &mut State,
+        command: &[u8],
+        data: &mut [u8],
+    ) -> Result<usize, CheckCondition> {
+        let expected_length = match command[0] >> 5 {
+            0 => 6,
+            1 | 2 => 10,
+            4 => 16,
+            5 => 12,
+            _ if command[0] == VARIABLE_LENGTH && command.len() > 7 => 8 + command[7] as usize,
+            _ => return Err(CheckCondition::invalid_command_operation_code()),
+        };
+        if command.len() < expected_length {
+            return Err(CheckCondition::invalid_field_in_cdb());
+        }
+
+        match command[0] {
+            TEST_UNIT_READY => Ok(0),
+            REQUEST_SENSE => {
+                let descriptor_format = command[1] & 0x01 != 0;
+                let allocation_length = (command[4] as usize).min(data.len());
+                Ok(CheckCondition::new(0, 0, 0)
+                    .write_to(descriptor_format, &mut data[..allocation_length]))
+            }
+            INQUIRY => self.inquiry(command, data),
+            READ_CAPACITY_10 => {
+                let last_lba = u32::try_from(self.logical_block_count - 1).unwrap_or(u32::MAX);
+                let mut response = [0u8; 8];
+                response[..4].copy_from_slice(&last_lba.to_be_bytes());
+                response[4..].copy_from_slice(&self.logical_block_size.to_be_bytes());
+                Ok(copy_response(&response, data, response.len()))
+            }
+            SERVICE_ACTION_IN_16 => match command[1] & 0x1F {
+                READ_CAPACITY_16 => self.read_capacity_16(command, data),
+                GET_LBA_STATUS => self.get_lba_status(state, command, data),
+                _ => Err(CheckCondition::invalid_field_in_cdb()),
+            },
+            READ_6 => {
+                let (lba, blocks) = decode_6(command);
+                self.read(state, lba, blocks, data)
+            }
+            READ_10 => self.read(state, be(&command[2..6]), be(&command[7..9]), data),
+            READ_12 => self.read(state, be(&command[2..6]), be(&command[6..10]), data),
+            READ_16 => self.read(state, be(&command[2..10]), be(&command[10..14]), data),
+            WRITE_6 => {
+                let (lba, blocks) = decode_6(command);
+                self.write(state, lba, blocks, data)
+            }
+            WRITE_10 | WRITE_AND_VERIFY_10 => {
+                self.write(state, be(&command[2..6]), be(&command[7..9]), data)
+            }
+            WRITE_12 | WRITE_AND_VERIFY_12 => {
+                self.write(state, be(&command[2..6]), be(&command[6..10]), data)
+            }
+            WRITE_16 | WRITE_AND_VERIFY_16 => {
+                self.write(state, be(&command[2..10]), be(&command[10..14]), data)
+            }
+            VERIFY_10 => self.verify(
+                state,
+                command[1],
+                be(&command[2..6]),
+                be(&command[7..9]),
+                data,
+            ),
+            VERIFY_12 => self.verify(
+                state,
+                command[1],
+                be(&command[2..6]),
+                be(&command[6..10]),
+                data,
+            ),
+            VERIFY_16 => self.verify(
+                state,
+                command[1],
+                be(&command[2..10]),
+                be(&command[10..14]),
+                data,
+            ),
+            WRITE_SAME_10 => self.write_same(
+                state,
+                command[1] & 0b1000 != 0,
+                false,
+                be(&command[2..6]),
+                be(&command[7..9]),
+                data,
+            ),
+            WRITE_SAME_16 => self.write_same(
+                state,
+                command[1] & 0b1000 != 0,
+                command[1] & 0b0001 != 0,
+                be(&command[2..10]),
+                be(&command[10..14]),
+                data,
+            ),
+            VARIABLE_LENGTH => {
+                if command.len() < 32 || command[7] < 0x18 {
+                    return Err(CheckCondition::invalid_field_in_cdb());
+                }
+
+                let flags = command[10];
+                let lba = be(&command[12..20]);
+                let blocks = be(&command[28..32]);
+                match be(&command[8..10]) as u16 {
+                    READ_32 => self.read(state, lba, blocks, data),
+                    VERIFY_32 => self.verify(state, flags, lba, blocks, data),
+                    WRITE_32 | WRITE_AND_VERIFY_32 => self.write(state, lba, blocks, data),
+                    WRITE_SAME_32 => self.write_same(
+                        state,
+                        flags & 0b1000 != 0,
+                        flags & 0b0001 != 0,
+                        lba,
+                        blocks,
+                        data,
+                    ),
+                    _ => Err(CheckCondition::invalid_field_in_cdb()),
+                }
+            }
+            UNMAP => self.unmap(state, command, data),
+            SYNCHRONIZE_CACHE_10 => {
+                self.synchronize_cache(state, be(&command[2..6]), be(&command[7..9]))
+            }
+            SYNCHRONIZE_CACHE_16 => {
+                self.synchronize_cache(state, be(&command[2..10]), be(&command[10..14]))
+            }
+            MODE_SENSE_6 => self.mode_sense(state, command, data),
+            MODE_SENSE_10 => self.mode_sense(state, command, data),
+            MODE_SELECT_6 => self.mode_select(state, command, data),
+            MODE_SELECT_10 => self.mode_select(state, command, data),
+            LOG_SENSE => {
+                let page = pages::log_page(command[2] & 0x3F, command[3])?;
+                Ok(copy_response(&page, data, be(&command[7..9]) as usize))
+            }
+            REPORT_LUNS => {
+                let mut response = [0u8; 16];
+                response[3] = 8;
+                Ok(copy_response(&response, data, be(&command[6..10]) as usize))
+            }
+            _ => Err(CheckCondition::invalid_command_operation_code()),
+        }
+    }
+
+    fn inquiry(&self, command: &[u8], data: &mut [u8]) -> Result<usize, CheckCondition> {
+        let enable_vital_product_data = command[1] & 0x01 != 0;
+        let page_code = command[2];
+        let allocation_length = be(&command[3..5]) as usize;
+
+        let response = match enable_vital_product_data {
+            false if page_code != 0 => return Err(CheckCondition::invalid_field_in_cdb()),
+            false => pages::standard_inquiry(&self.identity),
+            true => pages::vital_product_data(page_code, &self.identity, self.thin_provisioning)?,
+        };
+
+        Ok(copy_response(&response, data, allocation_length))
+    }
+
+    fn read_capacity_16(&self, command: &[u8], data: &mut [u8]) -> Result<usize, CheckCondition> {
+        let mut response = [0u8; 32];
+        response[..8].copy_from_slice(&(self.logical_block_count - 1).to_be_bytes());
+        response[8..12].copy_from_slice(&self.logical_block_size.to_be_bytes());
+        if self.thin_provisioning {
+            // LBPME, LBPRZ
+            response[14] = 0b1100_0000;
+        }
+
+        Ok(copy_response(
+            &response,
+            data,
+            be(&command[10..14]) as usize,
+        ))
+    }
+
+    fn get_lba_status(
+        &self,
+        state: &State,
+        command: &[u8],
+        data: &mut [u8],
+    ) -> Result<usize, CheckCondition> {
+        const HEADER_LENGTH: usize = 8;
+        const DESCRIPTOR_LENGTH: usize = 16;
+
+        let starting_lba = be(&command[2..10]);
+        let allocation_length = be(&command[10..14]) as usize;
+        if starting_lba >= self.logical_block_count {
+            return Err(CheckCondition::logical_block_address_out_of_range());
+        }
+
+        let max_descriptors = allocation_length.saturating_sub(HEADER_LENGTH) / DESCRIPTOR_LENGTH;
+        let mut response = vec![0u8; HEADER_LENGTH];
+        let mut lba = starting_lba;
+        let mut descriptors = 0;
+        while lba < self.logical_block_count && descriptors < max_descriptors.max(1) {
+            let mapped = !self.thin_provisioning || state.is_mapped(lba);
+            let mut end = lba + 1;
+            while end < self.logical_block_count
+                && end - lba < u32::MAX as u64
+                && (!self.thin_provisioning || state.is_mapped(end) == mapped)
+            {
+                end += 1;
+            }
+
+            response.extend_from_slice(&lba.to_be_bytes());
+            response.extend_from_slice(&((end - lba) as u32).to_be_bytes());
+            response.extend_from_slice(&[if mapped { 0x00 } else { 0x01 }, 0, 0, 0]);
+
+            descriptors += 1;
+            lba = end;
+        }
+
+        let parameter_data_length = (response.len() - 4) as u32;
+        response[..4].copy_from_slice(&parameter_data_length.to_be_bytes());
+
+        Ok(copy_response(&response, data, allocation_length))
+    }
+
+    fn check_range(&self, lba: u64, blocks: u64) -> Result<(), CheckCondition> {
+        match lba.checked_add(blocks) {
+            Some(end) if end <= self.logical_block_count => Ok(()),
+            _ => Err(CheckCondition::logical_block_address_out_of_range()),
+        }
+    }
+
+    fn check_transfer(&self, lba: u64, blocks: u64) -> Result<usize, CheckCondition> {
+        self.check_range(lba, blocks)?;
+        if blocks > BLOCK_LIMITS.maximum_transfer_length as u64 {
+            return Err(CheckCondition::invalid_field_in_cdb());
+        }
+
+        Ok(blocks as usize * self.logical_block_size as usize)
+    }
+
+    fn read(
+        &self,
+        state: &mut State,
+        lba: u64,
+        blocks: u64,
+        data: &mut [u8],
+    ) -> Result<usize, CheckCondition> {
+        let length = self.check_transfer(lba, blocks)?.min(data.len());
+        let offset = lba * self.logical_block_size as u64;
+
+        state
+            .medium
+            .read(offset, &mut data[..length])
+            .map_err(|_| {
+                CheckCondition::new(SENSE_KEY_MEDIUM_ERROR, 0x11, 0x00).with_information(lba)
+            })?;
+
+        Ok(length)
+    }
+
+    fn write(
+        &self,
+        state: &mut State,
+        lba: u64,
+        blocks: u64,
+        data: &[u8],
+    ) -> Result<usize, CheckCondition> {
+        let length = self.check_transfer(lba, blocks)?;
+        if data.len() < length {
+            return Err(CheckCondition::invalid_field_in_cdb());
+        }
+
+        let offset = lba * self.logical_block_size as u64;
+        state.medium.write(offset, &data[..length]).map_err(|_| {
+            CheckCondition::new(SENSE_KEY_MEDIUM_ERROR, 0x0C, 0x00).with_information(lba)
+        })?;
+        if !state.mode_pages.write_cache_enabled() {
+            state.medium.flush().map_err(|_| {
+                CheckCondition::new(SENSE_KEY_MEDIUM_ERROR, 0x0C, 0x00).with_information(lba)
+            })?;
+        }
+        state.set_mapped(lba, blocks, true);
+
+        Ok(length)
+    }
+
+    fn verify(
+        &self,
+        state: &mut State,
+        flags: u8,
+        lba: u64,
+        blocks: u64,
+        data: &[u8],
+    ) -> Result<usize, CheckCondition> {
+        let length = self.check_transfer(lba, blocks)?;
+        let block_size = self.logical_block_size as usize;
+
+        let expected_length = match (flags >> 1) & 0b11 {
+            0b00 => 0,
+            0b01 => length,
+            0b11 if blocks == 0 => 0,
+            0b11 => block_size,
+            _ => return Err(CheckCondition::invalid_field_in_cdb()),
+        };
+        if data.len() < expected_length {
+            return Err(CheckCondition::invalid_field_in_cdb());
+        }
+
+        let mut medium = vec![0u8; length];
+        state
+            .medium
+            .read(lba * block_size as u64, &mut medium)
+            .map_err(|_| {
+                CheckCondition::new(SENSE_KEY_MEDIUM_ERROR, 0x11, 0x00).with_information(lba)
+            })?;
+
+        if expected_length != 0 {
+            let expected = &data[..expected_length];
+            let miscompare = medium
+                .chunks(expected_length)
+                .flat_map(|chunk| chunk.iter().zip(expected))
+                .position(|(a, b)| a != b);
+            if let Some(offset) = miscompare {
+                return Err(CheckCondition::new(SENSE_KEY_MISCOMPARE, 0x1D, 0x00)
+                    .with_information(offset as u64));
+            }
+        }
+
+        Ok(expected_length)
+    }
+
+    fn write_same(
+        &self,
+        state: &mut State,
+        unmap: bool,
+        no_data_out_buffer: bool,
+        lba: u64,
+        blocks: u64,
+        data: &[u8],
+    ) -> Result<usize, CheckCondition> {
+        let block_size = self.logical_block_size as usize;
+
+        if blocks == 0 || blocks > BLOCK_LIMITS.maximum_write_same_length {
+            return Err(CheckCondition::invalid_field_in_cdb());
+        }
+        if unmap && !self.thin_provisioning {
+            return Err(CheckCondition::invalid_field_in_cdb());
+        }
+        self.check_range(lba, blocks)?;
+
+        let block = match no_data_out_buffer {
+            true => vec![0u8; block_size],
+            false if data.len() < block_size => return Err(CheckCondition::invalid_field_in_cdb()),
+            false => data[..block_size].to_vec(),
+        };
+
+        let offset = lba * block_size as u64;
+        for index in 0..blocks {
+            state
+                .medium
+                .write(offset + index * block_size as u64, &block)
+                .map_err(|_| {
+                    CheckCondition::new(SENSE_KEY_MEDIUM_ERROR, 0x0C, 0x00)
+                        .with_information(lba + index)
+                })?;
+        }
+
+        let deallocate = unmap && block.iter().all(|&b| b == 0);
+        state.set_mapped(lba, blocks, !deallocate);
+
+        Ok(if no_data_out_buffer { 0 } else { block_size })
+    }
+
+    fn unmap(
+        &self,
+        state: &mut State,
+        command: &[u8],
+        data: &[u8],
+    ) -> Result<usize, CheckCondition> {
+        const HEADER_LENGTH: usize = 8;
+        const DESCRIPTOR_LENGTH: usize = 16;
+
+        if !self.thin_provisioning {
+            return Err(CheckCondition::invalid_command_operation_code());
+        }
+
+        let parameter_list_length = (be(&command[7..9]) as usize).min(data.len());
+        if parameter_list_length == 0 {
+            return Ok(0);
+        }
+        if parameter_list_length < HEADER_LENGTH {
+            return Err(CheckCondition::parameter_list_length_error());
+        }
+
+        let descriptor_data_length = be(&data[2..4]) as usize;
+        let descriptors = data[HEADER_LENGTH..parameter_list_length]
+            .chunks_exact(DESCRIPTOR_LENGTH)
+            .take(descriptor_data_length / DESCRIPTOR_LENGTH)
+            .map(|d| (be(&d[..8]), be(&d[8..12])))
+            .collect::<Vec<_>>();
+
+        if descriptors.len() > BLOCK_LIMITS.maximum_unmap_block_descriptor_count as usize {
+            return Err(CheckCondition::invalid_field_in_parameter_list());
+        }
+
+        for &(lba, blocks) in &descriptors {
+            self.check_range(lba, blocks)?;
+            if blocks > BLOCK_LIMITS.maximum_unmap_lba_count as u64 {
+                return Err(CheckCondition::invalid_field_in_parameter_list());
+            }
+        }
+
+        let block_size = self.logical_block_size as u64;
+        let zeros = vec![0u8; self.logical_block_size as usize];
+        for (lba, blocks) in descriptors {
+            for index in lba..lba + blocks {
+                state
+                    .medium
+                    .write(index * block_size, &zeros)
+                    .map_err(|_| {
+                        CheckCondition::new(SENSE_KEY_MEDIUM_ERROR, 0x0C, 0x00)
+                            .with_information(index)
+                    })?;
+            }
+            state.set_mapped(lba, blocks, false);
+        }
+
+        Ok(parameter_list_length)
+    }
+
+    fn synchronize_cache(
+        &self,
+        state: &mut State,
+        lba: u64,
+        blocks: u64,
+    ) -> Result<usize, CheckCondition> {
+        self.check_range(lba, blocks)?;
+        state
+            .medium
+            .flush()
+            .map_err(|_| CheckCondition::new(SENSE_KEY_MEDIUM_ERROR, 0x0C, 0x00))?;
+
+        Ok(0)
+    }
+
+    fn mode_sense(
+        &self,
+        state: &State,
+        command: &[u8],
+        data: &mut [u8],
+    ) -> Result<usize, CheckCondition> {
+        let is_6 = command[0] == MODE_SENSE_6;
+        let disable_block_descriptors = command[1] & 0b1000 != 0;
+        let long_lba_accepted = !is_6 && command[1] & 0b1_0000 != 0;
+        let page_control = command[2] >> 6;
+        let page_code = command[2] & 0x3F;
+        let subpage_code = command[3];
+        let allocation_length = match is_6 {
+            true => command[4] as usize,
+            false => be(&command[7..9]) as usize,
+        };
+
+        if subpage_code != 0 && !(page_code == ALL_PAGES && subpage_code == 0xFF) {
+            return Err(CheckCondition::invalid_field_in_cdb());
+        }
+        let pages = state.mode_pages.sense(page_control, page_code)?;
+
+        let mut descriptor = vec![];
+        if !disable_block_descriptors {
+            if long_lba_accepted {
+                descriptor.extend_from_slice(&self.logical_block_count.to_be_bytes());
+                descriptor.extend_from_slice(&[0; 4]);
+                descriptor.extend_from_slice(&self.logical_block_size.to_be_bytes());
+            } else {
+                let blocks = u32::try_from(self.logical_block_count).unwrap_or(u32::MAX);
+                descriptor.extend_from_slice(&blocks.to_be_bytes());
+                descriptor.extend_from_slice(&self.logical_block_size.to_be_bytes());
+                descriptor[4] = 0;
+            }
+        }
+
+        // DPOFUA
+        let device_specific_parameter = 0x10;
+        let mut response = match is_6 {
+            true => vec![0, 0, device_specific_parameter, descriptor.len() as u8],
+            false => {
+                let mut header = vec![0, 0, 0, device_specific_parameter];
+                header.push(long_lba_accepted as u8);
+                header.push(0);
+                header.extend_from_slice(&(descriptor.len() as u16).to_be_bytes());
+                header
+            }
+        };
+        response.extend_from_slice(&descriptor);
+        response.extend_from_slice(&pages);
+
+        match is_6 {
+            true => response[0] = (response.len() - 1) as u8,
+            false => {
+                let length = (response.len() - 2) as u16;
+                response[..2].copy_from_slice(&length.to_be_bytes());
+            }
+        }
+
+        Ok(copy_response(&response, data, allocation_length))
+    }
+
+    fn mode_select(
+        &self,
+        state: &mut State,
+        command: &[u8],
+        data: &[u8],
+    ) -> Result<usize, CheckCondition> {
+        let is_6 = command[0] == MODE_SELECT_6;
+        let page_format = command[1] & 0b1_0000 != 0;
+        let save_pages = command[1] & 0b0001 != 0;
+        let parameter_list_length = match is_6 {
+            true => command[4] as usize,
+            false => be(&command[7..9]) as usize,
+        };
+
+        if save_pages {
+            return Err(CheckCondition::saving_parameters_not_supported());
+        }
+        if parameter_list_length == 0 {
+            return Ok(0);
+        }
+        if !page_format {
+            return Err(CheckCondition::invalid_field_in_cdb());
+        }
+
+        let header_length = if is_6 { 4 } else { 8 };
+        if parameter_list_length > data.len() || parameter_list_length < header_length {
+            return Err(CheckCondition::parameter_list_length_error());
+        }
+        let parameters = &data[..parameter_list_length];
+
+        let block_descriptor_length = match is_6 {
+            true => parameters[3] as usize,
+            false => be(&parameters[6..8]) as usize,
+        };
+        let long_lba = !is_6 && parameters[4] & 0x01 != 0;
+        let pages_offset = header_length + block_descriptor_length;
+        if pages_offset > parameters.len() {
+            return Err(CheckCondition::parameter_list_length_error());
+        }
+
+        // the logical block size can't be changed
+        let descriptor_length = if long_lba { 16 } else { 8 };
+        let descriptors = parameters[header_length..pages_offset].chunks_exact(descriptor_length);
+        if !descriptors.remainder().is_empty() {
+            return Err(CheckCondition::invalid_field_in_parameter_list());
+        }
+        for descriptor in descriptors {
+            let block_length = match long_lba {
+                true => be(&descriptor[12..]),
+                false => be(&descriptor[5..]),
+            };
+            if block_length != self.logical_block_size as u64 {
+                return Err(CheckCondition::invalid_field_in_parameter_list());
+            }
+        }
+
+        state.mode_pages.select(&parameters[pages_offset..])?;
+
+        Ok(parameter_list_length)
+    }
+}
+
+impl Transport for EmulatedDisk {
+    fn execute(&self, request: TransportRequest) -> io::Result<TransportResponse> {
+        if request.command.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "empty command descriptor block",
+            ));
+        }
+
+        let mut state = self.lock();
+        let data_length = request.transfer_length();
+
+        // handlers take their parameters from the same buffer a data-in would go to
+        let mut data_out;
+        let data = match request.direction {
+            DataDirection::ToDevice => {
+                data_out = request.data_out.to_vec();
+                &mut data_out[..]
+            }
+            _ => request.data,
+        };
+
+        match self.dispatch(&mut state, request.command, data) {
+            Ok(transferred) => Ok(TransportResponse {
+                status: STATUS_GOOD,
+                residual: data_length - transferred.min(data_length),
+                ..Default::default()
+            }),
+            Err(check_condition) => Ok(TransportResponse {
+                status: STATUS_CHECK_CONDITION,
+                sense_length: check_condition
+                    .write_to(state.mode_pages.descriptor_sense(), request.sense),
+                residual: data_length,
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+fn be(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |value, &b| (value << 8) | b as u64)
+}
+
+fn decode_6(command: &[u8]) -> (u64, u64) {
+    let lba = be(&command[1..4]) & 0x1F_FFFF;
+    let blocks = match command[4] {
+        0 => 256,
+        n => n as u64,
+    };
+    (lba, blocks)
+}
+
+fn copy_response(response: &[u8], data: &mut [u8], allocation_length: usize) -> usize {
+    let length = response.len().min(allocation_length).min(data.len());
+    data[..length].copy_from_slice(&response[..length]);
+    length
+}
+
+const TEST_UNIT_READY: u8 = 0x00;
+const REQUEST_SENSE: u8 = 0x03;
+const READ_6: u8 = 0x08;
+const WRITE_6: u8 = 0x0A;
+const INQUIRY: u8 = 0x12;
+const MODE_SELECT_6: u8 = 0x15;
+const MODE_SENSE_6: u8 = 0x1A;
+const READ_CAPACITY_10: u8 = 0x25;
+const READ_10: u8 = 0x28;
+const WRITE_10: u8 = 0x2A;
+const WRITE_AND_VERIFY_10: u8 = 0x2E;
+const VERIFY_10: u8 = 0x2F;
+const SYNCHRONIZE_CACHE_10: u8 = 0x35;
+const WRITE_SAME_10: u8 = 0x41;
+const UNMAP: u8 = 0x42;
+const LOG_SENSE: u8 = 0x4D;
+const MODE_SELECT_10: u8 = 0x55;
+const MODE_SENSE_10: u8 = 0x5A;
+const VARIABLE_LENGTH: u8 = 0x7F;
+const READ_16: u8 = 0x88;
+const WRITE_16: u8 = 0x8A;
+const WRITE_AND_VERIFY_16: u8 = 0x8E;
+const VERIFY_16: u8 = 0x8F;
+const SYNCHRONIZE_CACHE_16: u8 = 0x91;
+const WRITE_SAME_16: u8 = 0x93;
+const SERVICE_ACTION_IN_16: u8 = 0x9E;
+const REPORT_LUNS: u8 = 0xA0;
+const READ_12: u8 = 0xA8;
+const WRITE_12: u8 = 0xAA;
+const WRITE_AND_VERIFY_12: u8 = 0xAE;
+const VERIFY_12: u8 = 0xAF;
+
+const READ_CAPACITY_16: u8 = 0x10;
+const GET_LBA_STATUS: u8 = 0x12;
+
+const READ_32: u16 = 0x0009;
+const VERIFY_32: u16 = 0x000A;
+const WRITE_32: u16 = 0x000B;
+const WRITE_AND_VERIFY_32: u16 = 0x000C;
+const WRITE_SAME_32: u16 = 0x000D;
+
+#[cfg(test)]
+mod tests {
+    use std::io::IoSliceMut;
+
+    use super::*;
+    use crate::{
+        command::{get_lba_status::ProvisioningStatus, shortcut},
+        DataDirection, Scsi,
+    };
+
+    fn execute(
+        disk: &EmulatedDisk,
+        command: &[u8],
+        data: &mut [u8],
+    ) -> (TransportResponse, Vec<u8>) {
+        let mut sense = [0u8; 252];
+        let response = disk
+            .execute(TransportRequest {
+                command,
+                direction: DataDirection::Unknown,
+                data,
+                data_out: &[],
+                sense: &mut sense,
+                timeout: std::time::Duration::from_secs(1),
+            })
+            .unwrap();
+        (response, sense[..response.sense_length].to_vec())
+    }
+
+    #[test]
+    fn read_write_test() {
+        let scsi = Scsi::with_transport("emulated", EmulatedDisk::new(512, 64).unwrap());
+
+        scsi.write()
+            .logical_block_address(3)
+            .parameter(&[0x5A; 1024])
+            .issue_16()
+            .unwrap();
+
+        let data = scsi
+            .read()
+            .logical_block_address(2)
+            .transfer_length(3)
+            .issue_10()
+            .unwrap();
+        assert!(data[..512].iter().all(|&b| b == 0));
+        assert!(data[512..].iter().all(|&b| b == 0x5A));
+
+        let capacity = scsi.read_capacity().issue_16().unwrap();
+        assert_eq!(capacity.returned_logical_block_address, 63);
+        assert_eq!(capacity.logical_block_length_in_bytes, 512);
+    }
+
+    #[test]
+    fn in_place_test() {
+        let scsi = Scsi::with_transport("emulated", EmulatedDisk::new(512, 64).unwrap());
+
+        let data = (0..2048).map(|i| i as u8).collect::<Vec<_>>();
+        scsi.write()
+            .logical_block_address(4)
+            .parameter_borrowed(&data)
+            .issue_16()
+            .unwrap();
+
+        let mut buffer = [0; 2560];
+        let length = scsi
+            .read()
+            .logical_block_address(4)
+            .transfer_length(4)
+            .issue_16_into(&mut buffer)
+            .unwrap();
+        assert_eq!(length, 2048);
+        assert_eq!(&buffer[..2048], &data[..]);
+
+        let (mut first, mut second) = ([0; 512], [0; 1536]);
+        let length = scsi
+            .read()
+            .logical_block_address(4)
+            .transfer_length(4)
+            .issue_10_vectored(&mut [IoSliceMut::new(&mut first), IoSliceMut::new(&mut second)])
+            .unwrap();
+        assert_eq!(length, 2048);
+        assert_eq!(&first, &data[..512]);
+        assert_eq!(&second, &data[512..]);
+
+        assert!(scsi
+            .read()
+            .transfer_length(2)
+            .issue_16_into(&mut [0; 512])
+            .is_err());
+    }
+
+    #[test]
+    fn check_condition_test() {
+        let disk = EmulatedDisk::new(512, 64).unwrap();
+
+        let (response, sense) = execute(&disk, &[0x28, 0, 0, 0, 0, 63, 0, 0, 2, 0], &mut [0; 1024]);
+        assert_eq!(response.status, STATUS_CHECK_CONDITION);
+        assert_eq!(response.residual, 1024);
+        assert_eq!(
+            (sense[0], sense[2], sense[12], sense[13]),
+            (0x70, 0x05, 0x21, 0x00)
+        );
+
+        let (response, sense) = execute(&disk, &[0xC0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0], &mut []);
+        assert_eq!(response.status, STATUS_CHECK_CONDITION);
+        assert_eq!((sense[2], sense[12]), (0x05, 0x20));
+
+        // D_SENSE switches to descriptor format
+        let mut parameters = [0u8; 16];
+        parameters[4..].copy_from_slice(&[0x0A, 0x0A, 0x06, 0, 0, 0, 0, 0, 0xFF, 0xFF, 0, 0]);
+        let (response, _) = execute(&disk, &[0x15, 0x10, 0, 0, 16, 0], &mut parameters);
+        assert_eq!(response.status, STATUS_GOOD);
+
+        let (_, sense) = execute(&disk, &[0x2F, 0x02, 0, 0, 0, 0, 0, 0, 1, 0], &mut [1; 512]);
+        assert_eq!(&sense[..4], &[0x72, 0x0E, 0x1D, 0x00]);
+
+        // a variable length CDB too short for its service action
+        let (_, sense) = execute(&disk, &[0x7F, 0, 0, 0, 0, 0, 0, 0, 0, 0x09], &mut []);
+        assert_eq!(&sense[..4], &[0x72, 0x05, 0x24, 0x00]);
+
+        // a block descriptor length that isn't a multiple of the descriptor length
+        let mut parameters = [0u8; 7];
+        parameters[3] = 3;
+        let (_, sense) = execute(&disk, &[0x15, 0x10, 0, 0, 7, 0], &mut parameters);
+        assert_eq!(&sense[..4], &[0x72, 0x05, 0x26, 0x00]);
+    }
+
+    #[test]
+    fn thin_provisioning_test() {
+        let mut disk = EmulatedDisk::new(512, 128).unwrap();
+        disk.thin_provisioning(true);
+        let scsi = Scsi::with_transport("emulated", disk);
+
+        scsi.write()
+            .logical_block_address(0)
+            .parameter(&[0xFF; 512 * 16])
+            .issue_10()
+            .unwrap();
+        scsi.unmap()
+            .parameter()
+            .add_block_descriptor(4, 4)
+            .done()
+            .unwrap()
+            .issue()
+            .unwrap();
+
+        let data = scsi
+            .read()
+            .logical_block_address(4)
+            .transfer_length(1)
+            .issue_10()
+            .unwrap();
+        assert!(data.iter().all(|&b| b == 0));
+
+        let status = scsi.get_lba_status().descriptor_length(8).issue().unwrap();
+        let descriptors = status
+            .lba_status_descriptors
+            .iter()
+            .map(|d| {
+                (
+                    d.logical_block_address,
+                    d.number_of_logical_blocks,
+                    matches!(d.provisioning_status, ProvisioningStatus::Deallocated),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            descriptors,
+            vec![(0, 4, false), (4, 4, true), (8, 8, false), (16, 112, true)]
+        );
+    }
+
+    #[test]
+    fn inquiry_test() {
+        let mut disk = EmulatedDisk::new(4096, 16).unwrap();
+        disk.vendor_identification("ACME")
+            .unit_serial_number("SN1234");
+        let scsi = Scsi::with_transport("emulated", disk);
+
+        let standard = shortcut::inquiry::standard_inquiry(&mut scsi.inquiry()).unwrap();
+        assert_eq!(standard.t10_vendor_identification.trim_end(), "ACME");
+        assert_eq!(standard.product_identification.trim_end(), "EMULATED DISK");
+
+        let serial = shortcut::inquiry::unit_serial_number(&mut scsi.inquiry()).unwrap();
+        assert_eq!(serial.product_serial_number, "SN1234");
+
+        let limits = shortcut::inquiry::block_limits(&mut scsi.inquiry()).unwrap();
+        assert!(limits.write_same_non_zero);
+        assert_eq!(
+            limits.maximum_transfer_length,
+            BLOCK_LIMITS.maximum_transfer_length
+        );
+    }
+}
@@ -0,0 +1,370 @@
+use std::{
+    io,
+    ops::Range,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+#[cfg(target_os = "linux")]
+use crate::os::linux::HostStatus;
+use crate::{command::sense::SenseKey, Transport, TransportRequest, TransportResponse};
+
+use super::sense::{CheckCondition, STATUS_CHECK_CONDITION};
+
+const STATUS_BUSY: u8 = 0x08;
+const STATUS_RESERVATION_CONFLICT: u8 = 0x18;
+const STATUS_TASK_SET_FULL: u8 = 0x28;
+
+/// A condition a [`FaultInjector`] reports instead of, or in addition to, the real outcome.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Fault {
+    /// CHECK CONDITION with sense data in fixed or descriptor format, the command isn't passed on.
+    CheckCondition {
+        sense_key: SenseKey,
+        additional_sense_code: u8,
+        additional_sense_code_qualifier: u8,
+        descriptor_format: bool,
+    },
+    Busy,
+    ReservationConflict,
+    TaskSetFull,
+    /// A host adapter failure, e.g. [`HostStatus::TimeOut`], the command isn't passed on.
+    #[cfg(target_os = "linux")]
+    Host(HostStatus),
+    /// The command is passed on, but reports at least `residual` bytes as not transferred.
+    ShortTransfer {
+        residual: usize,
+    },
+    /// The command is passed on after waiting.
+    Delay(Duration),
+}
+
+impl Fault {
+    /// CHECK CONDITION with fixed format sense data.
+    pub fn check_condition(sense_key: SenseKey, asc: u8, ascq: u8) -> Self {
+        Self::CheckCondition {
+            sense_key,
+            additional_sense_code: asc,
+            additional_sense_code_qualifier: ascq,
+            descriptor_format: false,
+        }
+    }
+}
+
+/// When a [`Fault`] is injected. Every criterion set must match, a rule without any matches every
+/// command.
+#[derive(Clone, Debug)]
+pub struct FaultRule {
+    fault: Fault,
+    operation_code: Option<u8>,
+    logical_blocks: Option<Range<u64>>,
+    command_index: Option<u64>,
+    times: Option<u64>,
+}
+
+impl FaultRule {
+    pub fn new(fault: Fault) -> Self {
+        Self {
+            fault,
+            operation_code: None,
+            logical_blocks: None,
+            command_index: None,
+            times: None,
+        }
+    }
+
+    pub fn operation_code(&mut self, value: u8) -> &mut Self {
+        self.operation_code = Some(value);
+        self
+    }
+
+    /// Matches medium access commands, e.g. READ, WRITE or VERIFY, touching any of these
+    /// logical blocks.
+    pub fn logical_blocks(&mut self, value: Range<u64>) -> &mut Self {
+        self.logical_blocks = Some(value);
+        self
+    }
+
+    /// Matches the nth command passing through the injector, counting from 0.
+    pub fn nth_command(&mut self, value: u64) -> &mut Self {
+        self.command_index = Some(value);
+        self
+    }
+
+    /// Injects the fault at most `value` times, every time by default.
+    pub fn times(&mut self, value: u64) -> &mut Self {
+        self.times = Some(value);
+        self
+    }
+
+    fn matches(&self, command: &[u8], command_index: u64) -> bool {
+        if self.times == Some(0) {
+            return false;
+        }
+
+        if let Some(operation_code) = self.operation_code {
+            if command.first() != Some(&operation_code) {
+                return false;
+            }
+        }
+
+        if let Some(index) = self.command_index {
+            if index != command_index {
+                return false;
+            }
+        }
+
+        if let Some(range) = &self.logical_blocks {
+            match logical_blocks(command) {
+                Some(blocks) if blocks.start < range.end && range.start < blocks.end => {}
+                _ => return false,
+            }
+        }
+
+        true
+    }
+}
+
+/// A [`Transport`] wrapper that makes commands fail on demand, to test error paths.
+///
+/// Wrap an emulator with [`Scsi::with_transport`](crate::Scsi::with_transport), or a real
+/// device with [`Scsi::wrap_transport`](crate::Scsi::wrap_transport). Injected sense data is
+/// parsed like the sense data of a real device.
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use scsir::{command::sense::SenseKey, emulator::*, Scsi};
+///
+/// let injector = Arc::new(FaultInjector::new(EmulatedDisk::new(512, 64)?));
+/// let scsi = Scsi::with_transport("faulty", injector.clone());
+///
+/// // UNRECOVERED READ ERROR for reads of the blocks 10 to 19
+/// let mut rule = FaultRule::new(Fault::check_condition(SenseKey::MediumError, 0x11, 0x00));
+/// rule.operation_code(0x28).logical_blocks(10..20);
+/// injector.inject(&rule);
+///
+/// assert!(scsi.read().logical_block_address(8).transfer_length(1).issue_10().is_ok());
+/// assert!(scsi.read().logical_block_address(8).transfer_length(4).issue_10().is_err());
+/// # Ok::<(), scsir::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct FaultInjector<T> {
+    inner: T,
+    rules: Mutex<Vec<FaultRule>>,
+    command_count: AtomicU64,
+}
+
+impl<T: Transport> FaultInjector<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            rules: Mutex::new(vec![]),
+            command_count: AtomicU64::new(0),
+        }
+    }
+
+    /// Adds a rule, which is checked after the rules added before it. Only the first matching
+    /// rule injects its fault.
+    pub fn inject(&self, rule: &FaultRule) {
+        self.rules.lock().unwrap().push(rule.clone());
+    }
+
+    pub fn clear(&self) {
+        self.rules.lock().unwrap().clear();
+    }
+
+    /// How many commands passed through the injector.
+    pub fn command_count(&self) -> u64 {
+        self.command_count.load(Ordering::Relaxed)
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    fn take_fault(&self, command: &[u8]) -> Option<Fault> {
+        let command_index = self.command_count.fetch_add(1, Ordering::Relaxed);
+
+        let mut rules = self.rules.lock().unwrap();
+        let rule = rules
+            .iter_mut()
+            .find(|rule| rule.matches(command, command_index))?;
+        if let Some(times) = &mut rule.times {
+            *times -= 1;
+        }
+
+        Some(rule.fault.clone())
+    }
+}
+
+impl<T: Transport> Transport for FaultInjector<T> {
+    fn execute(&self, request: TransportRequest) -> io::Result<TransportResponse> {
+        let failed = TransportResponse {
+            residual: request.transfer_length(),
+            ..Default::default()
+        };
+
+        match self.take_fault(request.command) {
+            None => self.inner.execute(request),
+            Some(Fault::CheckCondition {
+                sense_key,
+                additional_sense_code,
+                additional_sense_code_qualifier,
+                descriptor_format,
+            }) => {
+                let check_condition = CheckCondition::new(
+                    sense_key as u8,
+                    additional_sense_code,
+                    additional_sense_code_qualifier,
+                );
+                Ok(TransportResponse {
+                    status: STATUS_CHECK_CONDITION,
+                    sense_length: check_condition.write_to(descriptor_format, request.sense),
+                    ..failed
+                })
+            }
+            Some(Fault::Busy) => Ok(TransportResponse {
+                status: STATUS_BUSY,
+                ..failed
+            }),
+            Some(Fault::ReservationConflict) => Ok(TransportResponse {
+                status: STATUS_RESERVATION_CONFLICT,
+                ..failed
+            }),
+            Some(Fault::TaskSetFull) => Ok(TransportResponse {
+                status: STATUS_TASK_SET_FULL,
+                ..failed
+            }),
+            #[cfg(target_os = "linux")]
+            Some(Fault::Host(host_status)) => Ok(TransportResponse {
+                host_status: host_status.into(),
+                ..failed
+            }),
+            Some(Fault::ShortTransfer { residual }) => {
+                let length = request.transfer_length();
+                let response = self.inner.execute(request)?;
+                Ok(TransportResponse {
+                    residual: response.residual.max(residual.min(length)),
+                    ..response
+                })
+            }
+            Some(Fault::Delay(duration)) => {
+                thread::sleep(duration);
+                self.inner.execute(request)
+            }
+        }
+    }
+}
+
+/// The logical blocks a medium access command touches.
+fn logical_blocks(command: &[u8]) -> Option<Range<u64>> {
+    fn be(bytes: &[u8]) -> u64 {
+        bytes.iter().fold(0, |value, &b| value << 8 | b as u64)
+    }
+
+    let (lba, count) = match *command.first()? {
+        // READ(6), WRITE(6), a transfer length of 0 means 256 blocks
+        0x08 | 0x0A if command.len() >= 6 => {
+            let lba = be(&command[1..4]) & 0x1F_FFFF;
+            match command[4] {
+                0 => (lba, 256),
+                count => (lba, count as u64),
+            }
+        }
+        // READ(10), WRITE(10), WRITE AND VERIFY(10), VERIFY(10), WRITE SAME(10)
+        0x28 | 0x2A | 0x2E | 0x2F | 0x41 if command.len() >= 10 => {
+            (be(&command[2..6]), be(&command[7..9]))
+        }
+        // READ(12), WRITE(12), WRITE AND VERIFY(12), VERIFY(12)
+        0xA8 | 0xAA | 0xAE | 0xAF if command.len() >= 12 => {
+            (be(&command[2..6]), be(&command[6..10]))
+        }
+        // READ(16), WRITE(16), WRITE AND VERIFY(16), VERIFY(16), WRITE SAME(16)
+        0x88 | 0x8A | 0x8E | 0x8F | 0x93 if command.len() >= 16 => {
+            (be(&command[2..10]), be(&command[10..14]))
+        }
+        // READ(32), VERIFY(32), WRITE(32), WRITE AND VERIFY(32), WRITE SAME(32)
+        0x7F if command.len() >= 32 && matches!(be(&command[8..10]), 0x09..=0x0D) => {
+            (be(&command[12..20]), be(&command[28..32]))
+        }
+        _ => return None,
+    };
+
+    Some(lba..lba.saturating_add(count.max(1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{emulator::EmulatedDisk, Scsi, Status};
+
+    #[test]
+    fn fault_injection_test() {
+        let injector = std::sync::Arc::new(FaultInjector::new(EmulatedDisk::new(512, 64).unwrap()));
+        let scsi = Scsi::with_transport("faulty", injector.clone());
+
+        let mut rule = FaultRule::new(Fault::CheckCondition {
+            sense_key: SenseKey::MediumError,
+            additional_sense_code: 0x11,
+            additional_sense_code_qualifier: 0x00,
+            descriptor_format: true,
+        });
+        rule.logical_blocks(10..20);
+        injector.inject(&rule);
+
+        assert!(scsi
+            .read()
+            .logical_block_address(20)
+            .transfer_length(1)
+            .issue_16()
+            .is_ok());
+        let error = scsi
+            .read()
+            .logical_block_address(6)
+            .transfer_length(5)
+            .issue_16()
+            .unwrap_err();
+        let error = error.command_error().unwrap();
+        assert_eq!(error.sense_key(), Some(SenseKey::MediumError));
+        assert_eq!(error.asc_ascq(), Some((0x11, 0x00)));
+        assert!(matches!(
+            error.sense(),
+            crate::command::sense::SenseData::Descriptor(_)
+        ));
+
+        injector.clear();
+        let mut rule = FaultRule::new(Fault::Busy);
+        rule.nth_command(injector.command_count() + 1).times(1);
+        injector.inject(&rule);
+        assert!(scsi.test_unit_ready().issue().is_ok());
+        let error = scsi.test_unit_ready().issue().unwrap_err();
+        assert_eq!(error.command_error().unwrap().status(), Status::Busy);
+        assert!(scsi.test_unit_ready().issue().is_ok());
+
+        let mut rule = FaultRule::new(Fault::ShortTransfer { residual: 512 });
+        rule.operation_code(0x28);
+        injector.inject(&rule);
+        let (_, info) = scsi
+            .read()
+            .logical_block_address(0)
+            .transfer_length(2)
+            .issue_10_with_info()
+            .unwrap();
+        assert_eq!(info.residual(), 512);
+
+        #[cfg(target_os = "linux")]
+        {
+            injector.inject(&FaultRule::new(Fault::Host(HostStatus::TimeOut)));
+            let error = scsi.test_unit_ready().issue().unwrap_err();
+            assert_eq!(
+                error.command_error().unwrap().host_status(),
+                HostStatus::TimeOut
+            );
+        }
+    }
+}
@@ -0,0 +1,9 @@
+//! In-process SCSI targets that can be plugged into [`Scsi::with_transport`](crate::Scsi::with_transport).
+
+mod disk;
+mod fault;
+mod pages;
+mod sense;
+
+pub use disk::EmulatedDisk;
+pub use fault::{Fault, FaultInjector, FaultRule};
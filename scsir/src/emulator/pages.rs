@@ -0,0 +1,290 @@
+use super::{
+    disk::{Identity, BLOCK_LIMITS, PERIPHERAL_DEVICE_TYPE},
+    sense::CheckCondition,
+};
+
+pub(super) const SUPPORTED_VITAL_PRODUCT_DATA_PAGES: u8 = 0x00;
+pub(super) const UNIT_SERIAL_NUMBER: u8 = 0x80;
+pub(super) const DEVICE_IDENTIFICATION: u8 = 0x83;
+pub(super) const BLOCK_LIMITS_PAGE: u8 = 0xB0;
+pub(super) const BLOCK_DEVICE_CHARACTERISTICS: u8 = 0xB1;
+pub(super) const LOGICAL_BLOCK_PROVISIONING: u8 = 0xB2;
+
+const VITAL_PRODUCT_DATA_PAGES: [u8; 6] = [
+    SUPPORTED_VITAL_PRODUCT_DATA_PAGES,
+    UNIT_SERIAL_NUMBER,
+    DEVICE_IDENTIFICATION,
+    BLOCK_LIMITS_PAGE,
+    BLOCK_DEVICE_CHARACTERISTICS,
+    LOGICAL_BLOCK_PROVISIONING,
+];
+
+pub(super) fn standard_inquiry(identity: &Identity) -> Vec<u8> {
+    let mut page = vec![0u8; 96];
+    page[0] = PERIPHERAL_DEVICE_TYPE;
+    // SPC-4
+    page[2] = 0x06;
+    // HISUP, response data format 2
+    page[3] = 0x12;
+    page[4] = (page.len() - 5) as u8;
+    // CMDQUE
+    page[7] = 0x02;
+    page[8..16].copy_from_slice(&padded::<8>(&identity.vendor_identification));
+    page[16..32].copy_from_slice(&padded::<16>(&identity.product_identification));
+    page[32..36].copy_from_slice(&padded::<4>(&identity.product_revision_level));
+    // SAM-5, SPC-4, SBC-3
+    for (index, descriptor) in [0x00A0u16, 0x0460, 0x04C0].iter().enumerate() {
+        page[58 + index * 2..60 + index * 2].copy_from_slice(&descriptor.to_be_bytes());
+    }
+
+    page
+}
+
+pub(super) fn vital_product_data(
+    page_code: u8,
+    identity: &Identity,
+    thin_provisioning: bool,
+) -> Result<Vec<u8>, CheckCondition> {
+    let mut page = match page_code {
+        SUPPORTED_VITAL_PRODUCT_DATA_PAGES => {
+            let mut page = vec![0u8; 4];
+            page.extend_from_slice(&VITAL_PRODUCT_DATA_PAGES);
+            page
+        }
+        UNIT_SERIAL_NUMBER => {
+            let mut page = vec![0u8; 4];
+            page.extend_from_slice(identity.unit_serial_number.as_bytes());
+            page
+        }
+        DEVICE_IDENTIFICATION => {
+            let mut page = vec![0u8; 4];
+            // NAA, binary, associated with the logical unit
+            page.extend_from_slice(&[0x01, 0x03, 0x00, 0x08]);
+            page.extend_from_slice(&identity.logical_unit_name.to_be_bytes());
+            // T10 vendor ID based, ASCII
+            let mut t10 = padded::<8>(&identity.vendor_identification).to_vec();
+            t10.extend_from_slice(&padded::<16>(&identity.product_identification));
+            t10.extend_from_slice(identity.unit_serial_number.as_bytes());
+            page.extend_from_slice(&[0x02, 0x01, 0x00, t10.len() as u8]);
+            page.extend_from_slice(&t10);
+            page
+        }
+        BLOCK_LIMITS_PAGE => {
+            let mut page = vec![0u8; 64];
+            // WSNZ
+            page[4] = 0x01;
+            page[6..8].copy_from_slice(
+                &BLOCK_LIMITS
+                    .optimal_transfer_length_granularity
+                    .to_be_bytes(),
+            );
+            page[8..12].copy_from_slice(&BLOCK_LIMITS.maximum_transfer_length.to_be_bytes());
+            page[12..16].copy_from_slice(&BLOCK_LIMITS.optimal_transfer_length.to_be_bytes());
+            if thin_provisioning {
+                page[20..24].copy_from_slice(&BLOCK_LIMITS.maximum_unmap_lba_count.to_be_bytes());
+                page[24..28].copy_from_slice(
+                    &BLOCK_LIMITS
+                        .maximum_unmap_block_descriptor_count
+                        .to_be_bytes(),
+                );
+                page[28..32].copy_from_slice(&1u32.to_be_bytes());
+            }
+            page[36..44].copy_from_slice(&BLOCK_LIMITS.maximum_write_same_length.to_be_bytes());
+            page
+        }
+        BLOCK_DEVICE_CHARACTERISTICS => {
+            let mut page = vec![0u8; 64];
+            // non-rotating medium
+            page[4..6].copy_from_slice(&1u16.to_be_bytes());
+            page
+        }
+        LOGICAL_BLOCK_PROVISIONING => {
+            let mut page = vec![0u8; 8];
+            if thin_provisioning {
+                // LBPU, LBPWS, LBPWS10, LBPRZ = 001b
+                page[5] = 0b1110_0100;
+                // thin provisioned
+                page[6] = 0x02;
+            }
+            page
+        }
+        _ => return Err(CheckCondition::invalid_field_in_cdb()),
+    };
+
+    page[0] = PERIPHERAL_DEVICE_TYPE;
+    page[1] = page_code;
+    let page_length = (page.len() - 4) as u16;
+    page[2..4].copy_from_slice(&page_length.to_be_bytes());
+
+    Ok(page)
+}
+
+struct ModePageDefinition {
+    default: &'static [u8],
+    changeable: &'static [u8],
+}
+
+const MODE_PAGES: [ModePageDefinition; 3] = [
+    // Read-Write Error Recovery
+    ModePageDefinition {
+        default: &[
+            0x01, 0x0A, 0x80, 0x0B, 0x00, 0x00, 0x00, 0x00, 0x0B, 0x00, 0xFF, 0xFF,
+        ],
+        changeable: &[
+            0x00, 0x00, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0xFF, 0x00, 0xFF, 0xFF,
+        ],
+    },
+    // Caching, WCE and RCD are changeable
+    ModePageDefinition {
+        default: &[
+            0x08, 0x12, 0x04, 0x00, 0xFF, 0xFF, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0x80, 0x14,
+            0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00,
+        ],
+        changeable: &[
+            0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ],
+    },
+    // Control, D_SENSE is changeable
+    ModePageDefinition {
+        default: &[
+            0x0A, 0x0A, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0x00, 0x00,
+        ],
+        changeable: &[
+            0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ],
+    },
+];
+
+pub(super) const ALL_PAGES: u8 = 0x3F;
+const CONTROL_PAGE_CODE: u8 = 0x0A;
+const CACHING_PAGE_CODE: u8 = 0x08;
+
+#[derive(Debug)]
+pub(super) struct ModePages {
+    current: Vec<Vec<u8>>,
+}
+
+impl ModePages {
+    pub fn new() -> Self {
+        Self {
+            current: MODE_PAGES.iter().map(|p| p.default.to_vec()).collect(),
+        }
+    }
+
+    /// Returns the requested page(s) for MODE SENSE, `page_control` is the PC field.
+    pub fn sense(&self, page_control: u8, page_code: u8) -> Result<Vec<u8>, CheckCondition> {
+        if page_control == 0b11 {
+            return Err(CheckCondition::saving_parameters_not_supported());
+        }
+
+        let mut bytes = vec![];
+        for (index, definition) in MODE_PAGES.iter().enumerate() {
+            if page_code != ALL_PAGES && definition.default[0] != page_code {
+                continue;
+            }
+
+            match page_control {
+                0b00 => bytes.extend_from_slice(&self.current[index]),
+                0b01 => {
+                    bytes.extend_from_slice(&definition.default[..2]);
+                    bytes.extend_from_slice(&definition.changeable[2..]);
+                }
+                _ => bytes.extend_from_slice(definition.default),
+            }
+        }
+
+        if bytes.is_empty() {
+            return Err(CheckCondition::invalid_field_in_cdb());
+        }
+
+        Ok(bytes)
+    }
+
+    /// Applies the mode pages of a MODE SELECT parameter list, without the header and block
+    /// descriptors. Nothing is changed if any page is invalid.
+    pub fn select(&mut self, mut bytes: &[u8]) -> Result<(), CheckCondition> {
+        let mut updated = self.current.clone();
+
+        while !bytes.is_empty() {
+            if bytes.len() < 2 || bytes[0] & 0x40 != 0 {
+                return Err(CheckCondition::invalid_field_in_parameter_list());
+            }
+
+            let page_code = bytes[0] & 0x3F;
+            let length = bytes[1] as usize + 2;
+            let index = MODE_PAGES
+                .iter()
+                .position(|p| p.default[0] == page_code)
+                .ok_or_else(CheckCondition::invalid_field_in_parameter_list)?;
+            let definition = &MODE_PAGES[index];
+
+            if length != definition.default.len() || bytes.len() < length {
+                return Err(CheckCondition::parameter_list_length_error());
+            }
+
+            let page = &mut updated[index];
+            for offset in 2..length {
+                let mask = definition.changeable[offset];
+                if (bytes[offset] ^ page[offset]) & !mask != 0 {
+                    return Err(CheckCondition::invalid_field_in_parameter_list());
+                }
+                page[offset] = (page[offset] & !mask) | (bytes[offset] & mask);
+            }
+
+            bytes = &bytes[length..];
+        }
+
+        self.current = updated;
+
+        Ok(())
+    }
+
+    pub fn descriptor_sense(&self) -> bool {
+        self.page(CONTROL_PAGE_CODE)[2] & 0x04 != 0
+    }
+
+    pub fn write_cache_enabled(&self) -> bool {
+        self.page(CACHING_PAGE_CODE)[2] & 0x04 != 0
+    }
+
+    fn page(&self, page_code: u8) -> &[u8] {
+        let index = MODE_PAGES
+            .iter()
+            .position(|p| p.default[0] == page_code)
+            .unwrap();
+        &self.current[index]
+    }
+}
+
+pub(super) const SUPPORTED_LOG_PAGES: u8 = 0x00;
+pub(super) const TEMPERATURE: u8 = 0x0D;
+
+pub(super) fn log_page(page_code: u8, subpage_code: u8) -> Result<Vec<u8>, CheckCondition> {
+    if subpage_code != 0 {
+        return Err(CheckCondition::invalid_field_in_cdb());
+    }
+
+    let mut page = vec![page_code, 0x00, 0x00, 0x00];
+    match page_code {
+        SUPPORTED_LOG_PAGES => page.extend_from_slice(&[SUPPORTED_LOG_PAGES, TEMPERATURE]),
+        TEMPERATURE => {
+            // temperature 35 C, reference temperature 70 C
+            page.extend_from_slice(&[0x00, 0x00, 0x03, 0x02, 0x00, 35]);
+            page.extend_from_slice(&[0x00, 0x01, 0x03, 0x02, 0x00, 70]);
+        }
+        _ => return Err(CheckCondition::invalid_field_in_cdb()),
+    }
+
+    let page_length = (page.len() - 4) as u16;
+    page[2..4].copy_from_slice(&page_length.to_be_bytes());
+
+    Ok(page)
+}
+
+fn padded<const N: usize>(value: &str) -> [u8; N] {
+    let mut bytes = [b' '; N];
+    let length = value.len().min(N);
+    bytes[..length].copy_from_slice(&value.as_bytes()[..length]);
+    bytes
+}
@@ -0,0 +1,76 @@
+use crate::command::sense::{SenseDataBuilder, SenseKey};
+
+pub(super) const STATUS_GOOD: u8 = 0x00;
+pub(super) const STATUS_CHECK_CONDITION: u8 = 0x02;
+
+pub(super) const SENSE_KEY_MEDIUM_ERROR: u8 = 0x3;
+pub(super) const SENSE_KEY_ILLEGAL_REQUEST: u8 = 0x5;
+pub(super) const SENSE_KEY_MISCOMPARE: u8 = 0xE;
+
+/// A CHECK CONDITION the emulated device server wants to report.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct CheckCondition {
+    pub sense_key: u8,
+    pub additional_sense_code: u8,
+    pub additional_sense_code_qualifier: u8,
+    pub information: Option<u64>,
+}
+
+impl CheckCondition {
+    pub const fn new(sense_key: u8, asc: u8, ascq: u8) -> Self {
+        Self {
+            sense_key,
+            additional_sense_code: asc,
+            additional_sense_code_qualifier: ascq,
+            information: None,
+        }
+    }
+
+    pub const fn with_information(mut self, information: u64) -> Self {
+        self.information = Some(information);
+        self
+    }
+
+    pub const fn invalid_command_operation_code() -> Self {
+        Self::new(SENSE_KEY_ILLEGAL_REQUEST, 0x20, 0x00)
+    }
+
+    pub const fn logical_block_address_out_of_range() -> Self {
+        Self::new(SENSE_KEY_ILLEGAL_REQUEST, 0x21, 0x00)
+    }
+
+    pub const fn invalid_field_in_cdb() -> Self {
+        Self::new(SENSE_KEY_ILLEGAL_REQUEST, 0x24, 0x00)
+    }
+
+    pub const fn parameter_list_length_error() -> Self {
+        Self::new(SENSE_KEY_ILLEGAL_REQUEST, 0x1A, 0x00)
+    }
+
+    pub const fn invalid_field_in_parameter_list() -> Self {
+        Self::new(SENSE_KEY_ILLEGAL_REQUEST, 0x26, 0x00)
+    }
+
+    pub const fn saving_parameters_not_supported() -> Self {
+        Self::new(SENSE_KEY_ILLEGAL_REQUEST, 0x39, 0x00)
+    }
+
+    /// Writes the sense data in fixed or descriptor format, returns the written length.
+    pub fn write_to(&self, descriptor_format: bool, buffer: &mut [u8]) -> usize {
+        let mut builder = SenseDataBuilder::new(
+            SenseKey::from(self.sense_key & 0x0F),
+            self.additional_sense_code,
+            self.additional_sense_code_qualifier,
+        );
+        if let Some(information) = self.information {
+            builder.information(information);
+        }
+
+        // a single information descriptor always fits
+        let sense = builder.to_bytes(descriptor_format).unwrap();
+
+        let length = sense.len().min(buffer.len());
+        buffer[..length].copy_from_slice(&sense[..length]);
+        length
+    }
+}
@@ -1,12 +1,20 @@
-use std::{any::Any, io, path::PathBuf};
+use std::{fmt::Display, io, path::PathBuf};
 
 use thiserror::Error;
 
+use crate::{
+    command::sense::{SenseData, SenseKey},
+    result_data::Status,
+};
+
+#[cfg(target_os = "linux")]
+use crate::os::linux::{DriverStatus, HostStatus};
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug, Error)]
-pub enum Error<T = Box<dyn Any>> {
-    #[error("{0} is not a block device.")]
+pub enum Error<T = CommandError> {
+    #[error("{0} is not a block device or an SCSI Generic character device.")]
     NotBlockDevice(PathBuf),
     #[error("{0} is not an SCSI Generic device, or old SCSI Generic driver.")]
     NotScsiDevice(PathBuf),
@@ -14,10 +22,145 @@ pub enum Error<T = Box<dyn Any>> {
     ArgumentOutOfBounds(String),
     #[error("Bad argument: {0}")]
     BadArgument(String),
-    #[error("Check condition: {0:?}")]
+    /// The command reached the device, but failed, e.g. with CHECK CONDITION, BUSY, or an error
+    /// of the host adapter or driver.
+    #[error("Check condition: {0}")]
     CheckCondition(T),
     #[error("{0:?}")]
     Other(String),
     #[error("{0}")]
     IO(#[from] io::Error),
 }
+
+impl Error {
+    /// The failure reported by the device, if this is one.
+    pub fn command_error(&self) -> Option<&CommandError> {
+        match self {
+            Error::CheckCondition(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Everything known about a failed command.
+#[derive(Clone, Debug)]
+pub struct CommandError {
+    pub(crate) status: Status,
+    #[cfg(target_os = "linux")]
+    pub(crate) host_status: HostStatus,
+    #[cfg(target_os = "linux")]
+    pub(crate) driver_status: DriverStatus,
+    pub(crate) sense: SenseData,
+    pub(crate) command: Vec<u8>,
+    pub(crate) transferred_length: usize,
+}
+
+impl CommandError {
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn host_status(&self) -> HostStatus {
+        self.host_status
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn driver_status(&self) -> DriverStatus {
+        self.driver_status
+    }
+
+    pub fn sense(&self) -> &SenseData {
+        &self.sense
+    }
+
+    /// The command descriptor block that was issued.
+    pub fn command(&self) -> &[u8] {
+        &self.command
+    }
+
+    /// Number of data bytes transferred before the command failed.
+    pub fn transferred_length(&self) -> usize {
+        self.transferred_length
+    }
+
+    pub fn sense_key(&self) -> Option<SenseKey> {
+        self.sense.sense_key()
+    }
+
+    /// The additional sense code and its qualifier.
+    pub fn asc_ascq(&self) -> Option<(u8, u8)> {
+        Some((self.sense.asc()?, self.sense.ascq()?))
+    }
+
+    /// Whether issuing the same command again may succeed, e.g. after a UNIT ATTENTION, a bus
+    /// reset or a BUSY status.
+    pub fn is_retryable(&self) -> bool {
+        #[cfg(target_os = "linux")]
+        {
+            if matches!(
+                self.host_status,
+                HostStatus::BusBusy
+                    | HostStatus::Reset
+                    | HostStatus::SoftError
+                    | HostStatus::ImmediateRetry
+                    | HostStatus::Requeue
+            ) {
+                return true;
+            }
+
+            if self.driver_status.contains(DriverStatus::RETRY)
+                && !self.driver_status.contains(DriverStatus::ABORT)
+            {
+                return true;
+            }
+        }
+
+        match self.status {
+            Status::Busy | Status::TaskSetFull | Status::TaskAborted => return true,
+            Status::CheckCondition => {}
+            _ => return false,
+        }
+
+        match (self.sense_key(), self.asc_ascq()) {
+            (Some(SenseKey::UnitAttention | SenseKey::AbortedCommand), _) => true,
+            // LOGICAL UNIT IS IN PROCESS OF BECOMING READY
+            (Some(SenseKey::NotReady), Some((0x04, 0x01))) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "command {:02X?} failed. ", self.command)?;
+
+        #[cfg(target_os = "linux")]
+        {
+            if !matches!(self.host_status, HostStatus::Ok) {
+                write!(f, "host status: {:?}. ", self.host_status)?;
+            }
+
+            if !self.driver_status.is_empty() {
+                write!(f, "driver status: {:?}. ", self.driver_status)?;
+            }
+        }
+
+        write!(f, "Status: {:?}.", self.status)?;
+
+        match &self.sense {
+            SenseData::Fixed(sense) => write!(
+                f,
+                " Sense key: {:?}, {}",
+                sense.sense_key, sense.additional_sense_code
+            ),
+            SenseData::Descriptor(sense) => write!(
+                f,
+                " Sense key: {:?}, {}",
+                sense.sense_key, sense.additional_sense_code
+            ),
+            SenseData::Raw(sense) => write!(f, " Sense data: {:02X?}", sense),
+            SenseData::None => Ok(()),
+        }
+    }
+}
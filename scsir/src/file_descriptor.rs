@@ -36,6 +36,14 @@ impl FileDescriptor {
         Ok(file_type.is_block_device())
     }
 
+    #[cfg(target_os = "linux")]
+    pub fn is_char(&self) -> crate::Result<bool> {
+        use std::os::unix::prelude::FileTypeExt;
+
+        let file_type = self.file.metadata()?.file_type();
+        Ok(file_type.is_char_device())
+    }
+
     #[cfg(target_os = "windows")]
     pub fn is_block(&self) -> crate::Result<bool> {
         use std::io;
@@ -92,7 +100,18 @@ impl FileDescriptor {
         self.file.as_raw_handle()
     }
 
+    pub fn metadata(&self) -> std::io::Result<std::fs::Metadata> {
+        self.file.metadata()
+    }
+
     pub fn path(&self) -> &String {
         &self.path
     }
 }
+
+#[cfg(target_os = "linux")]
+impl std::os::unix::prelude::AsRawFd for FileDescriptor {
+    fn as_raw_fd(&self) -> std::os::unix::prelude::RawFd {
+        self.raw()
+    }
+}
@@ -0,0 +1,224 @@
+#[cfg(target_os = "linux")]
+use std::{fs, path::Path};
+
+#[cfg(target_os = "linux")]
+use crate::hex::parse_hex;
+use crate::{
+    hex::hex,
+    shortcut::inquiry::{device_identification, unit_serial_number, Association, Identifier},
+    Scsi,
+};
+
+/// EUI-64 and NAA designators, the ones `/dev/disk/by-id/wwn-*` links are named after.
+const EUI_64: u8 = 0x2;
+const NAA: u8 = 0x3;
+
+impl Scsi {
+    /// The NAA and EUI-64 designators of the logical unit, from the Device Identification VPD
+    /// page.
+    pub fn designators(&self) -> crate::Result<Vec<Vec<u8>>> {
+        let page = device_identification(&mut self.inquiry())?;
+
+        Ok(page
+            .descriptors
+            .into_iter()
+            .filter(|descriptor| {
+                matches!(
+                    descriptor.association,
+                    Association::AddressedPhysicalOrLogicalDevice
+                ) && matches!(descriptor.identifier_type, EUI_64 | NAA)
+            })
+            .filter_map(|descriptor| match descriptor.identifier {
+                Identifier::Binary(bytes) => Some(bytes),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// The Unit Serial Number VPD page, without padding.
+    pub fn serial_number(&self) -> crate::Result<String> {
+        let page = unit_serial_number(&mut self.inquiry())?;
+
+        Ok(page.product_serial_number.trim().to_owned())
+    }
+
+    /// Fails unless the device reports `designator`, see [`designators`](Self::designators).
+    pub fn verify_designator(&self, designator: &[u8]) -> crate::Result<()> {
+        let designators = self.designators()?;
+        if designators.iter().any(|d| d == designator) {
+            return Ok(());
+        }
+
+        Err(crate::Error::Other(format!(
+            "{} reports the designators [{}], not {}.",
+            self.path().display(),
+            designators
+                .iter()
+                .map(|d| hex(d))
+                .collect::<Vec<_>>()
+                .join(", "),
+            hex(designator)
+        )))
+    }
+
+    pub fn verify_serial_number(&self, serial_number: &str) -> crate::Result<()> {
+        let reported = self.serial_number()?;
+        if reported == serial_number.trim() {
+            return Ok(());
+        }
+
+        Err(crate::Error::Other(format!(
+            "{} reports the serial number {:?}, not {:?}.",
+            self.path().display(),
+            reported,
+            serial_number
+        )))
+    }
+
+    /// Opens the device reporting `designator`, e.g. `0x5000c500a1b2c3d4u64.to_be_bytes()`,
+    /// whatever its name is.
+    #[cfg(target_os = "linux")]
+    pub fn open_by_designator(designator: &[u8]) -> crate::Result<Scsi> {
+        Self::open_matching(&format!("designator {}", hex(designator)), |scsi| {
+            scsi.verify_designator(designator)
+        })
+    }
+
+    /// Opens the device reporting `serial_number` in the Unit Serial Number VPD page.
+    #[cfg(target_os = "linux")]
+    pub fn open_by_serial_number(serial_number: &str) -> crate::Result<Scsi> {
+        Self::open_matching(&format!("serial number {:?}", serial_number), |scsi| {
+            scsi.verify_serial_number(serial_number)
+        })
+    }
+
+    /// Opens a `/dev/disk/by-id` link, given by its name, e.g. `wwn-0x5000c500a1b2c3d4`, or by
+    /// its path.
+    ///
+    /// The device must report the designator of `wwn-`, `scsi-2` and `scsi-3` links, and the
+    /// serial number of `scsi-S` links, and the link must point to the same node after opening.
+    /// Other links, e.g. `ata-` or `usb-` ones, can't be verified and are rejected.
+    #[cfg(target_os = "linux")]
+    pub fn open_by_id<P: AsRef<Path> + ?Sized>(id: &P) -> crate::Result<Scsi> {
+        let id = id.as_ref();
+        let link = match id.parent() {
+            Some(parent) if parent.as_os_str().is_empty() => Path::new("/dev/disk/by-id").join(id),
+            _ => id.to_owned(),
+        };
+        let name = link
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        if !["wwn-0x", "scsi-2", "scsi-3", "scsi-S"]
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+        {
+            return Err(crate::Error::BadArgument(format!(
+                "{} can't be verified, only wwn-, scsi-2, scsi-3 and scsi-S links can.",
+                name
+            )));
+        }
+
+        let node = fs::canonicalize(&link)?;
+        let scsi = Scsi::new(&node)?;
+        if fs::canonicalize(&link)? != node {
+            return Err(crate::Error::Other(format!(
+                "{} changed while {} was opened.",
+                link.display(),
+                node.display()
+            )));
+        }
+
+        let designator = name
+            .strip_prefix("wwn-0x")
+            .or_else(|| name.strip_prefix("scsi-3"))
+            .or_else(|| name.strip_prefix("scsi-2"));
+        if let Some(designator) = designator {
+            let designator = parse_hex(designator)
+                .filter(|designator| !designator.is_empty())
+                .ok_or_else(|| {
+                    crate::Error::BadArgument(format!("{} has an invalid designator.", name))
+                })?;
+            scsi.verify_designator(&designator)?;
+        } else if let Some(name) = name.strip_prefix("scsi-S") {
+            // udev names these after the vendor, the product and the serial number
+            let serial_number = scsi.serial_number()?.replace(' ', "_");
+            if serial_number.is_empty() || !name.ends_with(&serial_number) {
+                return Err(crate::Error::Other(format!(
+                    "{} reports the serial number {:?}, which doesn't match {}.",
+                    node.display(),
+                    serial_number,
+                    link.display()
+                )));
+            }
+        }
+
+        Ok(scsi)
+    }
+
+    /// Opens every device found in sysfs until `verify` accepts one.
+    #[cfg(target_os = "linux")]
+    fn open_matching(
+        identity: &str,
+        verify: impl Fn(&Scsi) -> crate::Result<()>,
+    ) -> crate::Result<Scsi> {
+        for device in crate::os::linux::Enumerator::new().enumerate()? {
+            // devices we may not open or that don't answer are somebody else's
+            if let Ok(scsi) = device.open() {
+                if verify(&scsi).is_ok() {
+                    return Ok(scsi);
+                }
+            }
+        }
+
+        Err(crate::Error::Other(format!(
+            "no device with the {} was found.",
+            identity
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{emulator::EmulatedDisk, Scsi};
+
+    #[test]
+    fn verify_identity_test() {
+        let mut disk = EmulatedDisk::new(512, 64).unwrap();
+        disk.unit_serial_number("SN1234  ")
+            .logical_unit_name(0x5000_c500_a1b2_c3d4);
+        let scsi = Scsi::with_transport("disk", disk);
+
+        assert_eq!(
+            scsi.designators().unwrap(),
+            vec![0x5000_c500_a1b2_c3d4u64.to_be_bytes().to_vec()]
+        );
+        assert!(scsi
+            .verify_designator(&0x5000_c500_a1b2_c3d4u64.to_be_bytes())
+            .is_ok());
+        assert!(scsi
+            .verify_designator(&0x5000_c500_a1b2_c3d5u64.to_be_bytes())
+            .is_err());
+
+        assert_eq!(scsi.serial_number().unwrap(), "SN1234");
+        assert!(scsi.verify_serial_number("SN1234").is_ok());
+        assert!(scsi.verify_serial_number("SN12345").is_err());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn open_by_id_test() {
+        for id in [
+            "ata-WDC_WD40EFRX-68N32N0_WD-WCC7K0000000",
+            "usb-Generic_Flash_Disk_0123456789-0:0",
+            "scsi-0ATA_WDC_WD40EFRX-68N_WD-WCC7K0000000",
+            "scsi-1ATA_WDC_WD40EFRX-68N32N0_WD-WCC7K0000000",
+        ] {
+            assert!(matches!(
+                Scsi::open_by_id(id),
+                Err(crate::Error::BadArgument(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,421 @@
+use std::{fmt::Debug, io, path::Path, time::Duration};
+
+#[cfg(feature = "log")]
+use crate::hex::hex;
+use crate::{command::sense::SenseData, DataDirection, TransportResponse};
+
+/// Observes, and may alter or veto, every command a [`Scsi`](crate::Scsi) sends to its
+/// transport, see [`Scsi::add_interceptor`](crate::Scsi::add_interceptor).
+///
+/// Both hooks run once per attempt, so a retried command is seen several times.
+pub trait Interceptor: Debug + Send + Sync {
+    /// Called before the command is handed to the transport. Returning an error vetoes the
+    /// command, it is reported by `issue` as an I/O error and never reaches the device.
+    fn before(&self, command: &mut PendingCommand) -> io::Result<()> {
+        let _ = command;
+        Ok(())
+    }
+
+    /// Called when the command completed, was vetoed, or couldn't be delivered.
+    fn after(&self, command: &CompletedCommand) {
+        let _ = command;
+    }
+}
+
+/// A command about to be sent, its command descriptor block and timeout can be changed.
+#[derive(Debug)]
+pub struct PendingCommand<'a> {
+    pub(crate) path: &'a Path,
+    pub(crate) command: Vec<u8>,
+    pub(crate) direction: DataDirection,
+    pub(crate) data_out: &'a [u8],
+    pub(crate) timeout: Duration,
+}
+
+impl PendingCommand<'_> {
+    pub fn path(&self) -> &Path {
+        self.path
+    }
+
+    pub fn command(&self) -> &[u8] {
+        &self.command
+    }
+
+    pub fn command_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.command
+    }
+
+    pub fn direction(&self) -> DataDirection {
+        self.direction
+    }
+
+    /// The bytes sent to the device, empty for commands without data-out.
+    pub fn data_out(&self) -> &[u8] {
+        self.data_out
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+}
+
+/// The outcome of a command, as it was sent after every interceptor had its say.
+#[derive(Debug)]
+pub struct CompletedCommand<'a> {
+    pub(crate) path: &'a Path,
+    pub(crate) command: &'a [u8],
+    pub(crate) direction: DataDirection,
+    pub(crate) data_out: &'a [u8],
+    pub(crate) data_in: &'a [u8],
+    pub(crate) timeout: Duration,
+    pub(crate) response: Result<&'a TransportResponse, &'a io::Error>,
+    pub(crate) sense: &'a SenseData,
+    pub(crate) sense_bytes: &'a [u8],
+}
+
+impl CompletedCommand<'_> {
+    pub fn path(&self) -> &Path {
+        self.path
+    }
+
+    pub fn command(&self) -> &[u8] {
+        self.command
+    }
+
+    pub fn direction(&self) -> DataDirection {
+        self.direction
+    }
+
+    pub fn data_out(&self) -> &[u8] {
+        self.data_out
+    }
+
+    /// The bytes received from the device, empty for vectored commands.
+    pub fn data_in(&self) -> &[u8] {
+        self.data_in
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// The status, residual, duration and so on reported by the transport, or why the command
+    /// couldn't be delivered.
+    pub fn response(&self) -> Result<&TransportResponse, &io::Error> {
+        self.response
+    }
+
+    pub fn sense(&self) -> &SenseData {
+        self.sense
+    }
+
+    /// The sense data as the device returned it.
+    pub fn sense_bytes(&self) -> &[u8] {
+        self.sense_bytes
+    }
+}
+
+/// The name of the command in `command`, e.g. `"READ(10)"`, `None` for opcodes this crate
+/// doesn't know.
+pub fn operation_name(command: &[u8]) -> Option<&'static str> {
+    let operation_code = *command.first()?;
+    let service_action = command.get(1).map(|b| b & 0b0001_1111);
+
+    let name = match (operation_code, service_action) {
+        (0x00, _) => "TEST UNIT READY",
+        (0x01, _) => "REZERO UNIT",
+        (0x03, _) => "REQUEST SENSE",
+        (0x04, _) => "FORMAT UNIT",
+        (0x07, _) => "REASSIGN BLOCKS",
+        (0x08, _) => "READ(6)",
+        (0x0A, _) => "WRITE(6)",
+        (0x0B, _) => "SEEK(6)",
+        (0x12, _) => "INQUIRY",
+        (0x15, _) => "MODE SELECT(6)",
+        (0x1A, _) => "MODE SENSE(6)",
+        (0x1B, _) => "START STOP UNIT",
+        (0x1C, _) => "RECEIVE DIAGNOSTIC RESULTS",
+        (0x1D, _) => "SEND DIAGNOSTIC",
+        (0x25, _) => "READ CAPACITY(10)",
+        (0x28, _) => "READ(10)",
+        (0x2A, _) => "WRITE(10)",
+        (0x2B, _) => "SEEK(10)",
+        (0x2E, _) => "WRITE AND VERIFY(10)",
+        (0x2F, _) => "VERIFY(10)",
+        (0x35, _) => "SYNCHRONIZE CACHE(10)",
+        (0x37, _) => "READ DEFECT DATA(10)",
+        (0x3B, _) => "WRITE BUFFER",
+        (0x3C, _) => "READ BUFFER(10)",
+        (0x3E, _) => "READ LONG(10)",
+        (0x3F, _) => "WRITE LONG(10)",
+        (0x40, _) => "CHANGE DEFINITION",
+        (0x41, _) => "WRITE SAME(10)",
+        (0x42, _) => "UNMAP",
+        (0x43, _) => "READ TOC/PMA/ATIP",
+        (0x48, _) => "SANITIZE",
+        (0x4C, _) => "LOG SELECT",
+        (0x4D, _) => "LOG SENSE",
+        (0x53, _) => "XDWRITEREAD(10)",
+        (0x55, _) => "MODE SELECT(10)",
+        (0x5A, _) => "MODE SENSE(10)",
+        (0x5E, _) => "PERSISTENT RESERVE IN",
+        (0x5F, _) => "PERSISTENT RESERVE OUT",
+        (0x7F, _) => match command.get(8..10) {
+            Some([0x00, 0x09]) => "READ(32)",
+            Some([0x00, 0x0A]) => "VERIFY(32)",
+            Some([0x00, 0x0B]) => "WRITE(32)",
+            Some([0x00, 0x0C]) => "WRITE AND VERIFY(32)",
+            Some([0x00, 0x0D]) => "WRITE SAME(32)",
+            _ => "VARIABLE LENGTH",
+        },
+        (0x83, _) => "EXTENDED COPY",
+        (0x84, _) => "RECEIVE COPY RESULTS",
+        (0x85, _) => "ATA PASS-THROUGH(16)",
+        (0x88, _) => "READ(16)",
+        (0x89, _) => "COMPARE AND WRITE",
+        (0x8A, _) => "WRITE(16)",
+        (0x8E, _) => "WRITE AND VERIFY(16)",
+        (0x8F, _) => "VERIFY(16)",
+        (0x91, _) => "SYNCHRONIZE CACHE(16)",
+        (0x93, _) => "WRITE SAME(16)",
+        (0x94, Some(0x01)) => "CLOSE ZONE",
+        (0x94, Some(0x02)) => "FINISH ZONE",
+        (0x94, Some(0x03)) => "OPEN ZONE",
+        (0x94, Some(0x04)) => "RESET WRITE POINTER",
+        (0x95, Some(0x00)) => "REPORT ZONES",
+        (0x9A, _) => "WRITE STREAM(16)",
+        (0x9B, _) => "READ BUFFER(16)",
+        (0x9C, _) => "WRITE ATOMIC(16)",
+        (0x9E, Some(0x10)) => "READ CAPACITY(16)",
+        (0x9E, Some(0x12)) => "GET LBA STATUS",
+        (0x9E, Some(0x16)) => "GET STREAM STATUS",
+        (0x9E, Some(0x1A)) => "BACKGROUND CONTROL",
+        (0x9F, Some(0x11)) => "WRITE LONG(16)",
+        (0x9F, Some(0x14)) => "STREAM CONTROL",
+        (0xA0, _) => "REPORT LUNS",
+        (0xA1, _) => "ATA PASS-THROUGH(12)",
+        (0xA2, _) => "SECURITY PROTOCOL IN",
+        (0xA3, Some(0x05)) => "REPORT IDENTIFYING INFORMATION",
+        (0xA3, Some(0x0C)) => "REPORT SUPPORTED OPERATION CODES",
+        (0xA3, Some(0x0D)) => "REPORT SUPPORTED TASK MANAGEMENT FUNCTIONS",
+        (0xA3, Some(0x0F)) => "REPORT TIMESTAMP",
+        (0xA4, Some(0x06)) => "SET IDENTIFYING INFORMATION",
+        (0xA4, Some(0x0F)) => "SET TIMESTAMP",
+        (0xA8, _) => "READ(12)",
+        (0xAA, _) => "WRITE(12)",
+        (0xAE, _) => "WRITE AND VERIFY(12)",
+        (0xAF, _) => "VERIFY(12)",
+        (0xB5, _) => "SECURITY PROTOCOL OUT",
+        (0xB7, _) => "READ DEFECT DATA(12)",
+        _ => return None,
+    };
+
+    Some(name)
+}
+
+/// Logs every command through the `log` crate: the command descriptor block in hex with the
+/// name of the command before it is sent, the status, residual, duration and sense afterwards.
+/// Data bytes are logged at [`Trace`](log::Level::Trace) level.
+#[cfg(feature = "log")]
+#[derive(Clone, Debug)]
+pub struct LogInterceptor {
+    level: log::Level,
+}
+
+#[cfg(feature = "log")]
+impl LogInterceptor {
+    /// Logs at [`Debug`](log::Level::Debug) level.
+    pub fn new() -> Self {
+        Self {
+            level: log::Level::Debug,
+        }
+    }
+
+    pub fn level(&mut self, value: log::Level) -> &mut Self {
+        self.level = value;
+        self
+    }
+}
+
+#[cfg(feature = "log")]
+impl Default for LogInterceptor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "log")]
+impl Interceptor for LogInterceptor {
+    fn before(&self, command: &mut PendingCommand) -> io::Result<()> {
+        log::log!(
+            self.level,
+            "{}: {} [{}] {:?}, timeout {:?}",
+            command.path.display(),
+            operation_name(&command.command).unwrap_or("UNKNOWN"),
+            hex(&command.command),
+            command.direction,
+            command.timeout
+        );
+        if !command.data_out.is_empty() {
+            log::trace!("data-out [{}]", hex(command.data_out));
+        }
+
+        Ok(())
+    }
+
+    fn after(&self, command: &CompletedCommand) {
+        let name = operation_name(command.command).unwrap_or("UNKNOWN");
+        let response = match command.response {
+            Ok(response) => response,
+            Err(error) => {
+                log::log!(
+                    self.level,
+                    "{}: {} wasn't delivered: {}",
+                    command.path.display(),
+                    name,
+                    error
+                );
+                return;
+            }
+        };
+
+        let sense = match command.sense {
+            SenseData::Fixed(sense) => {
+                format!(", {:?}, {}", sense.sense_key, sense.additional_sense_code)
+            }
+            SenseData::Descriptor(sense) => {
+                format!(", {:?}, {}", sense.sense_key, sense.additional_sense_code)
+            }
+            SenseData::Raw(raw) => format!(", sense [{}]", hex(raw)),
+            SenseData::None => String::new(),
+        };
+        log::log!(
+            self.level,
+            "{}: {} status {:?}, host status {:#04x}, driver status {:#04x}, residual {}, duration {:?}{}",
+            command.path.display(),
+            name,
+            crate::Status::from(response.status),
+            response.host_status,
+            response.driver_status,
+            response.residual,
+            response.duration,
+            sense
+        );
+        if !command.data_in.is_empty() {
+            log::trace!("data-in [{}]", hex(command.data_in));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::{Scsi, Transport, TransportRequest};
+
+    #[derive(Debug, Default)]
+    struct EchoTransport;
+
+    impl Transport for EchoTransport {
+        fn execute(&self, request: TransportRequest) -> io::Result<TransportResponse> {
+            request.data.fill(request.command[0]);
+            Ok(TransportResponse {
+                duration: Some(Duration::from_millis(1)),
+                ..Default::default()
+            })
+        }
+    }
+
+    #[derive(Debug)]
+    struct Seen {
+        command: Vec<u8>,
+        data_in: Vec<u8>,
+        is_delivered: bool,
+    }
+
+    /// Blocks FORMAT UNIT and turns every READ(10) into a READ(16) opcode.
+    #[derive(Debug, Default)]
+    struct GuardInterceptor {
+        seen: Arc<Mutex<Vec<Seen>>>,
+    }
+
+    impl Interceptor for GuardInterceptor {
+        fn before(&self, command: &mut PendingCommand) -> io::Result<()> {
+            match command.command()[0] {
+                0x04 => Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    "FORMAT UNIT is blocked",
+                )),
+                0x28 => {
+                    command.command_mut()[0] = 0x88;
+                    Ok(())
+                }
+                _ => Ok(()),
+            }
+        }
+
+        fn after(&self, command: &CompletedCommand) {
+            self.seen.lock().unwrap().push(Seen {
+                command: command.command().to_vec(),
+                data_in: command.data_in().to_vec(),
+                is_delivered: command.response().is_ok(),
+            });
+        }
+    }
+
+    #[test]
+    fn interceptor_test() {
+        let interceptor = GuardInterceptor::default();
+        let seen = interceptor.seen.clone();
+        let mut scsi = Scsi::with_transport("echo", EchoTransport);
+        scsi.add_interceptor(interceptor);
+
+        let error = scsi.format_unit().issue().unwrap_err();
+        assert!(
+            matches!(error, crate::Error::IO(e) if e.kind() == io::ErrorKind::PermissionDenied)
+        );
+
+        let mut buffer = [0u8; 512];
+        scsi.read()
+            .transfer_length(1)
+            .issue_10_into(&mut buffer)
+            .unwrap();
+        assert!(buffer.iter().all(|&b| b == 0x88));
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 2);
+        assert_eq!(seen[0].command[0], 0x04);
+        assert!(!seen[0].is_delivered);
+        assert_eq!(operation_name(&seen[1].command), Some("READ(16)"));
+        assert_eq!(seen[1].data_in.len(), 512);
+        assert!(seen[1].is_delivered);
+    }
+
+    #[test]
+    fn rewritten_command_test() {
+        #[derive(Debug)]
+        struct BusyTransport;
+
+        impl Transport for BusyTransport {
+            fn execute(&self, _: TransportRequest) -> io::Result<TransportResponse> {
+                Ok(TransportResponse {
+                    status: 0x08,
+                    ..Default::default()
+                })
+            }
+        }
+
+        let mut scsi = Scsi::with_transport("busy", BusyTransport);
+        scsi.add_interceptor(GuardInterceptor::default());
+
+        let error = scsi.read().transfer_length(1).issue_10().unwrap_err();
+        let error = error.command_error().unwrap();
+        assert_eq!(error.status(), crate::Status::Busy);
+        assert_eq!(operation_name(error.command()), Some("READ(16)"));
+    }
+}
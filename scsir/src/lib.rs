@@ -1,19 +1,51 @@
 // modular_bitfield_msb generates fields that trip unused_parens; keep this crate clean.
 #![allow(unused_parens)]
 
+mod aligned_buffer;
+#[cfg(all(target_os = "linux", feature = "tokio"))]
+mod asynchronous;
 pub mod command;
+mod completion_info;
 mod data_direction;
 mod data_wrapper;
+pub mod emulator;
 mod error;
 mod file_descriptor;
-mod os;
+mod hex;
+mod identity;
+mod interceptor;
+pub mod os;
+mod profile;
+mod progress;
+#[cfg(target_os = "linux")]
+mod queue;
 mod result_data;
+mod retry;
 mod scsi;
+mod scsi_address;
+pub mod trace;
+mod transfer;
+mod transport;
 
+pub use aligned_buffer::AlignedBuffer;
+#[cfg(all(target_os = "linux", feature = "tokio"))]
+pub use asynchronous::AsyncScsi;
 pub use command::shortcut;
 pub use command::Command;
+pub use completion_info::CompletionInfo;
 pub use data_direction::DataDirection;
-pub use error::{Error, Result};
-pub use result_data::ResultData;
+pub use error::{CommandError, Error, Result};
+#[cfg(feature = "log")]
+pub use interceptor::LogInterceptor;
+pub use interceptor::{operation_name, CompletedCommand, Interceptor, PendingCommand};
+pub use profile::{AtomicWriteLimits, DeviceProfile, SupportedOperation, ZonedModel};
+pub use progress::{Operation, Progress, ProgressEvent, ProgressMonitor};
+#[cfg(target_os = "linux")]
+pub use queue::{CommandQueue, Completion};
+pub use result_data::{ResultData, Status};
+pub use retry::{Retries, RetryCategory, RetryPolicy};
+pub use transfer::{Chunks, TransferLimits};
+pub use transport::{Transport, TransportRequest, TransportResponse};
 
 pub use scsi::Scsi;
+pub use scsi_address::ScsiAddress;
@@ -0,0 +1,429 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{Scsi, ScsiAddress};
+
+/// How a device is attached to the host, as far as sysfs tells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TransportKind {
+    Sas,
+    FibreChannel,
+    Iscsi,
+    Usb,
+    Ata,
+    Unknown,
+}
+
+/// A SCSI device found in sysfs, see [`Enumerator`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeviceInfo {
+    pub address: ScsiAddress,
+    /// 0x00 for disks, 0x01 for tapes, 0x05 for CD/DVD drives, see the standard inquiry data.
+    pub peripheral_device_type: u8,
+    pub vendor: String,
+    pub model: String,
+    pub revision: String,
+    /// The sd, sr or st node, e.g. `sda`, `sr0` or `st0`.
+    pub device_node: Option<String>,
+    /// The sg node, e.g. `sg0`.
+    pub generic_node: Option<String>,
+    /// The bsg node, e.g. `0:0:0:0`, in `/dev/bsg`.
+    pub bsg_node: Option<String>,
+    pub transport: TransportKind,
+    pub logical_block_size: Option<u32>,
+    /// In bytes, only known for block devices.
+    pub capacity: Option<u64>,
+}
+
+impl DeviceInfo {
+    /// The sg node if there is one, which can issue any command, the sd, sr or st node otherwise.
+    pub fn path(&self) -> Option<PathBuf> {
+        self.generic_path().or_else(|| self.device_path())
+    }
+
+    pub fn device_path(&self) -> Option<PathBuf> {
+        self.device_node
+            .as_ref()
+            .map(|node| Path::new("/dev").join(node))
+    }
+
+    pub fn generic_path(&self) -> Option<PathBuf> {
+        self.generic_node
+            .as_ref()
+            .map(|node| Path::new("/dev").join(node))
+    }
+
+    /// The bsg node takes sg v4 headers, which bidirectional commands and CDBs longer than 255
+    /// bytes need, while the sg driver of mainline kernels only takes v3 ones.
+    pub fn bsg_path(&self) -> Option<PathBuf> {
+        self.bsg_node
+            .as_ref()
+            .map(|node| Path::new("/dev/bsg").join(node))
+    }
+
+    /// Opens [`path`](Self::path).
+    pub fn open(&self) -> crate::Result<Scsi> {
+        Scsi::new(&self.path().ok_or_else(|| self.no_node())?)
+    }
+
+    pub fn open_readonly(&self) -> crate::Result<Scsi> {
+        Scsi::new_readonly(&self.path().ok_or_else(|| self.no_node())?)
+    }
+
+    fn no_node(&self) -> crate::Error {
+        let ScsiAddress {
+            host,
+            channel,
+            target,
+            lun,
+        } = self.address;
+        crate::Error::Other(format!(
+            "the device {}:{}:{}:{} has no device node.",
+            host, channel, target, lun
+        ))
+    }
+}
+
+/// Finds SCSI devices by walking `/sys/class/scsi_device`, `/sys/class/scsi_generic`,
+/// `/sys/class/scsi_tape`, `/sys/class/bsg` and `/sys/block`.
+///
+/// ```no_run
+/// use scsir::os::linux::{Enumerator, TransportKind};
+///
+/// for device in Enumerator::new()
+///     .peripheral_device_type(0x00)
+///     .transport(TransportKind::Usb)
+///     .enumerate()?
+/// {
+///     println!("{:?} {} {}", device.path(), device.vendor, device.model);
+/// }
+/// # Ok::<(), scsir::Error>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct Enumerator {
+    sysfs: PathBuf,
+    peripheral_device_type: Option<u8>,
+    transport: Option<TransportKind>,
+}
+
+impl Default for Enumerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Enumerator {
+    pub fn new() -> Self {
+        Self {
+            sysfs: PathBuf::from("/sys"),
+            peripheral_device_type: None,
+            transport: None,
+        }
+    }
+
+    /// Where sysfs is mounted, `/sys` by default.
+    pub fn sysfs_root<P: AsRef<Path> + ?Sized>(&mut self, value: &P) -> &mut Self {
+        self.sysfs = value.as_ref().to_owned();
+        self
+    }
+
+    pub fn peripheral_device_type(&mut self, value: u8) -> &mut Self {
+        self.peripheral_device_type = Some(value);
+        self
+    }
+
+    pub fn transport(&mut self, value: TransportKind) -> &mut Self {
+        self.transport = Some(value);
+        self
+    }
+
+    /// The matching devices, ordered by address.
+    pub fn enumerate(&self) -> crate::Result<Vec<DeviceInfo>> {
+        let generic_nodes = self.class_devices("class/scsi_generic")?;
+        let bsg_nodes = self.class_devices("class/bsg")?;
+        let tape_nodes = self.class_devices("class/scsi_tape")?;
+        let block_nodes = self.class_devices("block")?;
+
+        let mut devices = vec![];
+        for (name, device) in self.class_devices("class/scsi_device")? {
+            let address = match parse_address(&name) {
+                Some(address) => address,
+                None => continue,
+            };
+
+            let peripheral_device_type =
+                match read_attribute(&device, "type").and_then(|value| value.parse().ok()) {
+                    Some(value) => value,
+                    None => continue,
+                };
+            if self
+                .peripheral_device_type
+                .is_some_and(|value| value != peripheral_device_type)
+            {
+                continue;
+            }
+
+            let transport = transport_kind(&device);
+            if self.transport.is_some_and(|value| value != transport) {
+                continue;
+            }
+
+            let find = |nodes: &[(String, PathBuf)]| {
+                nodes
+                    .iter()
+                    .find(|(_, node_device)| *node_device == device)
+                    .map(|(node, _)| node.clone())
+            };
+            // only the rewinding st node, not nst or the st*l/m/a mode nodes
+            let tape_node = tape_nodes
+                .iter()
+                .filter(|(node, _)| {
+                    node.strip_prefix("st")
+                        .is_some_and(|number| number.bytes().all(|b| b.is_ascii_digit()))
+                })
+                .find(|(_, node_device)| *node_device == device)
+                .map(|(node, _)| node.clone());
+            let device_node = find(&block_nodes).or(tape_node);
+
+            let (logical_block_size, capacity) = match &device_node {
+                Some(node) if block_nodes.iter().any(|(name, _)| name == node) => {
+                    let block = self.sysfs.join("block").join(node);
+                    let logical_block_size = read_attribute(&block, "queue/logical_block_size")
+                        .and_then(|value| value.parse().ok());
+                    // always in 512 byte sectors, whatever the logical block size is
+                    let capacity = read_attribute(&block, "size")
+                        .and_then(|value| value.parse::<u64>().ok())
+                        .map(|sectors| sectors * 512);
+                    (logical_block_size, capacity)
+                }
+                _ => (None, None),
+            };
+
+            devices.push(DeviceInfo {
+                address,
+                peripheral_device_type,
+                vendor: read_attribute(&device, "vendor").unwrap_or_default(),
+                model: read_attribute(&device, "model").unwrap_or_default(),
+                revision: read_attribute(&device, "rev").unwrap_or_default(),
+                device_node,
+                generic_node: find(&generic_nodes),
+                bsg_node: find(&bsg_nodes),
+                transport,
+                logical_block_size,
+                capacity,
+            });
+        }
+
+        devices.sort_by_key(|device| {
+            let address = device.address;
+            (address.host, address.channel, address.target, address.lun)
+        });
+
+        Ok(devices)
+    }
+
+    /// The entries of a class directory, with the resolved path of their SCSI device.
+    fn class_devices(&self, class: &str) -> crate::Result<Vec<(String, PathBuf)>> {
+        let entries = match fs::read_dir(self.sysfs.join(class)) {
+            Ok(entries) => entries,
+            // no devices of this class were ever attached
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut devices = vec![];
+        for entry in entries {
+            let entry = entry?;
+            // e.g. loop and nvme block devices don't link to a SCSI device
+            if let Ok(device) = fs::canonicalize(entry.path().join("device")) {
+                devices.push((entry.file_name().to_string_lossy().into_owned(), device));
+            }
+        }
+
+        Ok(devices)
+    }
+}
+
+fn parse_address(name: &str) -> Option<ScsiAddress> {
+    let mut parts = name.split(':');
+    let address = ScsiAddress {
+        host: parts.next()?.parse().ok()?,
+        channel: parts.next()?.parse().ok()?,
+        target: parts.next()?.parse().ok()?,
+        lun: parts.next()?.parse().ok()?,
+    };
+
+    match parts.next() {
+        Some(_) => None,
+        None => Some(address),
+    }
+}
+
+fn read_attribute(directory: &Path, name: &str) -> Option<String> {
+    fs::read_to_string(directory.join(name))
+        .ok()
+        .map(|value| value.trim().to_owned())
+}
+
+/// Guessed from the devices between the host controller and the SCSI device.
+fn transport_kind(device: &Path) -> TransportKind {
+    let components: Vec<_> = device
+        .components()
+        .filter_map(|component| component.as_os_str().to_str())
+        .collect();
+    let any = |f: fn(&str) -> bool| components.iter().any(|component| f(component));
+    let numbered = |component: &str, prefix: &str| {
+        component
+            .strip_prefix(prefix)
+            .is_some_and(|rest| !rest.is_empty() && rest.bytes().all(|b| b.is_ascii_digit()))
+    };
+
+    if any(|component| component.starts_with("usb")) {
+        TransportKind::Usb
+    } else if any(|component| component.starts_with("session")) {
+        TransportKind::Iscsi
+    } else if any(|component| component.starts_with("rport-")) {
+        TransportKind::FibreChannel
+    } else if any(|component| component.starts_with("end_device-")) {
+        TransportKind::Sas
+    } else if components
+        .iter()
+        .any(|component| numbered(component, "ata"))
+    {
+        TransportKind::Ata
+    } else {
+        TransportKind::Unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::symlink;
+
+    use super::*;
+
+    fn fake_device(root: &Path, device: &str, attributes: &[(&str, &str)]) -> PathBuf {
+        let device = root.join("devices").join(device);
+        fs::create_dir_all(&device).unwrap();
+        for (name, value) in attributes {
+            fs::write(device.join(name), format!("{}\n", value)).unwrap();
+        }
+
+        let name = device.file_name().unwrap();
+        let class = root.join("class/scsi_device").join(name);
+        fs::create_dir_all(&class).unwrap();
+        symlink(&device, class.join("device")).unwrap();
+
+        device
+    }
+
+    fn fake_node(root: &Path, class: &str, node: &str, device: &Path) -> PathBuf {
+        let directory = root.join(class).join(node);
+        fs::create_dir_all(&directory).unwrap();
+        symlink(device, directory.join("device")).unwrap();
+
+        directory
+    }
+
+    #[test]
+    fn enumerate_test() {
+        let root = std::env::temp_dir().join(format!("scsir-sysfs-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+
+        let disk = fake_device(
+            &root,
+            "pci0000:00/0000:00:1f.2/ata1/host0/target0:0:0/0:0:0:0",
+            &[
+                ("type", "0"),
+                ("vendor", "ATA     "),
+                ("model", "Samsung SSD 870 "),
+                ("rev", "1B6Q"),
+            ],
+        );
+        let sda = fake_node(&root, "block", "sda", &disk);
+        fs::create_dir_all(sda.join("queue")).unwrap();
+        fs::write(sda.join("queue/logical_block_size"), "512\n").unwrap();
+        fs::write(sda.join("size"), "2048\n").unwrap();
+        fake_node(&root, "class/scsi_generic", "sg0", &disk);
+        fake_node(&root, "class/bsg", "0:0:0:0", &disk);
+
+        let cdrom = fake_device(
+            &root,
+            "pci0000:00/0000:00:14.0/usb2/2-1/2-1:1.0/host6/target6:0:0/6:0:0:1",
+            &[
+                ("type", "5"),
+                ("vendor", "ASUS"),
+                ("model", "SDRW"),
+                ("rev", "1.00"),
+            ],
+        );
+        fake_node(&root, "block", "sr0", &cdrom);
+        fake_node(&root, "class/scsi_generic", "sg1", &cdrom);
+
+        let tape = fake_device(
+            &root,
+            "pci0000:00/0000:03:00.0/host2/port-2:0/end_device-2:0/target2:0:0/2:0:0:0",
+            &[
+                ("type", "1"),
+                ("vendor", "HP"),
+                ("model", "Ultrium"),
+                ("rev", "Z"),
+            ],
+        );
+        fake_node(&root, "class/scsi_tape", "nst0", &tape);
+        fake_node(&root, "class/scsi_tape", "st0", &tape);
+        fake_node(&root, "class/scsi_generic", "sg2", &tape);
+
+        let devices = Enumerator::new().sysfs_root(&root).enumerate().unwrap();
+        assert_eq!(devices.len(), 3);
+
+        assert_eq!(
+            devices[0],
+            DeviceInfo {
+                address: ScsiAddress::default(),
+                peripheral_device_type: 0,
+                vendor: "ATA".to_owned(),
+                model: "Samsung SSD 870".to_owned(),
+                revision: "1B6Q".to_owned(),
+                device_node: Some("sda".to_owned()),
+                generic_node: Some("sg0".to_owned()),
+                bsg_node: Some("0:0:0:0".to_owned()),
+                transport: TransportKind::Ata,
+                logical_block_size: Some(512),
+                capacity: Some(2048 * 512),
+            }
+        );
+        assert_eq!(devices[0].path(), Some(PathBuf::from("/dev/sg0")));
+        assert_eq!(
+            devices[0].bsg_path(),
+            Some(PathBuf::from("/dev/bsg/0:0:0:0"))
+        );
+
+        assert_eq!(devices[1].device_node.as_deref(), Some("st0"));
+        assert_eq!(devices[1].generic_node.as_deref(), Some("sg2"));
+        assert_eq!(devices[1].transport, TransportKind::Sas);
+        assert_eq!(devices[1].capacity, None);
+
+        assert_eq!(devices[2].address.lun, 1);
+        assert_eq!(devices[2].transport, TransportKind::Usb);
+
+        let devices = Enumerator::new()
+            .sysfs_root(&root)
+            .peripheral_device_type(5)
+            .enumerate()
+            .unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].device_path(), Some(PathBuf::from("/dev/sr0")));
+
+        let devices = Enumerator::new()
+            .sysfs_root(&root)
+            .transport(TransportKind::FibreChannel)
+            .enumerate()
+            .unwrap();
+        assert!(devices.is_empty());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,6 +1,6 @@
 use std::ffi::c_ushort;
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum HostStatus {
     /// NO error
     Ok,
@@ -55,3 +55,25 @@ impl From<c_ushort> for HostStatus {
         }
     }
 }
+
+impl From<HostStatus> for c_ushort {
+    fn from(value: HostStatus) -> Self {
+        match value {
+            HostStatus::Ok => 0x00,
+            HostStatus::NoConnect => 0x01,
+            HostStatus::BusBusy => 0x02,
+            HostStatus::TimeOut => 0x03,
+            HostStatus::BadTarget => 0x04,
+            HostStatus::Abort => 0x05,
+            HostStatus::Parity => 0x06,
+            HostStatus::Error => 0x07,
+            HostStatus::Reset => 0x08,
+            HostStatus::BadInterrupt => 0x09,
+            HostStatus::Passthrough => 0x0a,
+            HostStatus::SoftError => 0x0b,
+            HostStatus::ImmediateRetry => 0x0c,
+            HostStatus::Requeue => 0x0d,
+            HostStatus::Unknown => 0xff,
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use nix::libc::{self, c_int};
+
+use crate::{file_descriptor::FileDescriptor, Scsi};
+
+use super::AccessFlags;
+
+const SG_GET_SG_TABLESIZE: u32 = 0x227F;
+const SG_GET_RESERVED_SIZE: u32 = 0x2272;
+
+/// The most bytes the kernel takes per command, from `SG_GET_SG_TABLESIZE`, the
+/// `max_sectors_kb` and `max_hw_sectors_kb` of the request queue, and `SG_GET_RESERVED_SIZE`
+/// for memory mapped I/O, `None` if none of them is known.
+pub(crate) fn kernel_transfer_limit(scsi: &Scsi) -> Option<u64> {
+    let file_descriptor = scsi.file_descriptor()?;
+
+    let mut limits = vec![];
+
+    // every element of the scatter-gather list holds at least a page of a user buffer
+    if let Some(table_size) = ioctl_int(file_descriptor, SG_GET_SG_TABLESIZE) {
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if table_size > 0 && page_size > 0 {
+            limits.push(table_size as u64 * page_size as u64);
+        }
+    }
+
+    if let Some(queue) = request_queue(scsi.path()) {
+        for attribute in ["max_sectors_kb", "max_hw_sectors_kb"] {
+            if let Some(kilobytes) = fs::read_to_string(queue.join(attribute))
+                .ok()
+                .and_then(|value| value.trim().parse::<u64>().ok())
+            {
+                limits.push(kilobytes * 1024);
+            }
+        }
+    }
+
+    if scsi.access_flags().contains(AccessFlags::MEMORY_MAPPED_IO) {
+        if let Some(reserved_size) = ioctl_int(file_descriptor, SG_GET_RESERVED_SIZE) {
+            limits.push(reserved_size.max(0) as u64);
+        }
+    }
+
+    limits.into_iter().min()
+}
+
+fn ioctl_int(file_descriptor: &FileDescriptor, request: u32) -> Option<c_int> {
+    let mut value: c_int = 0;
+    let result = unsafe { libc::ioctl(file_descriptor.raw(), request as _, &mut value) };
+
+    (result == 0).then_some(value)
+}
+
+/// The sysfs `queue` directory of the block device behind `path`, an sd or sr node, one of
+/// their partitions, or the sg node of a device with a block driver.
+fn request_queue(path: &Path) -> Option<PathBuf> {
+    let node = fs::canonicalize(path).ok()?;
+    let name = node.file_name()?.to_str()?;
+
+    let block = match name.strip_prefix("sg") {
+        Some(_) => fs::read_dir(
+            Path::new("/sys/class/scsi_generic")
+                .join(name)
+                .join("device/block"),
+        )
+        .ok()?
+        .next()?
+        .ok()?
+        .path(),
+        None => Path::new("/sys/class/block").join(name),
+    };
+    let block = fs::canonicalize(block).ok()?;
+
+    // partitions share the queue of their disk
+    [block.join("queue"), block.parent()?.join("queue")]
+        .into_iter()
+        .find(|queue| queue.is_dir())
+}
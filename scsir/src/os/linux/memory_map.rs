@@ -0,0 +1,135 @@
+use std::{
+    fmt::Debug,
+    io,
+    ops::{Deref, DerefMut},
+    ptr, slice,
+    sync::Arc,
+};
+
+use nix::libc;
+
+use crate::file_descriptor::FileDescriptor;
+
+const SG_SET_RESERVED_SIZE: u32 = 0x2275;
+const SG_GET_RESERVED_SIZE: u32 = 0x2272;
+
+/// The reserved buffer of an sg file descriptor, mapped into the address space of the process.
+///
+/// Commands issued with [`AccessFlags::MEMORY_MAPPED_IO`](super::AccessFlags::MEMORY_MAPPED_IO)
+/// transfer their data through it, so their buffer must be this map, e.g.
+/// `scsi.read().transfer_length(8).issue_10_into(&mut memory_map)`.
+pub struct MemoryMap {
+    inner: Arc<Mapping>,
+}
+
+pub(crate) struct Mapping {
+    // keeps the file open while it is mapped
+    _file_descriptor: Arc<FileDescriptor>,
+    address: *mut u8,
+    length: usize,
+}
+
+unsafe impl Send for Mapping {}
+unsafe impl Sync for Mapping {}
+
+impl MemoryMap {
+    pub(crate) fn new(file_descriptor: Arc<FileDescriptor>, length: usize) -> io::Result<Self> {
+        if length == 0 || length > libc::c_int::MAX as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("can't reserve {} bytes", length),
+            ));
+        }
+
+        let requested = length as libc::c_int;
+        let result =
+            unsafe { libc::ioctl(file_descriptor.raw(), SG_SET_RESERVED_SIZE as _, &requested) };
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // the driver may reserve less, it is capped by the maximum transfer size of the host
+        let mut reserved: libc::c_int = 0;
+        let result = unsafe {
+            libc::ioctl(
+                file_descriptor.raw(),
+                SG_GET_RESERVED_SIZE as _,
+                &mut reserved,
+            )
+        };
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        if (reserved as usize) < length {
+            return Err(io::Error::new(
+                io::ErrorKind::OutOfMemory,
+                format!("the driver only reserved {} of {} bytes", reserved, length),
+            ));
+        }
+
+        let address = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                length,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                file_descriptor.raw(),
+                0,
+            )
+        };
+        if address == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self {
+            inner: Arc::new(Mapping {
+                _file_descriptor: file_descriptor,
+                address: address.cast(),
+                length,
+            }),
+        })
+    }
+
+    pub(crate) fn mapping(&self) -> &Arc<Mapping> {
+        &self.inner
+    }
+}
+
+impl Mapping {
+    /// Whether `buffer` is exactly the mapped memory.
+    pub(crate) fn is(&self, buffer: &[u8]) -> bool {
+        ptr::eq(buffer.as_ptr(), self.address) && buffer.len() <= self.length
+    }
+}
+
+impl Deref for MemoryMap {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { slice::from_raw_parts(self.inner.address, self.inner.length) }
+    }
+}
+
+impl DerefMut for MemoryMap {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { slice::from_raw_parts_mut(self.inner.address, self.inner.length) }
+    }
+}
+
+impl Debug for MemoryMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MemoryMap")
+            .field("address", &self.inner.address)
+            .field("length", &self.inner.length)
+            .finish()
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.address.cast(), self.length);
+        }
+    }
+}
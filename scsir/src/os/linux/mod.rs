@@ -1,14 +1,33 @@
 mod access_flag;
 mod auxiliary_info;
 mod driver_status;
+mod enumerate;
 mod host_status;
+mod limits;
+mod memory_map;
+mod reset;
 mod result_data_ext;
+mod scsi_id;
+mod sg_async;
 mod sg_io_header;
+mod sg_io_v4;
+mod sg_transport;
+mod sg_v4_transport;
 
 pub use access_flag::AccessFlags;
 pub use auxiliary_info::AuxiliaryInfo;
 pub use driver_status::DriverStatus;
+pub use enumerate::{DeviceInfo, Enumerator, TransportKind};
 pub use host_status::HostStatus;
+pub(crate) use limits::kernel_transfer_limit;
+pub use memory_map::MemoryMap;
+pub use reset::ResetLevel;
 #[allow(unused_imports)]
 pub use result_data_ext::ResultDataExt;
+pub(crate) use scsi_id::scsi_address;
+pub(crate) use sg_async::{read_response, write_request, AsyncResponse};
+pub(crate) use sg_io_header::RawSgIoHeader;
 pub use sg_io_header::SgIoHeader;
+pub use sg_io_v4::SgIoV4;
+pub(crate) use sg_transport::{sg_version_num, SgTransport};
+pub(crate) use sg_v4_transport::{supports_sg_v4, SgV4Transport};
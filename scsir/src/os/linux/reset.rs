@@ -0,0 +1,121 @@
+use nix::libc::{self, c_int};
+
+use crate::{
+    command::report_supported_task_management_functions::CommandResult as TaskManagementFunctions,
+    Scsi,
+};
+
+const SG_SCSI_RESET: u32 = 0x2284;
+const SG_SCSI_RESET_NO_ESCALATE: c_int = 0x100;
+
+/// What `SG_SCSI_RESET` resets, from the narrowest to the widest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ResetLevel {
+    /// The logical unit, with a LOGICAL UNIT RESET task management function.
+    Device,
+    /// Every logical unit of the target, with a target reset or I_T NEXUS RESET.
+    Target,
+    /// Every device on the bus the target is attached to.
+    Bus,
+    /// Every device attached to the host adapter.
+    Host,
+}
+
+impl ResetLevel {
+    fn value(self) -> c_int {
+        match self {
+            ResetLevel::Device => 1,
+            ResetLevel::Bus => 2,
+            ResetLevel::Host => 3,
+            ResetLevel::Target => 4,
+        }
+    }
+
+    /// Whether the logical unit claims to support the task management function of this level.
+    /// Bus and host resets are done by the host adapter, so they are always supported.
+    pub fn is_supported_by(self, functions: &TaskManagementFunctions) -> bool {
+        match self {
+            ResetLevel::Device => functions.logical_unit_reset_supported,
+            ResetLevel::Target => functions.i_t_nexus_reset_supported,
+            ResetLevel::Bus | ResetLevel::Host => true,
+        }
+    }
+}
+
+impl Scsi {
+    /// Resets at `level` through the `SG_SCSI_RESET` ioctl. If that fails, the kernel goes on
+    /// with the wider levels. Needs `CAP_SYS_ADMIN` and `CAP_SYS_RAWIO`.
+    pub fn reset(&self, level: ResetLevel) -> crate::Result<()> {
+        self.scsi_reset(level.value())
+    }
+
+    /// Like [`reset`](Self::reset), but fails instead of escalating to a wider level.
+    pub fn reset_no_escalate(&self, level: ResetLevel) -> crate::Result<()> {
+        self.scsi_reset(level.value() | SG_SCSI_RESET_NO_ESCALATE)
+    }
+
+    /// The levels the logical unit claims to support, from the narrowest to the widest, see
+    /// [`ResetLevel::is_supported_by`].
+    pub fn supported_reset_levels(&self) -> crate::Result<Vec<ResetLevel>> {
+        let functions = self.report_supported_task_management_functions().issue()?;
+
+        Ok([
+            ResetLevel::Device,
+            ResetLevel::Target,
+            ResetLevel::Bus,
+            ResetLevel::Host,
+        ]
+        .into_iter()
+        .filter(|level| level.is_supported_by(&functions))
+        .collect())
+    }
+
+    fn scsi_reset(&self, mut value: c_int) -> crate::Result<()> {
+        let Some(file_descriptor) = self.file_descriptor() else {
+            return Err(crate::Error::Other(format!(
+                "{} can't be reset, it needs a device opened from a path.",
+                self.path().display()
+            )));
+        };
+
+        let result = unsafe { libc::ioctl(file_descriptor.raw(), SG_SCSI_RESET as _, &mut value) };
+        if result != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+    use crate::{Transport, TransportRequest, TransportResponse};
+
+    /// Answers REPORT SUPPORTED TASK MANAGEMENT FUNCTIONS with LOGICAL UNIT RESET only.
+    #[derive(Debug)]
+    struct LogicalUnitResetOnly;
+
+    impl Transport for LogicalUnitResetOnly {
+        fn execute(&self, request: TransportRequest) -> io::Result<TransportResponse> {
+            request.data.fill(0);
+            request.data[0] = 0x08;
+
+            Ok(TransportResponse::default())
+        }
+    }
+
+    #[test]
+    fn reset_level_test() {
+        let scsi = Scsi::with_transport("lun", LogicalUnitResetOnly);
+
+        assert_eq!(
+            scsi.supported_reset_levels().unwrap(),
+            vec![ResetLevel::Device, ResetLevel::Bus, ResetLevel::Host]
+        );
+        assert!(scsi.reset(ResetLevel::Device).is_err());
+        assert!(scsi.reset_no_escalate(ResetLevel::Host).is_err());
+    }
+}
@@ -1,11 +1,13 @@
 use crate::ResultData;
 
-use super::{DriverStatus, HostStatus};
+use super::{AuxiliaryInfo, DriverStatus, HostStatus};
 
 #[allow(dead_code)]
 pub trait ResultDataExt {
     fn host_status(&self) -> &HostStatus;
     fn driver_status(&self) -> &DriverStatus;
+    /// Tells whether direct I/O was done.
+    fn auxiliary_info(&self) -> &AuxiliaryInfo;
 }
 
 impl<'a, D> ResultDataExt for ResultData<'a, D> {
@@ -16,4 +18,8 @@ impl<'a, D> ResultDataExt for ResultData<'a, D> {
     fn driver_status(&self) -> &DriverStatus {
         &self.driver_status
     }
+
+    fn auxiliary_info(&self) -> &AuxiliaryInfo {
+        &self.auxiliary_info
+    }
 }
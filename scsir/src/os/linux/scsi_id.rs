@@ -0,0 +1,97 @@
+use std::io;
+
+use nix::libc::{self, c_int, c_short};
+
+use crate::{file_descriptor::FileDescriptor, ScsiAddress};
+
+const SG_GET_SCSI_ID: u32 = 0x2276;
+const SCSI_IOCTL_GET_IDLUN: u32 = 0x5382;
+const SCSI_IOCTL_GET_BUS_NUMBER: u32 = 0x5386;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SgScsiId {
+    pub host_no: c_int,
+    pub channel: c_int,
+    pub scsi_id: c_int,
+    pub lun: c_int,
+    pub scsi_type: c_int,
+    pub h_cmd_per_lun: c_short,
+    pub d_queue_depth: c_short,
+    pub unused: [c_int; 2],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+struct ScsiIdlun {
+    dev_id: u32,
+    host_unique_id: u32,
+}
+
+/// Uses `SG_GET_SCSI_ID` for sg devices, and falls back to `SCSI_IOCTL_GET_IDLUN` for block
+/// devices, which only reports the low 8 bits of each field.
+pub(crate) fn scsi_address(file: &FileDescriptor) -> io::Result<ScsiAddress> {
+    let mut id = SgScsiId::default();
+    let result = unsafe { libc::ioctl(file.raw(), SG_GET_SCSI_ID as _, &mut id) };
+    if result == 0 {
+        return Ok(ScsiAddress {
+            host: id.host_no as u32,
+            channel: id.channel as u32,
+            target: id.scsi_id as u32,
+            lun: id.lun as u32 as u64,
+        });
+    }
+
+    let mut idlun = ScsiIdlun::default();
+    if unsafe { libc::ioctl(file.raw(), SCSI_IOCTL_GET_IDLUN as _, &mut idlun) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut host: c_int = 0;
+    if unsafe { libc::ioctl(file.raw(), SCSI_IOCTL_GET_BUS_NUMBER as _, &mut host) } != 0 {
+        host = (idlun.dev_id >> 24) as c_int;
+    }
+
+    Ok(ScsiAddress {
+        host: host as u32,
+        channel: (idlun.dev_id >> 16) & 0xFF,
+        target: idlun.dev_id & 0xFF,
+        lun: ((idlun.dev_id >> 8) & 0xFF) as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_test() {
+        const UNINIT: ::std::mem::MaybeUninit<SgScsiId> = ::std::mem::MaybeUninit::uninit();
+        let ptr = UNINIT.as_ptr();
+        assert_eq!(
+            ::std::mem::size_of::<SgScsiId>(),
+            32usize,
+            concat!("Size of: ", stringify!(SgScsiId))
+        );
+        assert_eq!(
+            unsafe { ::std::ptr::addr_of!((*ptr).scsi_type) as usize - ptr as usize },
+            16usize,
+            concat!(
+                "Offset of field: ",
+                stringify!(SgScsiId),
+                "::",
+                stringify!(scsi_type)
+            )
+        );
+        assert_eq!(
+            unsafe { ::std::ptr::addr_of!((*ptr).d_queue_depth) as usize - ptr as usize },
+            22usize,
+            concat!(
+                "Offset of field: ",
+                stringify!(SgScsiId),
+                "::",
+                stringify!(d_queue_depth)
+            )
+        );
+    }
+}
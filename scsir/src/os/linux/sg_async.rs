@@ -0,0 +1,157 @@
+use std::{io, mem::size_of_val, os::fd::RawFd, ptr, time::Duration};
+
+use nix::libc;
+
+use crate::{
+    transport::{TransportRequest, TransportResponse},
+    DataDirection,
+};
+
+use super::{AccessFlags, AuxiliaryInfo, DriverStatus, RawSgIoHeader};
+
+/// A command finished by the asynchronous sg interface.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct AsyncResponse {
+    pub pack_id: i32,
+    pub user_pointer: usize,
+    pub response: TransportResponse,
+}
+
+/// Queues a command with `write()`, without waiting for it.
+///
+/// # Safety
+///
+/// The driver keeps pointers to `request.data`, `request.data_out` and `request.sense`, and
+/// fills the writable ones while the response is read, so they must stay valid until then.
+pub(crate) unsafe fn write_request(
+    fd: RawFd,
+    request: TransportRequest,
+    pack_id: i32,
+    user_pointer: usize,
+) -> io::Result<()> {
+    if request.direction != DataDirection::ToDevice && !request.data_out.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "bidirectional commands can't be queued",
+        ));
+    }
+
+    if request.command.len() > u8::MAX as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Current command length is {}, max command length is {}",
+                request.command.len(),
+                u8::MAX
+            ),
+        ));
+    }
+
+    // the driver only reads data-out memory, it's never written through this pointer
+    let (data, data_length) = match request.direction {
+        DataDirection::ToDevice => (request.data_out.as_ptr() as *mut u8, request.data_out.len()),
+        _ => (request.data.as_mut_ptr(), request.data.len()),
+    };
+
+    let sg_header: RawSgIoHeader<u8, u8, u8> = RawSgIoHeader {
+        interface_id: b'S' as i32,
+        data_direction: request.direction.into(),
+        command_length: request.command.len() as u8,
+        max_sense_buffer_length: request.sense.len().min(u8::MAX as usize) as u8,
+        iovec_count: 0,
+        data_length: u32::try_from(data_length).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("data buffer of {} bytes is too large", data_length),
+            )
+        })?,
+        data: match data_length {
+            0 => ptr::null_mut(),
+            _ => data,
+        },
+        command: request.command.first(),
+        sense_buffer: request.sense.first_mut(),
+        timeout: request
+            .timeout
+            .as_millis()
+            .clamp(u32::MIN as u128, u32::MAX as u128) as u32,
+        flags: AccessFlags::DEFAULT,
+        pack_id,
+        user_pointer,
+        status: 0,
+        masked_status: 0,
+        message_status: 0,
+        sense_buffer_written: 0,
+        host_status: 0,
+        driver_status: DriverStatus::OK,
+        residual_count: 0,
+        duration: 0,
+        info: AuxiliaryInfo::OK,
+    };
+
+    let written = libc::write(
+        fd,
+        &sg_header as *const _ as *const libc::c_void,
+        size_of_val(&sg_header),
+    );
+    if written < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Reads any finished command with `read()`, blocks unless the file is non-blocking.
+pub(crate) fn read_response(fd: RawFd) -> io::Result<AsyncResponse> {
+    let mut sg_header: RawSgIoHeader<u8, u8, u8> = RawSgIoHeader {
+        interface_id: b'S' as i32,
+        data_direction: 0,
+        command_length: 0,
+        max_sense_buffer_length: 0,
+        iovec_count: 0,
+        data_length: 0,
+        data: ptr::null_mut(),
+        command: None,
+        sense_buffer: None,
+        timeout: 0,
+        flags: AccessFlags::DEFAULT,
+        // any pack id
+        pack_id: -1,
+        user_pointer: 0,
+        status: 0,
+        masked_status: 0,
+        message_status: 0,
+        sense_buffer_written: 0,
+        host_status: 0,
+        driver_status: DriverStatus::OK,
+        residual_count: 0,
+        duration: 0,
+        info: AuxiliaryInfo::OK,
+    };
+
+    let read = unsafe {
+        libc::read(
+            fd,
+            &mut sg_header as *mut _ as *mut libc::c_void,
+            size_of_val(&sg_header),
+        )
+    };
+    if read < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(AsyncResponse {
+        pack_id: sg_header.pack_id,
+        user_pointer: sg_header.user_pointer,
+        response: TransportResponse {
+            status: sg_header.status,
+            host_status: sg_header.host_status,
+            driver_status: sg_header.driver_status.bits(),
+            auxiliary_info: sg_header.info.bits(),
+            sense_length: sg_header.sense_buffer_written as usize,
+            residual: sg_header.residual_count.max(0) as usize,
+            data_out_residual: 0,
+            duration: Some(Duration::from_millis(sg_header.duration.into())),
+        },
+    })
+}
@@ -51,11 +51,66 @@ pub struct SgIoHeader<'a, C, D, S> {
     pub info: AuxiliaryInfo,
 }
 
+/// The same layout as [`SgIoHeader`], but `data` is a raw pointer, null for none, so that
+/// data-out memory borrowed shared can be handed to the driver, which only reads it.
+#[repr(C)]
+#[derive(Debug)]
+pub(crate) struct RawSgIoHeader<'a, C, D, S> {
+    pub interface_id: c_int,
+    pub data_direction: c_int,
+    pub command_length: c_uchar,
+    pub max_sense_buffer_length: c_uchar,
+    pub iovec_count: c_ushort,
+    pub data_length: c_uint,
+    pub data: *mut D,
+    pub command: Option<&'a C>,
+    pub sense_buffer: Option<&'a mut S>,
+    pub timeout: c_uint,
+    pub flags: AccessFlags,
+    pub pack_id: c_int,
+    pub user_pointer: usize,
+    pub status: c_uchar,
+    pub masked_status: c_uchar,
+    pub message_status: c_uchar,
+    pub sense_buffer_written: c_uchar,
+    pub host_status: c_ushort,
+    pub driver_status: DriverStatus,
+    pub residual_count: c_int,
+    pub duration: c_uint,
+    pub info: AuxiliaryInfo,
+}
+
 #[allow(deref_nullptr)]
 #[cfg(test)]
 mod tests {
-    use super::SgIoHeader as SgIoHeaderGeneric;
+    use super::{RawSgIoHeader as RawSgIoHeaderGeneric, SgIoHeader as SgIoHeaderGeneric};
     type SgIoHeader<'a> = SgIoHeaderGeneric<'a, (), (), ()>;
+    type RawSgIoHeader<'a> = RawSgIoHeaderGeneric<'a, (), (), ()>;
+
+    #[test]
+    fn raw_layout_test() {
+        const UNINIT: ::std::mem::MaybeUninit<SgIoHeader> = ::std::mem::MaybeUninit::uninit();
+        const RAW_UNINIT: ::std::mem::MaybeUninit<RawSgIoHeader> =
+            ::std::mem::MaybeUninit::uninit();
+        let (ptr, raw_ptr) = (UNINIT.as_ptr(), RAW_UNINIT.as_ptr());
+
+        assert_eq!(
+            ::std::mem::size_of::<SgIoHeader>(),
+            ::std::mem::size_of::<RawSgIoHeader>()
+        );
+        assert_eq!(
+            ::std::mem::align_of::<SgIoHeader>(),
+            ::std::mem::align_of::<RawSgIoHeader>()
+        );
+        assert_eq!(
+            unsafe { ::std::ptr::addr_of!((*ptr).data) as usize - ptr as usize },
+            unsafe { ::std::ptr::addr_of!((*raw_ptr).data) as usize - raw_ptr as usize }
+        );
+        assert_eq!(
+            unsafe { ::std::ptr::addr_of!((*ptr).info) as usize - ptr as usize },
+            unsafe { ::std::ptr::addr_of!((*raw_ptr).info) as usize - raw_ptr as usize }
+        );
+    }
 
     #[test]
     #[cfg(target_arch = "x86_64")]
@@ -0,0 +1,160 @@
+use super::{access_flag::AccessFlags, auxiliary_info::AuxiliaryInfo};
+
+/// `struct sg_io_v4`, accepted by bsg nodes and by sg drivers of version 4 or newer.
+///
+/// Pointers are carried as `u64` so the layout is identical on 32 and 64 bit platforms.
+#[repr(C)]
+#[derive(Debug)]
+pub struct SgIoV4 {
+    /// \[i\] 'Q' to differentiate from v3
+    pub guard: i32,
+    /// \[i\] 0 -> SCSI , ....
+    pub protocol: u32,
+    /// \[i\] 0 -> SCSI command, 1 -> SCSI task management function, ....
+    pub subprotocol: u32,
+    /// \[i\] in bytes
+    pub request_length: u32,
+    /// \[i\], \[*i\] {SCSI: cdb}
+    pub request: u64,
+    /// \[i\] {SCSI: task tag (only if flagged)}
+    pub request_tag: u64,
+    /// \[i\] {SCSI: task attribute}
+    pub request_attribute: u32,
+    /// \[i\] {SCSI: task priority}
+    pub request_priority: u32,
+    /// \[i\] {spare, for padding}
+    pub request_extra: u32,
+    /// \[i\] in bytes
+    pub max_response_length: u32,
+    /// \[i\], \[*o\] {SCSI: (auto)sense data}
+    pub response: u64,
+    /// \[i\] 0 -> "flat" dout transfer else dout_xfer points to array of iovec
+    pub data_out_iovec_count: u32,
+    /// \[i\] bytes to be transferred to device
+    pub data_out_transfer_length: u32,
+    /// \[i\] 0 -> "flat" din transfer
+    pub data_in_iovec_count: u32,
+    /// \[i\] bytes to be transferred from device
+    pub data_in_transfer_length: u32,
+    /// \[i\], \[*i\]
+    pub data_out: u64,
+    /// \[i\], \[*o\]
+    pub data_in: u64,
+    /// \[i\] units: millisecond
+    pub timeout: u32,
+    /// \[i\] bit mask
+    pub flags: AccessFlags,
+    /// \[i->o\] unused internally
+    pub user_pointer: u64,
+    /// \[i\]
+    pub spare_in: u32,
+    /// \[o\] 0 -> ok
+    pub driver_status: u32,
+    /// \[o\] 0 -> ok
+    pub transport_status: u32,
+    /// \[o\] {SCSI: command completion status}
+    pub device_status: u32,
+    /// \[o\] {SCSI: status auxiliary information}
+    pub retry_delay: u32,
+    /// \[o\] additional information
+    pub info: AuxiliaryInfo,
+    /// \[o\] time to complete, in milliseconds
+    pub duration: u32,
+    /// \[o\] bytes of response actually written
+    pub response_length: u32,
+    /// \[o\] din_xfer_len - actual_din_xfer_len
+    pub data_in_residual: i32,
+    /// \[o\] dout_xfer_len - actual_dout_xfer_len
+    pub data_out_residual: i32,
+    /// \[o\] {SCSI: transport generated task tag}
+    pub generated_tag: u64,
+    /// \[o\]
+    pub spare_out: u32,
+    pub padding: u32,
+}
+
+impl Default for SgIoV4 {
+    fn default() -> Self {
+        Self {
+            guard: b'Q' as i32,
+            protocol: 0,
+            subprotocol: 0,
+            request_length: 0,
+            request: 0,
+            request_tag: 0,
+            request_attribute: 0,
+            request_priority: 0,
+            request_extra: 0,
+            max_response_length: 0,
+            response: 0,
+            data_out_iovec_count: 0,
+            data_out_transfer_length: 0,
+            data_in_iovec_count: 0,
+            data_in_transfer_length: 0,
+            data_out: 0,
+            data_in: 0,
+            timeout: 0,
+            flags: AccessFlags::DEFAULT,
+            user_pointer: 0,
+            spare_in: 0,
+            driver_status: 0,
+            transport_status: 0,
+            device_status: 0,
+            retry_delay: 0,
+            info: AuxiliaryInfo::OK,
+            duration: 0,
+            response_length: 0,
+            data_in_residual: 0,
+            data_out_residual: 0,
+            generated_tag: 0,
+            spare_out: 0,
+            padding: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! assert_offset {
+        ($ptr:expr, $field:ident, $offset:expr) => {
+            assert_eq!(
+                unsafe { ::std::ptr::addr_of!((*$ptr).$field) as usize - $ptr as usize },
+                $offset,
+                concat!(
+                    "Offset of field: ",
+                    stringify!(SgIoV4),
+                    "::",
+                    stringify!($field)
+                )
+            );
+        };
+    }
+
+    #[test]
+    fn layout_test() {
+        const UNINIT: ::std::mem::MaybeUninit<SgIoV4> = ::std::mem::MaybeUninit::uninit();
+        let ptr = UNINIT.as_ptr();
+        assert_eq!(
+            ::std::mem::size_of::<SgIoV4>(),
+            160usize,
+            concat!("Size of: ", stringify!(SgIoV4))
+        );
+        assert_eq!(
+            ::std::mem::align_of::<SgIoV4>(),
+            8usize,
+            concat!("Alignment of ", stringify!(SgIoV4))
+        );
+        assert_offset!(ptr, request, 16);
+        assert_offset!(ptr, response, 48);
+        assert_offset!(ptr, data_out, 72);
+        assert_offset!(ptr, data_in, 80);
+        assert_offset!(ptr, user_pointer, 96);
+        assert_offset!(ptr, device_status, 116);
+        assert_offset!(ptr, response_length, 132);
+        assert_offset!(ptr, data_in_residual, 136);
+        assert_offset!(ptr, generated_tag, 144);
+        assert_offset!(ptr, padding, 156);
+    }
+}
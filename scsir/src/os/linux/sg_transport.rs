@@ -0,0 +1,247 @@
+use std::{
+    fmt::Debug,
+    io::{self, IoSliceMut},
+    ptr,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use nix::libc;
+
+use crate::{
+    file_descriptor::FileDescriptor,
+    transport::{Transport, TransportRequest, TransportResponse},
+    DataDirection,
+};
+
+use super::{
+    memory_map::Mapping, sg_v4_transport::needs_sg_v4, AccessFlags, AuxiliaryInfo, DriverStatus,
+    RawSgIoHeader,
+};
+
+const SG_IO: u32 = 0x2285;
+const SG_GET_VERSION_NUM: u32 = 0x2282;
+
+/// Issues commands through the `SG_IO` ioctl.
+pub(crate) struct SgTransport {
+    file_descriptor: Arc<FileDescriptor>,
+    access_flags: AtomicU32,
+    memory_map: Mutex<Option<Arc<Mapping>>>,
+}
+
+impl SgTransport {
+    pub(crate) fn new(file_descriptor: Arc<FileDescriptor>) -> Self {
+        Self {
+            file_descriptor,
+            access_flags: AtomicU32::new(AccessFlags::DEFAULT.bits()),
+            memory_map: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn set_access_flags(&self, flags: AccessFlags) {
+        self.access_flags.store(flags.bits(), Ordering::Relaxed);
+    }
+
+    pub(crate) fn access_flags(&self) -> AccessFlags {
+        AccessFlags::from_bits_retain(self.access_flags.load(Ordering::Relaxed))
+    }
+
+    pub(crate) fn set_memory_map(&self, mapping: Arc<Mapping>) {
+        *self.memory_map.lock().unwrap_or_else(|e| e.into_inner()) = Some(mapping);
+    }
+
+    /// Memory mapped I/O only applies to transfers through the memory map, `None` stands for
+    /// a scatter-gather list.
+    fn access_flags_for(&self, data: Option<&[u8]>) -> io::Result<AccessFlags> {
+        let mut flags = self.access_flags();
+
+        if flags.contains(AccessFlags::MEMORY_MAPPED_IO) {
+            let is_mapped = match data {
+                Some([]) => {
+                    flags.remove(AccessFlags::MEMORY_MAPPED_IO);
+                    true
+                }
+                Some(data) => self
+                    .memory_map
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .as_ref()
+                    .is_some_and(|mapping| mapping.is(data)),
+                None => false,
+            };
+
+            if !is_mapped {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "memory mapped I/O needs the memory map as the data buffer",
+                ));
+            }
+        }
+
+        Ok(flags)
+    }
+}
+
+impl Transport for SgTransport {
+    fn execute(&self, mut request: TransportRequest) -> io::Result<TransportResponse> {
+        let data = std::mem::take(&mut request.data);
+        let flags = match request.direction {
+            DataDirection::ToDevice => self.access_flags_for(Some(request.data_out))?,
+            _ => self.access_flags_for(Some(data))?,
+        };
+
+        // the driver only reads data-out memory, it's never written through this pointer
+        let (data, data_length) = match request.direction {
+            DataDirection::ToDevice => {
+                (request.data_out.as_ptr() as *mut u8, request.data_out.len())
+            }
+            _ => (data.as_mut_ptr(), data.len()),
+        };
+
+        // the driver transfers through its reserved buffer, which is the memory map
+        let data = match flags.contains(AccessFlags::MEMORY_MAPPED_IO) || data_length == 0 {
+            true => ptr::null_mut(),
+            false => data,
+        };
+        let data_length = u32::try_from(data_length).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("data buffer of {} bytes is too large", data_length),
+            )
+        })?;
+
+        self.submit(request, data, 0, data_length, flags)
+    }
+
+    /// Hands `buffers` to the driver as a scatter-gather list, through `iovec_count`.
+    fn execute_vectored(
+        &self,
+        request: TransportRequest,
+        buffers: &mut [IoSliceMut],
+    ) -> io::Result<TransportResponse> {
+        let data_length = buffers.iter().map(|buffer| buffer.len()).sum::<usize>();
+
+        if buffers.len() > u16::MAX as usize || data_length > u32::MAX as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{} buffers of {} bytes in total are too many for a scatter-gather list",
+                    buffers.len(),
+                    data_length
+                ),
+            ));
+        }
+
+        // IoSliceMut is guaranteed to be ABI compatible with iovec, i.e. sg_iovec
+        let flags = match data_length {
+            0 => self.access_flags_for(Some(&[]))?,
+            _ => self.access_flags_for(None)?,
+        };
+
+        let iovec_count = buffers.len() as u16;
+        self.submit(
+            request,
+            match buffers.is_empty() {
+                true => ptr::null_mut(),
+                false => buffers.as_mut_ptr(),
+            },
+            iovec_count,
+            data_length as u32,
+            flags,
+        )
+    }
+}
+
+impl SgTransport {
+    fn submit<D>(
+        &self,
+        request: TransportRequest,
+        data: *mut D,
+        iovec_count: u16,
+        data_length: u32,
+        flags: AccessFlags,
+    ) -> io::Result<TransportResponse> {
+        if request.direction != DataDirection::ToDevice && !request.data_out.is_empty() {
+            return Err(needs_sg_v4(
+                &self.file_descriptor,
+                "bidirectional transfers",
+            ));
+        }
+
+        if request.command.len() > u8::MAX as usize {
+            return Err(needs_sg_v4(
+                &self.file_descriptor,
+                &format!("commands of {} bytes", request.command.len()),
+            ));
+        }
+
+        let mut sg_header: RawSgIoHeader<u8, D, u8> = RawSgIoHeader {
+            interface_id: b'S' as i32,
+            data_direction: request.direction.into(),
+            command_length: request.command.len() as u8,
+            max_sense_buffer_length: request.sense.len().min(u8::MAX as usize) as u8,
+            iovec_count,
+            data_length,
+            data,
+            command: request.command.first(),
+            sense_buffer: request.sense.first_mut(),
+            timeout: request
+                .timeout
+                .as_millis()
+                .clamp(u32::MIN as u128, u32::MAX as u128) as u32,
+            flags,
+            pack_id: 0,
+            user_pointer: 0,
+            status: 0,
+            masked_status: 0,
+            message_status: 0,
+            sense_buffer_written: 0,
+            host_status: 0,
+            driver_status: DriverStatus::OK,
+            residual_count: 0,
+            duration: 0,
+            info: AuxiliaryInfo::OK,
+        };
+
+        let ioctl_result =
+            unsafe { libc::ioctl(self.file_descriptor.raw(), SG_IO as _, &mut sg_header) };
+
+        if ioctl_result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(TransportResponse {
+            status: sg_header.status,
+            host_status: sg_header.host_status,
+            driver_status: sg_header.driver_status.bits(),
+            auxiliary_info: sg_header.info.bits(),
+            sense_length: sg_header.sense_buffer_written as usize,
+            residual: sg_header.residual_count.max(0) as usize,
+            data_out_residual: 0,
+            duration: Some(Duration::from_millis(sg_header.duration.into())),
+        })
+    }
+}
+
+impl Debug for SgTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SgTransport")
+            .field("file_descriptor", &self.file_descriptor)
+            .field("access_flags", &self.access_flags())
+            .finish()
+    }
+}
+
+pub(crate) fn sg_version_num(file: &FileDescriptor) -> io::Result<i32> {
+    let mut version = 0;
+    let result = unsafe { libc::ioctl(file.raw(), SG_GET_VERSION_NUM as _, &mut version) };
+
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(version)
+}
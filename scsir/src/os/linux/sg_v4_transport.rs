@@ -0,0 +1,144 @@
+use std::{
+    io,
+    os::unix::fs::{FileTypeExt, MetadataExt},
+    sync::Arc,
+    time::Duration,
+};
+
+use nix::libc;
+
+use crate::{
+    file_descriptor::FileDescriptor,
+    transport::{Transport, TransportRequest, TransportResponse},
+    DataDirection,
+};
+
+use super::{scsi_address, sg_transport::sg_version_num, SgIoV4};
+
+const SG_IO: u32 = 0x2285;
+const BSG_PROTOCOL_SCSI: u32 = 0;
+const BSG_SUB_PROTOCOL_SCSI_CMD: u32 = 0;
+const FIRST_SG_V4_VERSION_NUM: i32 = 40000;
+
+/// Issues commands through the `SG_IO` ioctl with `struct sg_io_v4`, which allows CDBs longer
+/// than 255 bytes and separate data-in and data-out buffers.
+///
+/// Only bsg nodes, e.g. `/dev/bsg/0:0:0:0`, take v4 headers on mainline kernels, their sg
+/// driver is version 3.5. An sg driver of version 4.0 or later, as found in some out of tree
+/// builds, takes them on `/dev/sg*` nodes as well.
+#[derive(Debug)]
+pub(crate) struct SgV4Transport {
+    file_descriptor: Arc<FileDescriptor>,
+}
+
+impl SgV4Transport {
+    pub(crate) fn new(file_descriptor: Arc<FileDescriptor>) -> Self {
+        Self { file_descriptor }
+    }
+}
+
+impl Transport for SgV4Transport {
+    fn execute(&self, request: TransportRequest) -> io::Result<TransportResponse> {
+        let mut header = SgIoV4 {
+            protocol: BSG_PROTOCOL_SCSI,
+            subprotocol: BSG_SUB_PROTOCOL_SCSI_CMD,
+            request_length: length_u32(request.command.len(), "command")?,
+            request: request.command.as_ptr() as u64,
+            max_response_length: length_u32(request.sense.len(), "sense")?,
+            response: request.sense.as_mut_ptr() as u64,
+            timeout: request
+                .timeout
+                .as_millis()
+                .clamp(u32::MIN as u128, u32::MAX as u128) as u32,
+            ..Default::default()
+        };
+
+        let data_length = length_u32(request.data.len(), "data")?;
+        let data_out_length = length_u32(request.data_out.len(), "data-out")?;
+        if matches!(
+            request.direction,
+            DataDirection::FromDevice | DataDirection::ToFromDevice
+        ) {
+            header.data_in_transfer_length = data_length;
+            header.data_in = request.data.as_mut_ptr() as u64;
+        }
+        if data_out_length != 0 {
+            header.data_out_transfer_length = data_out_length;
+            header.data_out = request.data_out.as_ptr() as u64;
+        }
+
+        let ioctl_result =
+            unsafe { libc::ioctl(self.file_descriptor.raw(), SG_IO as _, &mut header) };
+
+        if ioctl_result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let (residual, data_out_residual) = match request.direction {
+            DataDirection::ToDevice => (header.data_out_residual, 0),
+            _ => (header.data_in_residual, header.data_out_residual),
+        };
+
+        Ok(TransportResponse {
+            status: header.device_status as u8,
+            host_status: header.transport_status as u16,
+            driver_status: header.driver_status as u16,
+            auxiliary_info: header.info.bits(),
+            sense_length: (header.response_length as usize).min(request.sense.len()),
+            residual: residual.max(0) as usize,
+            data_out_residual: data_out_residual.max(0) as usize,
+            duration: Some(Duration::from_millis(header.duration.into())),
+        })
+    }
+}
+
+/// The error for a command only [`SgV4Transport`] can issue, naming the bsg node of the device
+/// behind `file`, which takes it.
+pub(crate) fn needs_sg_v4(file: &FileDescriptor, what: &str) -> io::Error {
+    let node = match scsi_address(file) {
+        Ok(address) => format!(
+            "/dev/bsg/{}:{}:{}:{}",
+            address.host, address.channel, address.target, address.lun
+        ),
+        Err(_) => "its /dev/bsg node".to_owned(),
+    };
+
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!(
+            "{} need an sg v4 capable device, open {} instead",
+            what, node
+        ),
+    )
+}
+
+/// bsg nodes only understand v4 headers, sg nodes do since driver version 4.0.
+pub(crate) fn supports_sg_v4(file: &FileDescriptor) -> bool {
+    let metadata = match file.metadata() {
+        Ok(metadata) if metadata.file_type().is_char_device() => metadata,
+        _ => return false,
+    };
+
+    let rdev = metadata.rdev();
+    let subsystem = format!(
+        "/sys/dev/char/{}:{}/subsystem",
+        libc::major(rdev),
+        libc::minor(rdev)
+    );
+    if let Ok(subsystem) = std::fs::read_link(subsystem) {
+        if subsystem.file_name().is_some_and(|name| name == "bsg") {
+            return true;
+        }
+    }
+
+    matches!(sg_version_num(file), Ok(version) if version >= FIRST_SG_V4_VERSION_NUM)
+}
+
+fn length_u32(length: usize, name: &str) -> io::Result<u32> {
+    u32::try_from(length).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} buffer of {} bytes is too large", name, length),
+        )
+    })
+}
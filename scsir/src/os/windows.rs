@@ -1,6 +1,23 @@
-use windows::Win32::Storage::IscsiDisc::SCSI_PASS_THROUGH_DIRECT;
+use std::{io, mem::size_of_val, sync::Arc};
 
-use crate::command::sense::MAX_SENSE_BUFFER_LENGTH;
+use windows::Win32::{
+    Foundation::HANDLE,
+    Storage::IscsiDisc::{
+        IOCTL_SCSI_GET_ADDRESS, IOCTL_SCSI_PASS_THROUGH_DIRECT, SCSI_ADDRESS,
+        SCSI_IOCTL_DATA_BIDIRECTIONAL, SCSI_IOCTL_DATA_IN, SCSI_IOCTL_DATA_OUT,
+        SCSI_IOCTL_DATA_UNSPECIFIED, SCSI_PASS_THROUGH_DIRECT,
+    },
+    System::IO::DeviceIoControl,
+};
+
+use crate::{
+    command::sense::MAX_SENSE_BUFFER_LENGTH,
+    file_descriptor::FileDescriptor,
+    transport::{Transport, TransportRequest, TransportResponse},
+    DataDirection, ScsiAddress,
+};
+
+const MAX_COMMAND_LENGTH: usize = 16;
 
 #[repr(C)]
 pub struct ScsiPassThroughDirectWrapper {
@@ -13,3 +30,137 @@ impl Default for ScsiPassThroughDirectWrapper {
         unsafe { std::mem::zeroed() }
     }
 }
+
+/// Issues commands through `IOCTL_SCSI_PASS_THROUGH_DIRECT`.
+#[derive(Debug)]
+pub(crate) struct PassThroughTransport {
+    file_descriptor: Arc<FileDescriptor>,
+}
+
+impl PassThroughTransport {
+    pub(crate) fn new(file_descriptor: Arc<FileDescriptor>) -> Self {
+        Self { file_descriptor }
+    }
+}
+
+impl Transport for PassThroughTransport {
+    fn execute(&self, request: TransportRequest) -> io::Result<TransportResponse> {
+        if request.direction != DataDirection::ToDevice && !request.data_out.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "bidirectional transfers are not supported by SCSI_PASS_THROUGH_DIRECT",
+            ));
+        }
+
+        if request.command.len() > MAX_COMMAND_LENGTH {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Current command length is {}, max command length is {}",
+                    request.command.len(),
+                    MAX_COMMAND_LENGTH
+                ),
+            ));
+        }
+
+        let mut header = ScsiPassThroughDirectWrapper::default();
+        let address_of_header = std::ptr::addr_of!(header) as usize;
+        let spt = &mut header.scsi_pass_through;
+        spt.Length = size_of_val(spt) as u16;
+        spt.CdbLength = request.command.len() as u8;
+        spt.SenseInfoLength = request.sense.len().min(MAX_SENSE_BUFFER_LENGTH) as u8;
+        spt.DataIn = match request.direction {
+            DataDirection::None => SCSI_IOCTL_DATA_UNSPECIFIED,
+            DataDirection::ToDevice => SCSI_IOCTL_DATA_OUT,
+            DataDirection::FromDevice => SCSI_IOCTL_DATA_IN,
+            DataDirection::ToFromDevice => SCSI_IOCTL_DATA_BIDIRECTIONAL,
+            DataDirection::Unknown => SCSI_IOCTL_DATA_UNSPECIFIED,
+        } as u8;
+
+        let data_length = request.transfer_length();
+        spt.DataTransferLength = data_length as u32;
+
+        spt.TimeOutValue = match request
+            .timeout
+            .as_secs()
+            .clamp(u32::MIN as u64, u32::MAX as u64)
+        {
+            0 => 1,
+            n => n as u32,
+        };
+
+        // the driver only reads data-out memory, it's never written through this pointer
+        spt.DataBuffer = match request.direction {
+            DataDirection::ToDevice => request.data_out.as_ptr() as *mut u8 as _,
+            _ => request.data.as_mut_ptr() as _,
+        };
+
+        spt.SenseInfoOffset =
+            (std::ptr::addr_of!(header.sense) as usize - address_of_header) as u32;
+
+        spt.Cdb[..request.command.len()].copy_from_slice(request.command);
+
+        let mut bytes_returned = 0;
+
+        let success = unsafe {
+            DeviceIoControl(
+                HANDLE(self.file_descriptor.raw() as isize),
+                IOCTL_SCSI_PASS_THROUGH_DIRECT,
+                Some(&header as *const _ as _),
+                size_of_val(&header) as u32,
+                Some(&mut header as *mut _ as _),
+                size_of_val(&header) as u32,
+                Some(&mut bytes_returned),
+                None,
+            )
+        };
+
+        if !success.as_bool() {
+            return Err(io::Error::last_os_error());
+        }
+
+        let sense_length =
+            (header.scsi_pass_through.SenseInfoLength as usize).min(request.sense.len());
+        request.sense[..sense_length].copy_from_slice(&header.sense[..sense_length]);
+
+        Ok(TransportResponse {
+            status: header.scsi_pass_through.ScsiStatus,
+            host_status: 0,
+            driver_status: 0,
+            auxiliary_info: 0,
+            sense_length,
+            residual: data_length
+                .saturating_sub(header.scsi_pass_through.DataTransferLength as usize),
+            data_out_residual: 0,
+            duration: None,
+        })
+    }
+}
+
+pub(crate) fn scsi_address(file: &FileDescriptor) -> io::Result<ScsiAddress> {
+    let mut scsi_address = SCSI_ADDRESS::default();
+    let mut bytes_returned = 0;
+    let success = unsafe {
+        DeviceIoControl(
+            HANDLE(file.raw() as isize),
+            IOCTL_SCSI_GET_ADDRESS,
+            None,
+            0,
+            Some(&mut scsi_address as *mut _ as _),
+            size_of_val(&scsi_address) as u32,
+            Some(&mut bytes_returned),
+            None,
+        )
+    };
+
+    if !success.as_bool() {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(ScsiAddress {
+        host: scsi_address.PortNumber as u32,
+        channel: scsi_address.PathId as u32,
+        target: scsi_address.TargetId as u32,
+        lun: scsi_address.Lun as u64,
+    })
+}
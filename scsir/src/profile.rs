@@ -0,0 +1,283 @@
+use crate::{
+    command::report_supported_operation_codes::CommandResult,
+    shortcut::inquiry::{
+        block_device_characteristics, block_limits, logical_block_provisioning, standard_inquiry,
+        supported_vital_product_data_pages,
+    },
+    Scsi,
+};
+
+const BLOCK_LIMITS: u8 = 0xB0;
+const BLOCK_DEVICE_CHARACTERISTICS: u8 = 0xB1;
+const LOGICAL_BLOCK_PROVISIONING: u8 = 0xB2;
+
+const HOST_MANAGED_ZONED_BLOCK_DEVICE: u8 = 0x14;
+const READ_16: u8 = 0x88;
+const UNMAP: u8 = 0x42;
+
+/// What a device can do, probed once with [`DeviceProfile::probe`] and then kept, e.g. per drive
+/// with the `serde` feature.
+///
+/// Every command but the standard INQUIRY may be rejected by the device, the affected fields
+/// stay `None`, empty or `false` then.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceProfile {
+    pub vendor_identification: String,
+    pub product_identification: String,
+    pub product_revision_level: String,
+    pub peripheral_device_type: u8,
+    pub version: u8,
+    pub supported_vital_product_data_pages: Vec<u8>,
+    /// `None` if REPORT SUPPORTED OPERATION CODES was rejected.
+    pub supported_operations: Option<Vec<SupportedOperation>>,
+    /// From READ CAPACITY(16).
+    pub logical_block_length: Option<u32>,
+    pub logical_block_count: Option<u64>,
+    pub logical_blocks_per_physical_block_exponent: Option<u8>,
+    /// Type 1, 2 or 3, `None` if protection information is disabled.
+    pub protection_type: Option<u8>,
+    pub thin_provisioned: bool,
+    /// In logical blocks, from the Block Limits VPD page, `None` if there is no limit.
+    pub maximum_transfer_length: Option<u32>,
+    pub optimal_transfer_length: Option<u32>,
+    pub optimal_transfer_length_granularity: Option<u16>,
+    pub maximum_unmap_lba_count: Option<u32>,
+    pub maximum_write_same_length: Option<u64>,
+    pub atomic_write_limits: Option<AtomicWriteLimits>,
+    /// From the Logical Block Provisioning VPD page.
+    pub logical_block_provisioning_unmap: bool,
+    pub logical_block_provisioning_write_same: bool,
+    pub zoned_model: ZonedModel,
+    /// 1 for non-rotating media, the RPM otherwise, from the Block Device Characteristics VPD
+    /// page.
+    pub medium_rotation_rate: Option<u16>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SupportedOperation {
+    pub operation_code: u8,
+    pub service_action: Option<u16>,
+    pub cdb_length: u16,
+}
+
+/// In logical blocks, for WRITE ATOMIC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AtomicWriteLimits {
+    pub maximum_transfer_length: u32,
+    pub alignment: u32,
+    pub transfer_length_granularity: u32,
+    pub maximum_transfer_length_with_atomic_boundary: u32,
+    pub maximum_boundary_size: u32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ZonedModel {
+    #[default]
+    NotZoned,
+    HostAware,
+    DeviceManaged,
+    HostManaged,
+}
+
+impl DeviceProfile {
+    /// Issues the standard INQUIRY, the supported VPD pages among Block Limits, Block Device
+    /// Characteristics and Logical Block Provisioning, READ CAPACITY(16) for block devices and
+    /// REPORT SUPPORTED OPERATION CODES. Only a failing standard INQUIRY fails.
+    pub fn probe(scsi: &Scsi) -> crate::Result<Self> {
+        let inquiry = standard_inquiry(&mut scsi.inquiry())?;
+
+        let mut profile = DeviceProfile {
+            vendor_identification: inquiry.t10_vendor_identification.trim().to_owned(),
+            product_identification: inquiry.product_identification.trim().to_owned(),
+            product_revision_level: inquiry.product_revision_level.trim().to_owned(),
+            peripheral_device_type: inquiry.peripheral_device_type,
+            version: inquiry.version,
+            ..Default::default()
+        };
+
+        if let Ok(pages) = supported_vital_product_data_pages(&mut scsi.inquiry()) {
+            profile.supported_vital_product_data_pages = pages.supported_pages;
+        }
+        profile.supported_operations = supported_operations(scsi);
+
+        if matches!(
+            profile.peripheral_device_type,
+            0x00 | HOST_MANAGED_ZONED_BLOCK_DEVICE
+        ) {
+            if let Ok(capacity) = scsi.read_capacity().issue_16() {
+                profile.logical_block_length = Some(capacity.logical_block_length_in_bytes);
+                profile.logical_block_count =
+                    Some(capacity.returned_logical_block_address.saturating_add(1));
+                profile.logical_blocks_per_physical_block_exponent =
+                    Some(capacity.logical_blocks_per_physical_block_exponent);
+                profile.protection_type = capacity
+                    .protection_enabled
+                    .then_some(capacity.protection_type + 1);
+                profile.thin_provisioned = capacity.logical_block_provisioning_management_enabled;
+            }
+        }
+
+        if profile.supports_page(BLOCK_LIMITS) {
+            if let Ok(limits) = block_limits(&mut scsi.inquiry()) {
+                let non_zero = |value: u32| (value != 0).then_some(value);
+                profile.maximum_transfer_length = non_zero(limits.maximum_transfer_length);
+                profile.optimal_transfer_length = non_zero(limits.optimal_transfer_length);
+                profile.optimal_transfer_length_granularity =
+                    (limits.optimal_transfer_length_granularity != 0)
+                        .then_some(limits.optimal_transfer_length_granularity);
+                profile.maximum_unmap_lba_count = non_zero(limits.maximum_unmap_lba_count);
+                profile.maximum_write_same_length = (limits.maximum_write_same_length != 0)
+                    .then_some(limits.maximum_write_same_length);
+                profile.atomic_write_limits = (limits.maximum_atomic_transfer_length != 0)
+                    .then_some(AtomicWriteLimits {
+                        maximum_transfer_length: limits.maximum_atomic_transfer_length,
+                        alignment: limits.atomic_alignment,
+                        transfer_length_granularity: limits.atomic_transfer_length_granularity,
+                        maximum_transfer_length_with_atomic_boundary: limits
+                            .maximum_atomic_transfer_length_with_atomic_boundary,
+                        maximum_boundary_size: limits.maximum_atomic_boundary_size,
+                    });
+            }
+        }
+
+        if profile.supports_page(LOGICAL_BLOCK_PROVISIONING) {
+            if let Ok(provisioning) = logical_block_provisioning(&mut scsi.inquiry()) {
+                profile.logical_block_provisioning_unmap =
+                    provisioning.logical_block_provisioning_unmap;
+                profile.logical_block_provisioning_write_same =
+                    provisioning.logical_block_provisioning_write_same;
+            }
+        }
+
+        if profile.peripheral_device_type == HOST_MANAGED_ZONED_BLOCK_DEVICE {
+            profile.zoned_model = ZonedModel::HostManaged;
+        }
+        if profile.supports_page(BLOCK_DEVICE_CHARACTERISTICS) {
+            if let Ok(characteristics) = block_device_characteristics(&mut scsi.inquiry()) {
+                profile.medium_rotation_rate = Some(characteristics.medium_rotation_rate);
+                profile.zoned_model = match (profile.zoned_model, characteristics.zoned) {
+                    (ZonedModel::HostManaged, _) => ZonedModel::HostManaged,
+                    (_, 0x1) => ZonedModel::HostAware,
+                    (_, 0x2) => ZonedModel::DeviceManaged,
+                    (model, _) => model,
+                };
+            }
+        }
+
+        Ok(profile)
+    }
+
+    pub fn supports_page(&self, page_code: u8) -> bool {
+        self.supported_vital_product_data_pages.contains(&page_code)
+    }
+
+    /// `None` if the device didn't report its supported operations.
+    pub fn supports(&self, operation_code: u8, service_action: Option<u16>) -> Option<bool> {
+        let operations = self.supported_operations.as_ref()?;
+
+        Some(operations.iter().any(|operation| {
+            operation.operation_code == operation_code
+                && (service_action.is_none() || operation.service_action == service_action)
+        }))
+    }
+
+    /// Falls back to whether READ CAPACITY(16) succeeded, as the Linux sd driver does, if the
+    /// device didn't report its supported operations.
+    pub fn supports_read_16(&self) -> bool {
+        self.supports(READ_16, None)
+            .unwrap_or(self.logical_block_count.is_some())
+    }
+
+    pub fn supports_unmap(&self) -> bool {
+        self.logical_block_provisioning_unmap
+            && self.maximum_unmap_lba_count.is_some()
+            && self.supports(UNMAP, None) != Some(false)
+    }
+
+    pub fn supports_write_same_unmap(&self) -> bool {
+        self.logical_block_provisioning_write_same
+    }
+
+    /// `None` if there is no limit or the logical block length is unknown.
+    pub fn maximum_transfer_bytes(&self) -> Option<u64> {
+        Some(self.maximum_transfer_length? as u64 * self.logical_block_length? as u64)
+    }
+}
+
+fn supported_operations(scsi: &Scsi) -> Option<Vec<SupportedOperation>> {
+    const HEADER_LENGTH: u32 = 4;
+    // a few thousand descriptors, don't let a broken device make us allocate gigabytes
+    const MAXIMUM_ALLOCATION_LENGTH: u32 = 0x1_0000;
+
+    let required = match scsi
+        .report_supported_operation_codes()
+        .allocation_length(HEADER_LENGTH)
+        .issue()
+        .ok()?
+    {
+        CommandResult::AllCommands(commands) => commands.required_allocation_length,
+        _ => return None,
+    };
+
+    match scsi
+        .report_supported_operation_codes()
+        .allocation_length(required.min(MAXIMUM_ALLOCATION_LENGTH))
+        .issue()
+        .ok()?
+    {
+        CommandResult::AllCommands(commands) => Some(
+            commands
+                .descriptors
+                .into_iter()
+                .map(|descriptor| SupportedOperation {
+                    operation_code: descriptor.operation_code,
+                    service_action: descriptor.service_action,
+                    cdb_length: descriptor.cdb_length,
+                })
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emulator::EmulatedDisk;
+
+    #[test]
+    fn probe_test() {
+        let mut disk = EmulatedDisk::new(4096, 256).unwrap();
+        disk.vendor_identification("SCSIR").thin_provisioning(true);
+        let scsi = Scsi::with_transport("disk", disk);
+
+        let profile = DeviceProfile::probe(&scsi).unwrap();
+        assert_eq!(profile.vendor_identification, "SCSIR");
+        assert_eq!(profile.peripheral_device_type, 0);
+        assert!(profile.supports_page(BLOCK_LIMITS));
+        // the emulator rejects REPORT SUPPORTED OPERATION CODES
+        assert_eq!(profile.supported_operations, None);
+        assert_eq!(profile.supports(READ_16, None), None);
+        assert!(profile.supports_read_16());
+        assert_eq!(profile.logical_block_length, Some(4096));
+        assert_eq!(profile.logical_block_count, Some(256));
+        assert_eq!(profile.protection_type, None);
+        assert!(profile.thin_provisioned);
+        assert!(profile.supports_unmap());
+        assert!(profile.supports_write_same_unmap());
+        assert_eq!(profile.maximum_transfer_bytes(), Some(0x1_0000 * 4096));
+        assert_eq!(profile.zoned_model, ZonedModel::NotZoned);
+        assert_eq!(profile.medium_rotation_rate, Some(1));
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&profile).unwrap();
+            let restored: DeviceProfile = serde_json::from_str(&json).unwrap();
+            assert_eq!(restored, profile);
+        }
+    }
+}
@@ -0,0 +1,435 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    command::sense::{Descriptor, SenseData, SenseKey},
+    Scsi,
+};
+
+/// The long running operation a device reports progress for, from the additional sense code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Operation {
+    /// LOGICAL UNIT NOT READY, FORMAT IN PROGRESS.
+    Format,
+    /// LOGICAL UNIT NOT READY, SANITIZE IN PROGRESS.
+    Sanitize,
+    /// LOGICAL UNIT NOT READY, SELF-TEST IN PROGRESS.
+    SelfTest,
+    /// LOGICAL UNIT IS IN PROCESS OF BECOMING READY or START STOP UNIT COMMAND IN PROGRESS, e.g.
+    /// after a START STOP UNIT with the IMMED bit set.
+    BecomingReady,
+    /// Any other additional sense code meaning an operation is in progress, e.g. OPERATION IN
+    /// PROGRESS.
+    Other { asc: u8, ascq: u8 },
+}
+
+impl Operation {
+    /// `None` if the additional sense code doesn't mean an operation is in progress.
+    pub fn from_asc(asc: u8, ascq: u8) -> Option<Self> {
+        match (asc, ascq) {
+            (0x04, 0x04) => Some(Self::Format),
+            (0x04, 0x1B) => Some(Self::Sanitize),
+            (0x04, 0x09) => Some(Self::SelfTest),
+            (0x04, 0x01) | (0x04, 0x1A) => Some(Self::BecomingReady),
+            // rebuild, recalculation, operation, long write, space allocation and depopulation
+            (0x04, 0x05..=0x08) | (0x04, 0x14) | (0x04, 0x24) | (0x00, 0x16) => {
+                Some(Self::Other { asc, ascq })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Progress {
+    pub operation: Operation,
+    /// In 65536ths, `None` if the device doesn't tell.
+    pub progress_indication: Option<u16>,
+    /// Extrapolated from the progress since the operation was first seen, `None` until it moved.
+    pub estimated_remaining: Option<Duration>,
+}
+
+impl Progress {
+    pub fn percent(&self) -> Option<f64> {
+        self.progress_indication
+            .map(|progress| progress as f64 * 100.0 / PROGRESS_DENOMINATOR)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum ProgressEvent {
+    /// The operation named by the additional sense code first, then those of the Another
+    /// Progress Indication descriptors.
+    InProgress(Vec<Progress>),
+    Completed,
+    /// The sense data that ended the operation, e.g. MEDIUM FORMAT CORRUPTED.
+    Failed(SenseData),
+}
+
+const PROGRESS_DENOMINATOR: f64 = 65536.0;
+
+/// Polls a device with REQUEST SENSE, or TEST UNIT READY, until the operation in progress
+/// completes or fails, see [`Scsi::monitor_progress`].
+///
+/// Yields an event per poll, UNIT ATTENTION conditions are skipped. Iteration ends after
+/// [`Completed`](ProgressEvent::Completed), [`Failed`](ProgressEvent::Failed) or the first
+/// error.
+#[derive(Clone, Debug)]
+pub struct ProgressMonitor<'a> {
+    interface: &'a Scsi,
+    interval: Duration,
+    test_unit_ready: bool,
+    descriptor_format: bool,
+    first_samples: Vec<(Operation, Instant, u16)>,
+    polled: bool,
+    finished: bool,
+}
+
+impl<'a> ProgressMonitor<'a> {
+    fn new(interface: &'a Scsi) -> Self {
+        Self {
+            interface,
+            interval: Duration::from_secs(5),
+            test_unit_ready: false,
+            descriptor_format: false,
+            first_samples: vec![],
+            polled: false,
+            finished: false,
+        }
+    }
+
+    /// The time between polls, 5 seconds by default.
+    pub fn interval(&mut self, value: Duration) -> &mut Self {
+        self.interval = value;
+        self
+    }
+
+    /// Polls with TEST UNIT READY instead of REQUEST SENSE, for devices that don't report
+    /// progress through the latter.
+    pub fn test_unit_ready(&mut self, value: bool) -> &mut Self {
+        self.test_unit_ready = value;
+        self
+    }
+
+    /// Asks REQUEST SENSE for descriptor format sense data, which may hold Another Progress
+    /// Indication descriptors.
+    pub fn descriptor_format(&mut self, value: bool) -> &mut Self {
+        self.descriptor_format = value;
+        self
+    }
+
+    fn poll(&self) -> crate::Result<SenseData> {
+        let result = match self.test_unit_ready {
+            true => self
+                .interface
+                .test_unit_ready()
+                .issue()
+                .map(|_| SenseData::None),
+            false => self
+                .interface
+                .request_sense()
+                .descriptor_format(self.descriptor_format)
+                .issue(),
+        };
+
+        // a CHECK CONDITION tells the state as well
+        match result {
+            Err(error) => match error.command_error() {
+                Some(command_error) if command_error.sense_key().is_some() => {
+                    Ok(command_error.sense().clone())
+                }
+                _ => Err(error),
+            },
+            sense => sense,
+        }
+    }
+
+    /// `None` if the sense data should be skipped.
+    fn event(&mut self, sense: SenseData) -> Option<ProgressEvent> {
+        let Some(sense_key) = sense.sense_key() else {
+            return Some(match sense {
+                SenseData::None => ProgressEvent::Completed,
+                sense => ProgressEvent::Failed(sense),
+            });
+        };
+
+        let (asc, ascq) = (sense.asc().unwrap_or(0), sense.ascq().unwrap_or(0));
+        let operation = match (sense_key, Operation::from_asc(asc, ascq)) {
+            (SenseKey::UnitAttention, _) => return None,
+            (SenseKey::NoSense | SenseKey::NotReady, Some(operation)) => operation,
+            (SenseKey::NoSense | SenseKey::NotReady, None) if sense.progress().is_some() => {
+                Operation::Other { asc, ascq }
+            }
+            (SenseKey::NoSense | SenseKey::RecoveredError, None) => {
+                return Some(ProgressEvent::Completed)
+            }
+            _ => return Some(ProgressEvent::Failed(sense)),
+        };
+
+        let now = Instant::now();
+        let mut progress = vec![self.progress(operation, sense.progress(), now)];
+        if let SenseData::Descriptor(sense) = &sense {
+            for descriptor in &sense.descriptors {
+                if let Descriptor::AnotherProgressIndication {
+                    additional_sense_code,
+                    progress_indication,
+                    ..
+                } = descriptor
+                {
+                    let (asc, ascq) = additional_sense_code.clone().into();
+                    let operation =
+                        Operation::from_asc(asc, ascq).unwrap_or(Operation::Other { asc, ascq });
+                    progress.push(self.progress(operation, Some(*progress_indication), now));
+                }
+            }
+        }
+
+        Some(ProgressEvent::InProgress(progress))
+    }
+
+    fn progress(
+        &mut self,
+        operation: Operation,
+        progress_indication: Option<u16>,
+        now: Instant,
+    ) -> Progress {
+        let estimated_remaining = progress_indication.and_then(|current| {
+            let sample = self
+                .first_samples
+                .iter_mut()
+                .find(|(sampled, _, _)| *sampled == operation);
+
+            match sample {
+                Some((_, time, first)) if current > *first => {
+                    let remaining =
+                        (PROGRESS_DENOMINATOR - current as f64) / (current - *first) as f64;
+                    Some(now.duration_since(*time).mul_f64(remaining))
+                }
+                // the operation started over
+                Some((_, time, first)) if current < *first => {
+                    (*time, *first) = (now, current);
+                    None
+                }
+                Some(_) => None,
+                None => {
+                    self.first_samples.push((operation, now, current));
+                    None
+                }
+            }
+        });
+
+        Progress {
+            operation,
+            progress_indication,
+            estimated_remaining,
+        }
+    }
+}
+
+impl Iterator for ProgressMonitor<'_> {
+    type Item = crate::Result<ProgressEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            if self.polled {
+                thread::sleep(self.interval);
+            }
+            self.polled = true;
+
+            let event = match self.poll() {
+                Ok(sense) => self.event(sense),
+                Err(error) => {
+                    self.finished = true;
+                    return Some(Err(error));
+                }
+            };
+
+            if let Some(event) = event {
+                self.finished = !matches!(event, ProgressEvent::InProgress(_));
+                return Some(Ok(event));
+            }
+        }
+
+        None
+    }
+}
+
+impl Scsi {
+    /// Follows a FORMAT UNIT, SANITIZE, self-test or START STOP UNIT issued with the IMMED bit
+    /// set, see [`ProgressMonitor`].
+    pub fn monitor_progress(&self) -> ProgressMonitor<'_> {
+        ProgressMonitor::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::VecDeque, io, sync::Mutex};
+
+    use super::*;
+    use crate::{
+        command::sense::{SenseDataBuilder, SenseKeySpecific},
+        Transport, TransportRequest, TransportResponse,
+    };
+
+    const REQUEST_SENSE: u8 = 0x03;
+
+    /// Answers REQUEST SENSE with the next sense data as parameter data, any other command with
+    /// CHECK CONDITION, GOOD once there is none left.
+    #[derive(Debug)]
+    struct Script(Mutex<VecDeque<Vec<u8>>>);
+
+    impl Script {
+        fn new(senses: &[&SenseDataBuilder], descriptor_format: bool) -> Self {
+            Self(Mutex::new(
+                senses
+                    .iter()
+                    .map(|sense| sense.to_bytes(descriptor_format).unwrap())
+                    .collect(),
+            ))
+        }
+    }
+
+    impl Transport for Script {
+        fn execute(&self, request: TransportRequest) -> io::Result<TransportResponse> {
+            let sense = self.0.lock().unwrap().pop_front().unwrap_or_default();
+
+            if request.command[0] == REQUEST_SENSE {
+                request.data[..sense.len()].copy_from_slice(&sense);
+                return Ok(TransportResponse {
+                    residual: request.data.len() - sense.len(),
+                    ..Default::default()
+                });
+            }
+
+            request.sense[..sense.len()].copy_from_slice(&sense);
+            Ok(TransportResponse {
+                status: if sense.is_empty() { 0x00 } else { 0x02 },
+                sense_length: sense.len(),
+                ..Default::default()
+            })
+        }
+    }
+
+    /// Like a SATL that doesn't report a residual.
+    #[derive(Debug)]
+    struct NoResidual(Script);
+
+    impl Transport for NoResidual {
+        fn execute(&self, request: TransportRequest) -> io::Result<TransportResponse> {
+            let response = self.0.execute(request)?;
+            Ok(TransportResponse {
+                residual: 0,
+                ..response
+            })
+        }
+    }
+
+    fn in_progress(progress: u16) -> SenseDataBuilder {
+        let mut sense = SenseDataBuilder::new(SenseKey::NotReady, 0x04, 0x04);
+        sense.sense_key_specific(SenseKeySpecific::NoSense {
+            progress_indication: progress,
+        });
+        sense
+    }
+
+    fn events(scsi: &Scsi, test_unit_ready: bool) -> Vec<ProgressEvent> {
+        scsi.monitor_progress()
+            .interval(Duration::from_millis(1))
+            .test_unit_ready(test_unit_ready)
+            .descriptor_format(true)
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    #[test]
+    fn request_sense_test() {
+        let mut sanitize = SenseDataBuilder::new(SenseKey::NotReady, 0x04, 0x1B);
+        sanitize.descriptor(Descriptor::AnotherProgressIndication {
+            sense_key: SenseKey::NotReady,
+            additional_sense_code: (0x04, 0x09).into(),
+            progress_indication: 0x2000,
+        });
+        let scsi = Scsi::with_transport(
+            "disk",
+            Script::new(
+                &[
+                    &in_progress(0x4000),
+                    &SenseDataBuilder::new(SenseKey::UnitAttention, 0x29, 0x00),
+                    &in_progress(0x8000),
+                    &sanitize,
+                    &SenseDataBuilder::new(SenseKey::NoSense, 0x00, 0x00),
+                    &in_progress(0x1000),
+                ],
+                true,
+            ),
+        );
+
+        let polled = events(&scsi, false);
+        assert_eq!(polled.len(), 4);
+        let ProgressEvent::InProgress(first) = &polled[0] else {
+            panic!("{:?}", polled);
+        };
+        assert_eq!(first[0].operation, Operation::Format);
+        assert_eq!(first[0].percent(), Some(25.0));
+        assert_eq!(first[0].estimated_remaining, None);
+        let ProgressEvent::InProgress(second) = &polled[1] else {
+            panic!("{:?}", polled);
+        };
+        assert_eq!(second[0].progress_indication, Some(0x8000));
+        assert!(second[0].estimated_remaining.is_some());
+        let ProgressEvent::InProgress(third) = &polled[2] else {
+            panic!("{:?}", polled);
+        };
+        assert_eq!(third[0].operation, Operation::Sanitize);
+        assert_eq!(third[0].progress_indication, None);
+        assert_eq!(third[1].operation, Operation::SelfTest);
+        assert_eq!(third[1].percent(), Some(12.5));
+        assert!(matches!(polled[3], ProgressEvent::Completed));
+    }
+
+    #[test]
+    fn test_unit_ready_test() {
+        let scsi = Scsi::with_transport(
+            "disk",
+            Script::new(
+                &[
+                    &in_progress(0x4000),
+                    &SenseDataBuilder::new(SenseKey::MediumError, 0x31, 0x00),
+                ],
+                false,
+            ),
+        );
+        let polled = events(&scsi, true);
+        assert!(matches!(&polled[..], [
+            ProgressEvent::InProgress(_),
+            ProgressEvent::Failed(sense),
+        ] if sense.asc() == Some(0x31)));
+
+        let scsi = Scsi::with_transport("disk", Script::new(&[], false));
+        assert!(matches!(
+            &events(&scsi, true)[..],
+            [ProgressEvent::Completed]
+        ));
+    }
+
+    #[test]
+    fn no_residual_test() {
+        let scsi = Scsi::with_transport(
+            "satl",
+            NoResidual(Script::new(
+                &[
+                    &in_progress(0x4000),
+                    &SenseDataBuilder::new(SenseKey::NoSense, 0x00, 0x00),
+                ],
+                false,
+            )),
+        );
+        assert!(matches!(
+            &events(&scsi, false)[..],
+            [ProgressEvent::InProgress(_), ProgressEvent::Completed]
+        ));
+    }
+}
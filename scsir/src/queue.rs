@@ -0,0 +1,278 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    io,
+    os::fd::{AsRawFd, RawFd},
+    sync::Arc,
+    time::Duration,
+};
+
+use nix::libc;
+
+use crate::{
+    command::sense::MAX_SENSE_BUFFER_LENGTH,
+    file_descriptor::FileDescriptor,
+    os::linux::{read_response, write_request, AsyncResponse},
+    scsi::{command_bytes, data_buffers, transfer_length},
+    transport::{TransportRequest, TransportResponse},
+    Command, Scsi,
+};
+
+/// Keeps several commands in flight on one sg character device, through the asynchronous
+/// `write()`/`read()` interface of the sg driver.
+///
+/// Commands are matched back to their submission with the `pack_id` field, and the `tag` given
+/// to [`submit`](CommandQueue::submit) travels in the `user_pointer` field. The sg driver
+/// limits the number of outstanding commands per file descriptor, 16 by default, and refuses
+/// more with `EDOM`. Only one queue should be used per [`Scsi`] at a time, otherwise they
+/// would steal each other's completions.
+///
+/// Dropping the queue waits for every outstanding command.
+pub struct CommandQueue<R> {
+    file_descriptor: Arc<FileDescriptor>,
+    timeout: Duration,
+    next_pack_id: i32,
+    pending: HashMap<i32, Box<dyn Pending<R>>>,
+}
+
+#[derive(Debug)]
+pub struct Completion<R> {
+    pub tag: usize,
+    pub result: R,
+}
+
+trait Pending<R> {
+    fn complete(self: Box<Self>, response: io::Result<TransportResponse>) -> R;
+}
+
+struct PendingCommand<T: Command> {
+    command: T,
+    data_buffer: T::DataBufferWrapper,
+    size_of_data_buffer: usize,
+    sense_buffer: [u8; MAX_SENSE_BUFFER_LENGTH],
+}
+
+impl<T: Command> Pending<T::ReturnType> for PendingCommand<T> {
+    fn complete(mut self: Box<Self>, response: io::Result<TransportResponse>) -> T::ReturnType {
+        Scsi::process_response(
+            &self.command,
+            command_bytes(&self.command.command()),
+            &mut self.data_buffer,
+            self.size_of_data_buffer,
+            &self.sense_buffer,
+            response,
+        )
+    }
+}
+
+impl<R> CommandQueue<R> {
+    pub(crate) fn new(file_descriptor: Arc<FileDescriptor>, timeout: Duration) -> Self {
+        Self {
+            file_descriptor,
+            timeout,
+            next_pack_id: 0,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Sends `command` to the device without waiting for it.
+    pub fn submit<T>(&mut self, command: T, tag: usize) -> crate::Result<()>
+    where
+        T: Command<ReturnType = R> + 'static,
+    {
+        let data_buffer = command.data();
+        let size_of_data_buffer = transfer_length(&command);
+        let mut pending = Box::new(PendingCommand {
+            command,
+            data_buffer,
+            size_of_data_buffer,
+            sense_buffer: [0; MAX_SENSE_BUFFER_LENGTH],
+        });
+
+        let pack_id = self.next_pack_id;
+        self.next_pack_id = self.next_pack_id.wrapping_add(1) & i32::MAX;
+
+        let command_buffer = pending.command.command();
+        let PendingCommand {
+            command,
+            data_buffer,
+            sense_buffer,
+            ..
+        } = pending.as_mut();
+        unsafe {
+            let (data, data_out) = data_buffers(&*command, data_buffer);
+            write_request(
+                self.file_descriptor.raw(),
+                TransportRequest {
+                    command: command_bytes(&command_buffer),
+                    direction: command.direction(),
+                    data,
+                    data_out,
+                    sense: sense_buffer,
+                    timeout: command.timeout_override().unwrap_or(self.timeout),
+                },
+                pack_id,
+                tag,
+            )?;
+        }
+
+        self.pending.insert(pack_id, pending);
+
+        Ok(())
+    }
+
+    /// Number of submitted commands that haven't been completed yet.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Waits at most `timeout` for a command to complete, forever if `None`. Returns `None` if
+    /// nothing completed in time, or nothing is outstanding.
+    pub fn poll(&mut self, timeout: Option<Duration>) -> crate::Result<Option<Completion<R>>> {
+        if self.pending.is_empty() {
+            return Ok(None);
+        }
+
+        let mut poll_fd = libc::pollfd {
+            fd: self.file_descriptor.raw(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout = match timeout {
+            Some(timeout) => timeout.as_millis().min(i32::MAX as u128) as i32,
+            None => -1,
+        };
+
+        let ready = unsafe { libc::poll(&mut poll_fd, 1, timeout) };
+        if ready < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        if ready == 0 {
+            return Ok(None);
+        }
+
+        self.read_completion().map(Some)
+    }
+
+    /// Blocks until a command completes, returns `None` only if nothing is outstanding.
+    pub fn wait(&mut self) -> crate::Result<Option<Completion<R>>> {
+        self.poll(None)
+    }
+
+    /// Reads one finished command, blocks if none is ready.
+    fn read_completion(&mut self) -> crate::Result<Completion<R>> {
+        let AsyncResponse {
+            pack_id,
+            user_pointer,
+            response,
+        } = read_response(self.file_descriptor.raw())?;
+
+        let pending = self.pending.remove(&pack_id).ok_or_else(|| {
+            crate::Error::Other(format!(
+                "completion of unknown pack id {}, is the device shared by another queue?",
+                pack_id
+            ))
+        })?;
+
+        Ok(Completion {
+            tag: user_pointer,
+            result: pending.complete(Ok(response)),
+        })
+    }
+}
+
+impl<R> AsRawFd for CommandQueue<R> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.file_descriptor.raw()
+    }
+}
+
+impl<R> Debug for CommandQueue<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CommandQueue")
+            .field("file_descriptor", &self.file_descriptor)
+            .field("timeout", &self.timeout)
+            .field("outstanding", &self.pending.len())
+            .finish()
+    }
+}
+
+impl<R> Drop for CommandQueue<R> {
+    fn drop(&mut self) {
+        // the driver copies data into the buffers while reading, they must outlive the commands
+        while !self.pending.is_empty() {
+            if self.read_completion().is_err() {
+                // can't tell which buffers are still in use, leak them instead
+                for (_, pending) in self.pending.drain() {
+                    std::mem::forget(pending);
+                }
+            }
+        }
+    }
+}
+
+impl Scsi {
+    /// Creates a queue for asynchronous commands, only sg character devices support it.
+    ///
+    /// Queued commands go to the driver directly, so the queue can't be created while
+    /// interceptors are attached, they would never see them.
+    pub fn command_queue<R>(&self) -> crate::Result<CommandQueue<R>> {
+        if self.has_interceptors() {
+            return Err(crate::Error::Other(
+                "queued commands bypass interceptors, clear them first.".to_owned(),
+            ));
+        }
+
+        let file_descriptor = match self.file_descriptor() {
+            Some(file_descriptor) if file_descriptor.is_char()? => file_descriptor.clone(),
+            _ => {
+                return Err(crate::Error::Other(format!(
+                    "{} is not an SCSI Generic character device.",
+                    self.path().display()
+                )))
+            }
+        };
+
+        Ok(CommandQueue::new(file_descriptor, self.timeout()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emulator::EmulatedDisk;
+
+    #[test]
+    fn custom_transport_test() {
+        let scsi = Scsi::with_transport("emulated", EmulatedDisk::new(512, 16).unwrap());
+
+        assert!(scsi.command_queue::<crate::Result<Vec<u8>>>().is_err());
+        assert!(scsi
+            .read()
+            .transfer_length(1)
+            .prepare_16()
+            .map(|command| scsi.issue(&command))
+            .unwrap()
+            .is_ok());
+    }
+
+    #[test]
+    fn interceptor_test() {
+        #[derive(Debug)]
+        struct NopInterceptor;
+
+        impl crate::Interceptor for NopInterceptor {}
+
+        let mut scsi = Scsi::with_transport("emulated", EmulatedDisk::new(512, 16).unwrap());
+        scsi.add_interceptor(NopInterceptor);
+
+        match scsi.command_queue::<crate::Result<Vec<u8>>>() {
+            Err(crate::Error::Other(message)) => assert!(message.contains("interceptors")),
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+}
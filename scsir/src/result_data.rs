@@ -1,13 +1,18 @@
 use std::io;
 
-use crate::{command::sense::SenseData, error};
+use crate::{
+    command::sense::{SenseData, SenseKey},
+    error, CommandError,
+};
 
 #[cfg(target_os = "linux")]
-use crate::os::linux::{DriverStatus, HostStatus};
+use crate::os::linux::{AuxiliaryInfo, DriverStatus, HostStatus};
 
 #[derive(Debug)]
 pub struct ResultData<'a, D> {
     pub(crate) ioctl_result: i32,
+    pub(crate) ioctl_error: Option<io::Error>,
+    pub(crate) command: &'a [u8],
     pub(crate) transfered_data_length: usize,
     pub(crate) data: &'a mut D,
     pub(crate) transfered_sense_length: usize,
@@ -17,10 +22,12 @@ pub struct ResultData<'a, D> {
     pub(crate) host_status: HostStatus,
     #[cfg(target_os = "linux")]
     pub(crate) driver_status: DriverStatus,
+    #[cfg(target_os = "linux")]
+    pub(crate) auxiliary_info: AuxiliaryInfo,
 }
 
 #[allow(dead_code)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Status {
     Good,
     CheckCondition,
@@ -35,41 +42,57 @@ pub enum Status {
 
 impl<D> ResultData<'_, D> {
     pub fn check_common_error(&self) -> crate::Result<()> {
-        let mut result = String::new();
-
         #[cfg(target_os = "linux")]
+        // sg sets DRIVER_SENSE whenever there is sense data, which the checks below look at
+        let is_transport_error = !matches!(self.host_status, HostStatus::Ok)
+            || !self
+                .driver_status
+                .difference(DriverStatus::SENSE)
+                .is_empty();
+        #[cfg(target_os = "windows")]
+        let is_transport_error = false;
+
+        // the command completed, the device only had to try harder
+        let is_recovered_error = self.sense_buffer.sense_key() == Some(SenseKey::RecoveredError);
+
+        if is_transport_error
+            || (!is_recovered_error
+                && (!matches!(self.status, Status::Good) || self.transfered_sense_length != 0))
         {
-            if !matches!(self.host_status, crate::os::linux::HostStatus::Ok) {
-                result.push_str(&format!("host status: {:?}. ", self.host_status));
-            }
-
-            if !self.driver_status.is_empty() {
-                result.push_str(&format!("driver status: {:?}. ", self.driver_status));
-            }
+            return Err(crate::Error::CheckCondition(self.command_error()));
         }
 
-        if !matches!(self.status, Status::Good) {
-            result.push_str(&format!("Status: {:?}. ", self.status));
-        }
-
-        if self.transfered_sense_length != 0 {
-            result.push_str(&format!("Sense data: {:02X?}", self.sense_buffer));
-        }
+        Ok(())
+    }
 
-        if !result.is_empty() {
-            return Err(crate::Error::Other(result));
+    pub fn command_error(&self) -> CommandError {
+        CommandError {
+            status: self.status,
+            #[cfg(target_os = "linux")]
+            host_status: self.host_status,
+            #[cfg(target_os = "linux")]
+            driver_status: self.driver_status,
+            sense: self.sense_buffer.clone(),
+            command: self.command.to_vec(),
+            transferred_length: self.transfered_data_length,
         }
-
-        Ok(())
     }
 
     pub fn check_ioctl_error(&self) -> crate::Result<()> {
-        match self.ioctl_result {
-            0 => Ok(()),
-            _ => Err(error::Error::IO(io::Error::last_os_error())),
+        match &self.ioctl_error {
+            None => Ok(()),
+            Some(error) => Err(error::Error::IO(match error.raw_os_error() {
+                Some(code) => io::Error::from_raw_os_error(code),
+                None => io::Error::new(error.kind(), error.to_string()),
+            })),
         }
     }
 
+    /// The command descriptor block that was issued.
+    pub fn command(&self) -> &[u8] {
+        self.command
+    }
+
     pub fn ioctl_result(&self) -> i32 {
         self.ioctl_result
     }
@@ -0,0 +1,268 @@
+use std::time::Duration;
+
+use crate::{
+    command::sense::{SenseData, SenseKey, MAX_SENSE_BUFFER_LENGTH},
+    result_data::Status,
+    Command, DataDirection, Scsi, TransportResponse,
+};
+
+#[cfg(target_os = "linux")]
+use crate::os::linux::HostStatus;
+
+/// Transient conditions worth issuing a command again for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RetryCategory {
+    /// UNIT ATTENTION, e.g. after a reset or a medium change.
+    UnitAttention,
+    /// NOT READY, LOGICAL UNIT IS IN PROCESS OF BECOMING READY.
+    BecomingReady,
+    /// BUSY status.
+    Busy,
+    /// TASK SET FULL status.
+    TaskSetFull,
+    /// The host adapter couldn't deliver the command, e.g. `DID_BUS_BUSY` or `DID_REQUEUE`.
+    HostBusy,
+    /// The command may have been partially executed before it was aborted, e.g. ABORTED
+    /// COMMAND, TASK ABORTED, `DID_SOFT_ERROR` or `DID_RESET`.
+    Aborted,
+}
+
+const CATEGORY_COUNT: usize = 6;
+
+impl RetryCategory {
+    pub fn classify(
+        response: &TransportResponse,
+        sense: &[u8; MAX_SENSE_BUFFER_LENGTH],
+    ) -> Option<Self> {
+        #[cfg(target_os = "linux")]
+        match HostStatus::from(response.host_status) {
+            HostStatus::Ok => {}
+            HostStatus::BusBusy | HostStatus::ImmediateRetry | HostStatus::Requeue => {
+                return Some(Self::HostBusy)
+            }
+            HostStatus::SoftError | HostStatus::Reset => return Some(Self::Aborted),
+            _ => return None,
+        }
+
+        match Status::from(response.status) {
+            Status::Busy => return Some(Self::Busy),
+            Status::TaskSetFull => return Some(Self::TaskSetFull),
+            Status::TaskAborted => return Some(Self::Aborted),
+            Status::CheckCondition => {}
+            _ => return None,
+        }
+
+        let sense = SenseData::parse(sense, response.sense_length.min(MAX_SENSE_BUFFER_LENGTH));
+        match (sense.sense_key()?, (sense.asc()?, sense.ascq()?)) {
+            (SenseKey::UnitAttention, _) => Some(Self::UnitAttention),
+            (SenseKey::NotReady, (0x04, 0x01)) => Some(Self::BecomingReady),
+            (SenseKey::AbortedCommand, _) => Some(Self::Aborted),
+            _ => None,
+        }
+    }
+
+    /// Whether the device may have acted on the command, so that issuing it again is only
+    /// safe if it is idempotent.
+    pub fn may_have_executed(&self) -> bool {
+        matches!(self, Self::Aborted)
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// How many times each category was retried.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Retries {
+    counts: [u32; CATEGORY_COUNT],
+}
+
+impl Retries {
+    pub fn get(&self, category: RetryCategory) -> u32 {
+        self.counts[category.index()]
+    }
+
+    pub fn total(&self) -> u32 {
+        self.counts.iter().sum()
+    }
+
+    pub(crate) fn add(&mut self, other: &Retries) {
+        for (count, other) in self.counts.iter_mut().zip(other.counts) {
+            *count = count.saturating_add(other);
+        }
+    }
+}
+
+/// Issues a command again when it fails with a transient condition, at most a limited number
+/// of times per [`RetryCategory`], waiting longer and longer between attempts.
+///
+/// Commands that write data are not retried after a condition that
+/// [may have executed](RetryCategory::may_have_executed) them, unless
+/// [`retry_writes`](RetryPolicy::retry_writes) is set. Attach it with
+/// [`Scsi::set_retry_policy`], or issue single commands with [`RetryPolicy::issue`].
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    limits: [u32; CATEGORY_COUNT],
+    initial_delay: Duration,
+    maximum_delay: Duration,
+    retry_writes: bool,
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        Self {
+            limits: [3, 10, 5, 5, 5, 2],
+            initial_delay: Duration::from_millis(10),
+            maximum_delay: Duration::from_secs(1),
+            retry_writes: false,
+        }
+    }
+
+    /// Never retries anything, a starting point to enable single categories.
+    pub fn none() -> Self {
+        Self {
+            limits: [0; CATEGORY_COUNT],
+            ..Self::new()
+        }
+    }
+
+    pub fn limit(&mut self, category: RetryCategory, value: u32) -> &mut Self {
+        self.limits[category.index()] = value;
+        self
+    }
+
+    /// The first retry waits `initial`, every following one of the same category twice as long,
+    /// but never more than `maximum`. UNIT ATTENTION is retried without waiting.
+    pub fn backoff(&mut self, initial: Duration, maximum: Duration) -> &mut Self {
+        self.initial_delay = initial;
+        self.maximum_delay = maximum;
+        self
+    }
+
+    pub fn retry_writes(&mut self, value: bool) -> &mut Self {
+        self.retry_writes = value;
+        self
+    }
+
+    /// Issues `command` through `scsi` with this policy instead of the one attached to `scsi`.
+    pub fn issue<T: Command>(&self, scsi: &Scsi, command: &T) -> (T::ReturnType, Retries) {
+        scsi.issue_with_retries(command, Some(self))
+    }
+
+    /// Counts the retry in `retries` and returns how long to wait before it, `None` if the
+    /// command shouldn't be issued again.
+    pub(crate) fn next_delay(
+        &self,
+        direction: DataDirection,
+        response: &TransportResponse,
+        sense: &[u8; MAX_SENSE_BUFFER_LENGTH],
+        retries: &mut Retries,
+    ) -> Option<Duration> {
+        let category = RetryCategory::classify(response, sense)?;
+
+        if category.may_have_executed()
+            && !self.retry_writes
+            && matches!(
+                direction,
+                DataDirection::ToDevice | DataDirection::ToFromDevice
+            )
+        {
+            return None;
+        }
+
+        let count = retries.get(category);
+        if count >= self.limits[category.index()] {
+            return None;
+        }
+        retries.counts[category.index()] += 1;
+
+        match category {
+            RetryCategory::UnitAttention => Some(Duration::ZERO),
+            _ => Some(
+                self.initial_delay
+                    .saturating_mul(1u32.checked_shl(count).unwrap_or(u32::MAX))
+                    .min(self.maximum_delay),
+            ),
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        sync::atomic::{AtomicU32, Ordering},
+    };
+
+    use super::*;
+    use crate::{Transport, TransportRequest};
+
+    /// Reports `sense` for the first `failures` commands.
+    #[derive(Debug)]
+    struct FlakyTransport {
+        sense: [u8; 18],
+        failures: AtomicU32,
+    }
+
+    impl FlakyTransport {
+        fn new(sense_key: u8, asc: u8, ascq: u8, failures: u32) -> Self {
+            Self {
+                sense: [
+                    0x70, 0, sense_key, 0, 0, 0, 0, 10, 0, 0, 0, 0, asc, ascq, 0, 0, 0, 0,
+                ],
+                failures: AtomicU32::new(failures),
+            }
+        }
+    }
+
+    impl Transport for FlakyTransport {
+        fn execute(&self, request: TransportRequest) -> io::Result<TransportResponse> {
+            if self.failures.load(Ordering::Relaxed) == 0 {
+                return Ok(TransportResponse::default());
+            }
+            self.failures.fetch_sub(1, Ordering::Relaxed);
+
+            request.sense[..self.sense.len()].copy_from_slice(&self.sense);
+            Ok(TransportResponse {
+                status: 0x02,
+                sense_length: self.sense.len(),
+                ..Default::default()
+            })
+        }
+    }
+
+    #[test]
+    fn retry_test() {
+        let mut scsi = Scsi::with_transport("flaky", FlakyTransport::new(0x06, 0x29, 0x00, 2));
+        assert!(scsi.test_unit_ready().issue().is_err());
+
+        scsi.set_retry_policy(Some(RetryPolicy::new()));
+        assert!(scsi.test_unit_ready().issue().is_ok());
+        assert_eq!(scsi.retries().get(RetryCategory::UnitAttention), 1);
+
+        let scsi = Scsi::with_transport("flaky", FlakyTransport::new(0x02, 0x04, 0x01, 3));
+        let mut policy = RetryPolicy::none();
+        policy
+            .limit(RetryCategory::BecomingReady, 2)
+            .backoff(Duration::ZERO, Duration::ZERO);
+        let (result, retries) =
+            policy.issue(&scsi, &scsi.read().transfer_length(1).prepare_10().unwrap());
+        assert!(result.is_err());
+        assert_eq!(retries.total(), 2);
+
+        // an aborted write may have reached the medium
+        let scsi = Scsi::with_transport("flaky", FlakyTransport::new(0x0B, 0x00, 0x00, 1));
+        let policy = RetryPolicy::new();
+        let write = scsi.write().parameter(&[0; 512]).prepare_10().unwrap();
+        let (result, retries) = policy.issue(&scsi, &write);
+        assert!(result.is_err());
+        assert_eq!(retries.total(), 0);
+    }
+}
@@ -3,22 +3,51 @@
 use std::{
     borrow::BorrowMut,
     fs::OpenOptions,
-    io,
+    io::{self, IoSliceMut},
     mem::size_of_val,
     path::{Path, PathBuf},
+    slice,
+    sync::{Arc, Mutex},
+    thread,
     time::Duration,
 };
 
-use crate::{file_descriptor::FileDescriptor, Command};
+#[cfg(target_os = "linux")]
+use crate::os::linux::{
+    supports_sg_v4, AccessFlags, AuxiliaryInfo, DriverStatus, MemoryMap, SgTransport, SgV4Transport,
+};
+use crate::{
+    command::sense::{SenseData, MAX_SENSE_BUFFER_LENGTH},
+    completion_info::{self, CompletionInfo},
+    file_descriptor::FileDescriptor,
+    interceptor::{CompletedCommand, Interceptor, PendingCommand},
+    result_data::{ResultData, Status},
+    retry::{Retries, RetryPolicy},
+    transport::{Transport, TransportRequest, TransportResponse},
+    Command, DataDirection, DeviceProfile, ScsiAddress,
+};
 
 #[derive(Debug)]
 pub struct Scsi {
     path: PathBuf,
-    file_descriptor: FileDescriptor,
+    file_descriptor: Option<Arc<FileDescriptor>>,
+    transport: Box<dyn Transport>,
+    #[cfg(target_os = "linux")]
+    sg_transport: Option<Arc<SgTransport>>,
+    address: Option<ScsiAddress>,
     timeout: Duration,
+    retry_policy: Option<RetryPolicy>,
+    retries: Mutex<Retries>,
+    interceptors: Vec<Box<dyn Interceptor>>,
+    profile: Mutex<Option<Arc<DeviceProfile>>>,
 }
 
 impl Scsi {
+    /// Opens an sg, bsg or block device node on Linux, a disk, volume or tape on Windows.
+    ///
+    /// Linux bsg nodes, e.g. `/dev/bsg/0:0:0:0`, are driven with sg v4 headers, which
+    /// bidirectional commands and CDBs longer than 255 bytes need. Other nodes only get them from
+    /// an sg driver of version 4.0 or later, mainline kernels ship version 3.5.
     pub fn new<P: AsRef<Path> + ?Sized>(path: &P) -> crate::Result<Scsi> {
         let mut options = OpenOptions::new();
         options.read(true).write(true);
@@ -33,197 +62,330 @@ impl Scsi {
         Self::from_descriptor(path, file_descriptor)
     }
 
-    #[cfg(target_os = "linux")]
-    pub fn issue<T: Command>(&self, command: &T) -> T::ReturnType {
-        use nix::libc;
+    /// Wraps a custom [`Transport`], e.g. a mock or an emulator, so that every command builder
+    /// can be issued against it. `path` is only used for identification.
+    pub fn with_transport<P: AsRef<Path> + ?Sized, T: Transport + 'static>(
+        path: &P,
+        transport: T,
+    ) -> Scsi {
+        Scsi {
+            path: path.as_ref().to_owned(),
+            file_descriptor: None,
+            transport: Box::new(transport),
+            #[cfg(target_os = "linux")]
+            sg_transport: None,
+            address: None,
+            timeout: Duration::from_millis(SG_DEFAULT_TIMEOUT),
+            retry_policy: None,
+            retries: Mutex::default(),
+            interceptors: vec![],
+            profile: Mutex::default(),
+        }
+    }
 
-        use crate::{
-            command::sense::{SenseData, MAX_SENSE_BUFFER_LENGTH},
-            os::linux::{AccessFlags, AuxiliaryInfo, DriverStatus, SgIoHeader},
-            result_data::{ResultData, Status},
-        };
+    pub fn issue<T: Command>(&self, command: &T) -> T::ReturnType {
+        let (result, retries) = self.issue_with_retries(command, self.retry_policy.as_ref());
+        if retries.total() != 0 {
+            self.retries.lock().unwrap().add(&retries);
+        }
 
-        const SG_IO: u32 = 0x2285;
+        result
+    }
 
+    /// Issues `command` until it succeeds or `policy` gives up on it.
+    pub(crate) fn issue_with_retries<T: Command>(
+        &self,
+        command: &T,
+        policy: Option<&RetryPolicy>,
+    ) -> (T::ReturnType, Retries) {
         let command_buffer = command.command();
         let mut data_buffer = command.data();
-        let raw_pointer_to_data_buffer = &mut data_buffer as *mut _;
         let mut sense_buffer = [0u8; MAX_SENSE_BUFFER_LENGTH];
 
-        let size_of_command_buffer = size_of_val(&command_buffer) as u8;
-        let size_of_data_buffer = command.data_size();
-        let size_of_sense_buffer = size_of_val(&sense_buffer) as u8;
-
-        let pointer_of_command_buffer = Some(&command_buffer);
-
-        let pointer_of_data_buffer = if size_of_data_buffer == 0 {
-            None
-        } else {
-            Some(data_buffer.borrow_mut())
-        };
-
-        let pointer_of_sense_buffer = Some(&mut sense_buffer);
+        let size_of_data_buffer = transfer_length(command);
 
         let timeout = command.timeout_override().unwrap_or(self.timeout);
 
-        let mut sg_header = SgIoHeader {
-            interface_id: b'S' as i32,
-            data_direction: command.direction().into(),
-            command_length: size_of_command_buffer,
-            max_sense_buffer_length: size_of_sense_buffer,
-            iovec_count: 0,
-            data_length: size_of_data_buffer,
-            data: pointer_of_data_buffer,
-            command: pointer_of_command_buffer,
-            sense_buffer: pointer_of_sense_buffer,
-            timeout: timeout
-                .as_millis()
-                .clamp(u32::MIN as u128, u32::MAX as u128) as u32,
-            flags: AccessFlags::DEFAULT,
-            pack_id: 0,
-            user_pointer: 0,
-            status: 0,
-            masked_status: 0,
-            message_status: 0,
-            sense_buffer_written: 0,
-            host_status: 0,
-            driver_status: DriverStatus::OK,
-            residual_count: 0,
-            duration: 0,
-            info: AuxiliaryInfo::OK,
-        };
+        let mut retries = Retries::default();
+        let (transport_result, rewritten_command) = loop {
+            sense_buffer.fill(0);
+
+            let (data, data_out) = unsafe { data_buffers(command, &mut data_buffer) };
+            let (transport_result, rewritten_command) = self.execute(
+                TransportRequest {
+                    command: command_bytes(&command_buffer),
+                    direction: command.direction(),
+                    data,
+                    data_out,
+                    sense: &mut sense_buffer,
+                    timeout,
+                },
+                None,
+            );
 
-        let ioctl_result = unsafe {
-            libc::ioctl(
-                self.file_descriptor.raw(),
-                SG_IO.try_into().unwrap(),
-                &mut sg_header,
-            )
+            let delay = match (&transport_result, policy) {
+                (Ok(response), Some(policy)) => {
+                    policy.next_delay(command.direction(), response, &sense_buffer, &mut retries)
+                }
+                _ => None,
+            };
+
+            match delay {
+                Some(delay) => thread::sleep(delay),
+                None => break (transport_result, rewritten_command),
+            }
         };
 
-        let sense_buffer_written = sg_header.sense_buffer_written as usize;
-        let sense_data = sg_header
-            .sense_buffer
-            .map(|b| SenseData::parse(b, sense_buffer_written))
-            .unwrap_or(SenseData::None);
+        self.record_completion(
+            &transport_result,
+            &sense_buffer,
+            size_of_data_buffer,
+            retries,
+        );
 
-        let result_data = ResultData {
-            ioctl_result,
-            transfered_data_length: sg_header.data_length as usize
-                - sg_header.residual_count as usize,
-            data: unsafe { &mut *raw_pointer_to_data_buffer },
-            transfered_sense_length: sense_buffer_written,
-            sense_buffer: &sense_data,
-            status: Status::from(sg_header.status),
-            host_status: sg_header.host_status.into(),
-            driver_status: sg_header.driver_status,
-        };
+        let result = Self::process_response(
+            command,
+            rewritten_command
+                .as_deref()
+                .unwrap_or(command_bytes(&command_buffer)),
+            &mut data_buffer,
+            size_of_data_buffer,
+            &sense_buffer,
+            transport_result,
+        );
 
-        command.process_result(result_data)
+        (result, retries)
     }
 
-    #[cfg(target_os = "windows")]
-    pub fn issue<T: Command>(&self, command: &T) -> T::ReturnType {
-        use std::slice;
+    /// Like `issue`, but the data is scattered over, or gathered from, `buffers`. Only the
+    /// first `command.data_size()` bytes of `buffers` take part in the transfer.
+    pub(crate) fn issue_vectored<T: Command>(
+        &self,
+        command: &T,
+        buffers: &mut [IoSliceMut],
+    ) -> T::ReturnType {
+        let command_buffer = command.command();
+        let mut data_buffer = command.data();
+        let mut sense_buffer = [0u8; MAX_SENSE_BUFFER_LENGTH];
 
-        use windows::Win32::{
-            Foundation::HANDLE,
-            Storage::IscsiDisc::{
-                IOCTL_SCSI_PASS_THROUGH_DIRECT, SCSI_IOCTL_DATA_BIDIRECTIONAL, SCSI_IOCTL_DATA_IN,
-                SCSI_IOCTL_DATA_OUT, SCSI_IOCTL_DATA_UNSPECIFIED,
-            },
-            System::IO::DeviceIoControl,
-        };
+        let size_of_data_buffer = command.data_size() as usize;
 
-        use crate::{
-            command::sense::SenseData,
-            os::windows::ScsiPassThroughDirectWrapper,
-            result_data::{ResultData, Status},
-        };
+        let mut remaining = size_of_data_buffer;
+        let mut segments = Vec::with_capacity(buffers.len());
+        for buffer in buffers.iter_mut() {
+            if remaining == 0 {
+                break;
+            }
 
-        const MAX_COMMAND_LENGTH: u8 = 16;
+            let length = buffer.len().min(remaining);
+            segments.push(IoSliceMut::new(&mut buffer[..length]));
+            remaining -= length;
+        }
 
-        let command_buffer = command.command();
-        let mut data_buffer = command.data();
+        let (transport_result, rewritten_command) = self.execute(
+            TransportRequest {
+                command: command_bytes(&command_buffer),
+                direction: command.direction(),
+                data: &mut [],
+                data_out: command.data_out(),
+                sense: &mut sense_buffer,
+                timeout: command.timeout_override().unwrap_or(self.timeout),
+            },
+            Some(&mut segments),
+        );
 
-        let size_of_command_buffer = size_of_val(&command_buffer) as u8;
-        let size_of_data_buffer = command.data_size();
+        self.record_completion(
+            &transport_result,
+            &sense_buffer,
+            size_of_data_buffer - remaining,
+            Retries::default(),
+        );
 
-        if size_of_command_buffer > MAX_COMMAND_LENGTH {
-            panic!(
-                "Current command length is {}, max command length is {}",
-                size_of_command_buffer, MAX_COMMAND_LENGTH
-            );
+        Self::process_response(
+            command,
+            rewritten_command
+                .as_deref()
+                .unwrap_or(command_bytes(&command_buffer)),
+            &mut data_buffer,
+            size_of_data_buffer - remaining,
+            &sense_buffer,
+            transport_result,
+        )
+    }
+
+    /// Runs `issue`, e.g. `|| scsi.read_capacity().issue_16()`, and returns its result along
+    /// with how the last command it issued to this device completed.
+    pub fn with_info<R>(
+        &self,
+        issue: impl FnOnce() -> crate::Result<R>,
+    ) -> crate::Result<(R, CompletionInfo)> {
+        match completion_info::capture(self.device_id(), issue) {
+            (Ok(value), Some(info)) => Ok((value, info)),
+            (Ok(_), None) => Err(crate::Error::Other(
+                "no command was issued to the device.".to_owned(),
+            )),
+            (Err(error), _) => Err(error),
         }
-        let command_pointer = &command_buffer as *const _ as *const u8;
-        let command_slice =
-            unsafe { slice::from_raw_parts(command_pointer, size_of_command_buffer as usize) };
-
-        let mut header = ScsiPassThroughDirectWrapper::default();
-        let address_of_header = std::ptr::addr_of!(header) as usize;
-        let mut spt = &mut header.scsi_pass_through;
-        spt.Length = size_of_val(spt) as u16;
-        spt.CdbLength = size_of_command_buffer;
-        spt.SenseInfoLength = size_of_val(&header.sense) as u8;
-        spt.DataIn = match command.direction() {
-            crate::DataDirection::None => SCSI_IOCTL_DATA_UNSPECIFIED,
-            crate::DataDirection::ToDevice => SCSI_IOCTL_DATA_OUT,
-            crate::DataDirection::FromDevice => SCSI_IOCTL_DATA_IN,
-            crate::DataDirection::ToFromDevice => SCSI_IOCTL_DATA_BIDIRECTIONAL,
-            crate::DataDirection::Unknown => SCSI_IOCTL_DATA_UNSPECIFIED,
-        } as u8;
-
-        spt.DataTransferLength = size_of_data_buffer;
+    }
 
-        let timeout = command.timeout_override().unwrap_or(self.timeout);
+    fn device_id(&self) -> usize {
+        self as *const Scsi as usize
+    }
 
-        spt.TimeOutValue = match timeout
-            .as_secs()
-            .clamp(u32::MIN as u64, u32::MAX as u64)
-        {
-            0 => 1,
-            n => n as u32,
+    fn record_completion(
+        &self,
+        transport_result: &io::Result<TransportResponse>,
+        sense_buffer: &[u8; MAX_SENSE_BUFFER_LENGTH],
+        size_of_data_buffer: usize,
+        retries: Retries,
+    ) {
+        let Ok(response) = transport_result else {
+            return;
         };
+        if !completion_info::is_capturing(self.device_id()) {
+            return;
+        }
 
-        spt.DataBuffer = data_buffer.borrow_mut() as *mut _ as _;
+        #[cfg(target_os = "linux")]
+        let is_direct_io = AuxiliaryInfo::from_bits_retain(response.auxiliary_info)
+            & AuxiliaryInfo::DIRECT_IO_MASK
+            == AuxiliaryInfo::DIRECT_IO;
+        #[cfg(target_os = "windows")]
+        let is_direct_io = false;
+
+        completion_info::record(
+            self.device_id(),
+            CompletionInfo {
+                status: Status::from(response.status),
+                duration: response.duration,
+                transferred_length: size_of_data_buffer.saturating_sub(response.residual),
+                residual: response.residual,
+                is_direct_io,
+                sense: SenseData::parse(
+                    sense_buffer,
+                    response.sense_length.min(MAX_SENSE_BUFFER_LENGTH),
+                ),
+                retries,
+            },
+        );
+    }
 
-        spt.SenseInfoOffset =
-            (std::ptr::addr_of!(header.sense) as usize - address_of_header) as u32;
+    /// Hands `request` to the transport, or to `execute_vectored` if there are `buffers`, with
+    /// the interceptors run around it. Also returns the command descriptor block executed, if
+    /// interceptors ran, since they may have rewritten it.
+    fn execute(
+        &self,
+        request: TransportRequest,
+        buffers: Option<&mut [IoSliceMut]>,
+    ) -> (io::Result<TransportResponse>, Option<Vec<u8>>) {
+        let transport = |request| match buffers {
+            Some(buffers) => self.transport.execute_vectored(request, buffers),
+            None => self.transport.execute(request),
+        };
 
-        spt.Cdb[..command_slice.len()].copy_from_slice(command_slice);
+        if self.interceptors.is_empty() {
+            return (transport(request), None);
+        }
 
-        let mut bytes_returned = 0;
+        let direction = request.direction;
+        let mut pending = PendingCommand {
+            path: &self.path,
+            command: request.command.to_vec(),
+            direction,
+            data_out: request.data_out,
+            timeout: request.timeout,
+        };
+        let veto = self
+            .interceptors
+            .iter()
+            .try_for_each(|interceptor| interceptor.before(&mut pending));
+        let PendingCommand {
+            command, timeout, ..
+        } = pending;
+
+        let result = match veto {
+            Ok(()) => transport(TransportRequest {
+                command: &command,
+                direction,
+                data: &mut *request.data,
+                data_out: request.data_out,
+                sense: &mut *request.sense,
+                timeout,
+            }),
+            Err(error) => Err(error),
+        };
 
-        let success = unsafe {
-            DeviceIoControl(
-                HANDLE(self.file_descriptor.raw() as isize),
-                IOCTL_SCSI_PASS_THROUGH_DIRECT,
-                Some(&header as *const _ as _),
-                size_of_val(&header) as u32,
-                Some(&mut header as *mut _ as _),
-                size_of_val(&header) as u32,
-                Some(&mut bytes_returned),
-                None,
-            )
+        let data_in = match (direction, &result) {
+            (DataDirection::FromDevice | DataDirection::ToFromDevice, Ok(response)) => {
+                let length = request.data.len().saturating_sub(response.residual);
+                &request.data[..length]
+            }
+            _ => &[][..],
         };
+        let sense = match (
+            <&[u8; MAX_SENSE_BUFFER_LENGTH]>::try_from(&*request.sense),
+            &result,
+        ) {
+            (Ok(sense), Ok(response)) => {
+                SenseData::parse(sense, response.sense_length.min(MAX_SENSE_BUFFER_LENGTH))
+            }
+            _ => SenseData::None,
+        };
+        let sense_length = match &result {
+            Ok(response) => response.sense_length.min(request.sense.len()),
+            Err(_) => 0,
+        };
+        let completed = CompletedCommand {
+            path: &self.path,
+            command: &command,
+            direction,
+            data_out: request.data_out,
+            data_in,
+            timeout,
+            response: result.as_ref(),
+            sense: &sense,
+            sense_bytes: &request.sense[..sense_length],
+        };
+        for interceptor in &self.interceptors {
+            interceptor.after(&completed);
+        }
 
-        let ioctl_result = match success.as_bool() {
-            true => 0,
-            false => -1,
+        (result, Some(command))
+    }
+
+    /// Turns the outcome of a transport into the return value of `command`, `command_buffer`
+    /// being the command descriptor block that was executed.
+    pub(crate) fn process_response<T: Command>(
+        command: &T,
+        command_buffer: &[u8],
+        data_buffer: &mut T::DataBufferWrapper,
+        size_of_data_buffer: usize,
+        sense_buffer: &[u8; MAX_SENSE_BUFFER_LENGTH],
+        transport_result: io::Result<TransportResponse>,
+    ) -> T::ReturnType {
+        let (ioctl_result, ioctl_error, response) = match transport_result {
+            Ok(response) => (0, None, response),
+            Err(error) => (-1, Some(error), TransportResponse::default()),
         };
 
-        let sense_data = SenseData::parse(
-            &header.sense,
-            header.scsi_pass_through.SenseInfoLength as usize,
-        );
+        let sense_buffer_written = response.sense_length.min(MAX_SENSE_BUFFER_LENGTH);
+        let sense_data = SenseData::parse(sense_buffer, sense_buffer_written);
 
         let result_data = ResultData {
             ioctl_result,
-            transfered_data_length: header.scsi_pass_through.DataTransferLength as usize,
-            data: &mut data_buffer,
-            transfered_sense_length: header.scsi_pass_through.SenseInfoLength as usize,
+            ioctl_error,
+            command: command_buffer,
+            transfered_data_length: size_of_data_buffer.saturating_sub(response.residual),
+            data: data_buffer,
+            transfered_sense_length: sense_buffer_written,
             sense_buffer: &sense_data,
-            status: Status::from(header.scsi_pass_through.ScsiStatus),
+            status: Status::from(response.status),
+            #[cfg(target_os = "linux")]
+            host_status: response.host_status.into(),
+            #[cfg(target_os = "linux")]
+            driver_status: DriverStatus::from_bits_retain(response.driver_status),
+            #[cfg(target_os = "linux")]
+            auxiliary_info: AuxiliaryInfo::from_bits_retain(response.auxiliary_info),
         };
 
         command.process_result(result_data)
@@ -241,11 +403,94 @@ impl Scsi {
         self.timeout
     }
 
+    /// Retries the following commands on transient conditions, `None` reports every failure
+    /// right away. Queued and vectored commands are never retried.
+    pub fn set_retry_policy(&mut self, policy: Option<RetryPolicy>) {
+        self.retry_policy = policy;
+    }
+
+    pub fn retry_policy(&self) -> Option<&RetryPolicy> {
+        self.retry_policy.as_ref()
+    }
+
+    /// Adds an interceptor, run after the ones added before it, that sees every command sent to
+    /// the transport and may alter or veto it. Commands queued with a `CommandQueue` or an
+    /// `AsyncScsi` don't go through the transport, so they aren't seen.
+    pub fn add_interceptor<I: Interceptor + 'static>(&mut self, interceptor: I) {
+        self.interceptors.push(Box::new(interceptor));
+    }
+
+    pub fn clear_interceptors(&mut self) {
+        self.interceptors.clear();
+    }
+
+    pub(crate) fn has_interceptors(&self) -> bool {
+        !self.interceptors.is_empty()
+    }
+
+    /// How many times commands were retried by the attached policy since the device was opened.
+    pub fn retries(&self) -> Retries {
+        *self.retries.lock().unwrap()
+    }
+
+    /// What the device can do, probed with [`DeviceProfile::probe`] on the first call. Used to
+    /// pick the CDB size and logical block size of the `issue` methods of READ, WRITE, VERIFY
+    /// and WRITE SAME.
+    pub fn device_profile(&self) -> crate::Result<Arc<DeviceProfile>> {
+        let mut profile = self.profile.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(profile) = &*profile {
+            return Ok(profile.clone());
+        }
+
+        let probed = Arc::new(DeviceProfile::probe(self)?);
+        *profile = Some(probed.clone());
+
+        Ok(probed)
+    }
+
+    /// Replaces the probed profile, e.g. with a stored one, `None` probes again on the next use,
+    /// e.g. after the logical block size was changed by FORMAT UNIT.
+    pub fn set_device_profile(&mut self, profile: Option<DeviceProfile>) {
+        *self.profile.get_mut().unwrap_or_else(|e| e.into_inner()) = profile.map(Arc::new);
+    }
+
+    pub(crate) fn file_descriptor(&self) -> Option<&Arc<FileDescriptor>> {
+        self.file_descriptor.as_ref()
+    }
+
+    pub fn transport(&self) -> &dyn Transport {
+        self.transport.as_ref()
+    }
+
+    /// Puts another transport in front of the current one, e.g. a
+    /// [`FaultInjector`](crate::emulator::FaultInjector) around a real device.
+    pub fn wrap_transport<T: Transport + 'static>(
+        self,
+        wrap: impl FnOnce(Box<dyn Transport>) -> T,
+    ) -> Scsi {
+        Scsi {
+            transport: Box::new(wrap(self.transport)),
+            ..self
+        }
+    }
+
+    /// The host, channel, target and lun of the device, `None` if the OS can't report it or
+    /// a custom transport is used.
+    pub fn scsi_address(&self) -> Option<ScsiAddress> {
+        self.address
+    }
+
     fn from_descriptor<P: AsRef<Path> + ?Sized>(
         path: &P,
         file_descriptor: FileDescriptor,
     ) -> crate::Result<Scsi> {
-        if !file_descriptor.is_block()? {
+        // sg character devices reach tapes, enclosures, changers and so on
+        #[cfg(target_os = "linux")]
+        let is_device = file_descriptor.is_block()? || file_descriptor.is_char()?;
+        #[cfg(target_os = "windows")]
+        let is_device = file_descriptor.is_block()?;
+
+        if !is_device {
             return Err(crate::Error::NotBlockDevice(path.as_ref().to_owned()));
         }
 
@@ -253,31 +498,96 @@ impl Scsi {
             return Err(crate::Error::NotScsiDevice(path.as_ref().to_owned()));
         }
 
+        #[cfg(target_os = "linux")]
+        let address = crate::os::linux::scsi_address(&file_descriptor).ok();
+        #[cfg(target_os = "windows")]
+        let address = crate::os::windows::scsi_address(&file_descriptor).ok();
+
+        let file_descriptor = Arc::new(file_descriptor);
+
+        #[cfg(target_os = "linux")]
+        let sg_transport = match supports_sg_v4(&file_descriptor) {
+            true => None,
+            false => Some(Arc::new(SgTransport::new(file_descriptor.clone()))),
+        };
+        #[cfg(target_os = "linux")]
+        let transport: Box<dyn Transport> = match &sg_transport {
+            Some(sg_transport) => Box::new(sg_transport.clone()),
+            None => Box::new(SgV4Transport::new(file_descriptor.clone())),
+        };
+        #[cfg(target_os = "windows")]
+        let transport: Box<dyn Transport> = Box::new(
+            crate::os::windows::PassThroughTransport::new(file_descriptor.clone()),
+        );
+
         Ok(Scsi {
             path: path.as_ref().to_owned(),
-            file_descriptor,
+            file_descriptor: Some(file_descriptor),
+            transport,
+            #[cfg(target_os = "linux")]
+            sg_transport,
+            address,
             timeout: Duration::from_millis(SG_DEFAULT_TIMEOUT),
+            retry_policy: None,
+            retries: Mutex::default(),
+            interceptors: vec![],
+            profile: Mutex::default(),
         })
     }
 
+    /// Selects indirect, direct or memory mapped I/O for the following commands. Anything but
+    /// [`AccessFlags::DEFAULT`] needs an sg v3 device, i.e. an sg character or block device.
+    ///
+    /// Direct I/O falls back to indirect I/O unless the buffer is suitably aligned, see
+    /// [`AlignedBuffer`](crate::AlignedBuffer), and `/proc/scsi/sg/allow_dio` is set. The
+    /// `auxiliary_info` of the response tells which one was done. Memory mapped I/O needs a
+    /// [`memory_map`](Scsi::memory_map) as the data buffer of every command with data.
     #[cfg(target_os = "linux")]
-    fn is_scsi_device(file: &FileDescriptor) -> crate::Result<bool> {
-        use nix::libc;
+    pub fn set_access_flags(&mut self, flags: AccessFlags) -> crate::Result<()> {
+        match &self.sg_transport {
+            Some(sg_transport) => sg_transport.set_access_flags(flags),
+            None if flags == AccessFlags::DEFAULT => {}
+            None => {
+                return Err(crate::Error::Other(format!(
+                    "{} doesn't support access flags, it needs an sg v3 device.",
+                    self.path.display()
+                )))
+            }
+        }
 
-        const SG_GET_VERSION_NUM: u32 = 0x2282;
+        Ok(())
+    }
 
-        let mut version = 0_i32;
-        let result = unsafe {
-            libc::ioctl(
-                file.raw(),
-                SG_GET_VERSION_NUM.try_into().unwrap(),
-                &mut version,
-            )
+    #[cfg(target_os = "linux")]
+    pub fn access_flags(&self) -> AccessFlags {
+        match &self.sg_transport {
+            Some(sg_transport) => sg_transport.access_flags(),
+            None => AccessFlags::DEFAULT,
+        }
+    }
+
+    /// Reserves `length` bytes in the sg driver and maps them, for memory mapped I/O. Creating
+    /// another map replaces this one.
+    #[cfg(target_os = "linux")]
+    pub fn memory_map(&self, length: usize) -> crate::Result<MemoryMap> {
+        let (Some(sg_transport), Some(file_descriptor)) =
+            (&self.sg_transport, &self.file_descriptor)
+        else {
+            return Err(crate::Error::Other(format!(
+                "{} doesn't support memory mapped I/O, it needs an sg v3 device.",
+                self.path.display()
+            )));
         };
 
-        if result != 0 {
-            Err(io::Error::last_os_error())?;
-        }
+        let memory_map = MemoryMap::new(file_descriptor.clone(), length)?;
+        sg_transport.set_memory_map(memory_map.mapping().clone());
+
+        Ok(memory_map)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn is_scsi_device(file: &FileDescriptor) -> crate::Result<bool> {
+        let version = crate::os::linux::sg_version_num(file)?;
 
         if version < 30000 {
             Ok(false)
@@ -321,4 +631,54 @@ impl Scsi {
     }
 }
 
-const SG_DEFAULT_TIMEOUT: u64 = 60_000;
+/// Views a command descriptor block as bytes.
+pub(crate) fn command_bytes<C>(command_buffer: &C) -> &[u8] {
+    unsafe {
+        slice::from_raw_parts(
+            command_buffer as *const C as *const u8,
+            size_of_val(command_buffer),
+        )
+    }
+}
+
+/// Views the data-in and data-out buffers of `command`. Its data buffer goes out for
+/// `ToDevice`, unless it lends one through [`Command::data_out`].
+///
+/// # Safety
+///
+/// `command.data_size()` must not exceed the size of the buffer behind the wrapper.
+pub(crate) unsafe fn data_buffers<'b, T: Command>(
+    command: &'b T,
+    data_buffer: &'b mut T::DataBufferWrapper,
+) -> (&'b mut [u8], &'b [u8]) {
+    let size = command.data_size() as usize;
+
+    match command.direction() {
+        DataDirection::ToDevice if !command.data_out().is_empty() => (&mut [], command.data_out()),
+        DataDirection::ToDevice => (&mut [], data_bytes::<T>(data_buffer, size)),
+        _ => (data_bytes::<T>(data_buffer, size), command.data_out()),
+    }
+}
+
+/// The length the residual of `command` counts from, see [`data_buffers`].
+pub(crate) fn transfer_length<T: Command>(command: &T) -> usize {
+    match command.direction() {
+        DataDirection::ToDevice if !command.data_out().is_empty() => command.data_out().len(),
+        _ => command.data_size() as usize,
+    }
+}
+
+/// Views the first `size` bytes of a command's data buffer.
+///
+/// # Safety
+///
+/// `size` must not exceed the size of the buffer behind the wrapper.
+unsafe fn data_bytes<T: Command>(data_buffer: &mut T::DataBufferWrapper, size: usize) -> &mut [u8] {
+    if size == 0 {
+        return &mut [];
+    }
+
+    slice::from_raw_parts_mut(data_buffer.borrow_mut() as *mut _ as *mut u8, size)
+}
+
+pub(crate) const SG_DEFAULT_TIMEOUT: u64 = 60_000;
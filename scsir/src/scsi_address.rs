@@ -0,0 +1,10 @@
+/// Where a device sits in the SCSI topology of the host.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ScsiAddress {
+    /// host adapter number, the port number on Windows
+    pub host: u32,
+    /// bus number, the path id on Windows
+    pub channel: u32,
+    pub target: u32,
+    pub lun: u64,
+}
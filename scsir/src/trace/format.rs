@@ -0,0 +1,265 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    time::Duration,
+};
+
+use crate::{
+    hex::{hex, parse_hex},
+    DataDirection, TransportResponse,
+};
+
+const MAGIC: &str = "scsir-trace";
+
+/// A command and what the device answered, in the order they were issued.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub command: Vec<u8>,
+    pub direction: DataDirection,
+    pub timeout: Duration,
+    pub data_out: Vec<u8>,
+    /// The bytes the device transferred, without the residual.
+    pub data_in: Vec<u8>,
+    pub sense: Vec<u8>,
+    /// `Err` with the reason if the command couldn't be delivered.
+    pub response: Result<TransportResponse, String>,
+}
+
+/// Commands exchanged with a device, see the [module documentation](super) for the file format.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Trace {
+    pub entries: Vec<TraceEntry>,
+}
+
+impl Trace {
+    /// The version of the file format written by this crate.
+    pub const VERSION: u32 = 1;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load<P: AsRef<Path> + ?Sized>(path: &P) -> crate::Result<Self> {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+
+    pub fn save<P: AsRef<Path> + ?Sized>(&self, path: &P) -> crate::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    pub fn read_from<R: BufRead>(reader: R) -> crate::Result<Self> {
+        let mut lines = reader.lines().enumerate();
+
+        let version = match lines.next() {
+            Some((_, line)) => {
+                let line = line?;
+                match line.split_once(' ') {
+                    Some((MAGIC, version)) => version.trim().parse::<u32>().ok(),
+                    _ => None,
+                }
+            }
+            None => None,
+        };
+        match version {
+            Some(version) if version <= Self::VERSION => {}
+            Some(version) => {
+                return Err(crate::Error::Other(format!(
+                    "trace version {} is newer than the supported version {}.",
+                    version,
+                    Self::VERSION
+                )))
+            }
+            None => {
+                return Err(crate::Error::Other(
+                    "not a scsir trace, the first line must be \"scsir-trace <version>\"."
+                        .to_owned(),
+                ))
+            }
+        }
+
+        let mut trace = Trace::new();
+        let mut entry: Option<PartialEntry> = None;
+        for (index, line) in lines {
+            let line = line?;
+            let line = line.trim();
+
+            if line.is_empty() {
+                if let Some(entry) = entry.take() {
+                    trace.entries.push(entry.finish()?);
+                }
+                continue;
+            }
+            if line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            entry
+                .get_or_insert_with(|| PartialEntry {
+                    first_line: index + 1,
+                    ..Default::default()
+                })
+                .set(key, value.trim())
+                .map_err(|message| {
+                    crate::Error::Other(format!("line {}: {}", index + 1, message))
+                })?;
+        }
+        if let Some(entry) = entry {
+            trace.entries.push(entry.finish()?);
+        }
+
+        Ok(trace)
+    }
+
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "{} {}", MAGIC, Self::VERSION)?;
+
+        for entry in &self.entries {
+            writeln!(writer)?;
+            writeln!(writer, "command {}", hex(&entry.command))?;
+            writeln!(writer, "direction {}", direction_name(entry.direction))?;
+            writeln!(writer, "timeout {}", entry.timeout.as_millis())?;
+
+            match &entry.response {
+                Ok(response) => {
+                    writeln!(writer, "status {:02x}", response.status)?;
+                    if response.host_status != 0 {
+                        writeln!(writer, "host-status {:04x}", response.host_status)?;
+                    }
+                    if response.driver_status != 0 {
+                        writeln!(writer, "driver-status {:04x}", response.driver_status)?;
+                    }
+                    if response.auxiliary_info != 0 {
+                        writeln!(writer, "auxiliary-info {:08x}", response.auxiliary_info)?;
+                    }
+                    if response.residual != 0 {
+                        writeln!(writer, "residual {}", response.residual)?;
+                    }
+                    if response.data_out_residual != 0 {
+                        writeln!(writer, "data-out-residual {}", response.data_out_residual)?;
+                    }
+                    if let Some(duration) = response.duration {
+                        writeln!(writer, "duration {}", duration.as_micros())?;
+                    }
+                }
+                Err(error) => writeln!(writer, "error {}", error.replace('\n', " "))?,
+            }
+
+            for (key, bytes) in [
+                ("data-out", &entry.data_out),
+                ("data-in", &entry.data_in),
+                ("sense", &entry.sense),
+            ] {
+                if !bytes.is_empty() {
+                    writeln!(writer, "{} {}", key, hex(bytes))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct PartialEntry {
+    first_line: usize,
+    command: Option<Vec<u8>>,
+    direction: Option<DataDirection>,
+    timeout: Duration,
+    data_out: Vec<u8>,
+    data_in: Vec<u8>,
+    sense: Vec<u8>,
+    response: TransportResponse,
+    error: Option<String>,
+}
+
+impl PartialEntry {
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "command" => self.command = Some(parse_bytes(value)?),
+            "direction" => self.direction = Some(parse_direction(value)?),
+            "timeout" => self.timeout = Duration::from_millis(parse_number(value, 10)?),
+            "data-out" => self.data_out = parse_bytes(value)?,
+            "data-in" => self.data_in = parse_bytes(value)?,
+            "sense" => self.sense = parse_bytes(value)?,
+            "status" => self.response.status = parse_number(value, 16)?,
+            "host-status" => self.response.host_status = parse_number(value, 16)?,
+            "driver-status" => self.response.driver_status = parse_number(value, 16)?,
+            "auxiliary-info" => self.response.auxiliary_info = parse_number(value, 16)?,
+            "residual" => self.response.residual = parse_number(value, 10)?,
+            "data-out-residual" => self.response.data_out_residual = parse_number(value, 10)?,
+            "duration" => {
+                self.response.duration = Some(Duration::from_micros(parse_number(value, 10)?))
+            }
+            "error" => self.error = Some(value.to_owned()),
+            // written by a newer version, which stays readable
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn finish(self) -> crate::Result<TraceEntry> {
+        let command = self.command.ok_or_else(|| {
+            crate::Error::Other(format!(
+                "the entry at line {} has no command.",
+                self.first_line
+            ))
+        })?;
+
+        let response = match self.error {
+            Some(error) => Err(error),
+            None => Ok(TransportResponse {
+                sense_length: self.sense.len(),
+                ..self.response
+            }),
+        };
+
+        Ok(TraceEntry {
+            command,
+            direction: self.direction.unwrap_or(DataDirection::None),
+            timeout: self.timeout,
+            data_out: self.data_out,
+            data_in: self.data_in,
+            sense: self.sense,
+            response,
+        })
+    }
+}
+
+fn direction_name(direction: DataDirection) -> &'static str {
+    match direction {
+        DataDirection::None => "none",
+        DataDirection::ToDevice => "to-device",
+        DataDirection::FromDevice => "from-device",
+        DataDirection::ToFromDevice => "to-from-device",
+        DataDirection::Unknown => "unknown",
+    }
+}
+
+fn parse_direction(value: &str) -> Result<DataDirection, String> {
+    match value {
+        "none" => Ok(DataDirection::None),
+        "to-device" => Ok(DataDirection::ToDevice),
+        "from-device" => Ok(DataDirection::FromDevice),
+        "to-from-device" => Ok(DataDirection::ToFromDevice),
+        "unknown" => Ok(DataDirection::Unknown),
+        _ => Err(format!("unknown direction \"{}\"", value)),
+    }
+}
+
+fn parse_number<T: TryFrom<u64>>(value: &str, radix: u32) -> Result<T, String> {
+    u64::from_str_radix(value, radix)
+        .ok()
+        .and_then(|number| T::try_from(number).ok())
+        .ok_or_else(|| format!("invalid number \"{}\"", value))
+}
+
+fn parse_bytes(value: &str) -> Result<Vec<u8>, String> {
+    parse_hex(value).ok_or_else(|| format!("invalid hex string \"{}\"", value))
+}
@@ -0,0 +1,70 @@
+//! Recording the commands exchanged with a device into a trace, and replaying a trace without
+//! the device.
+//!
+//! ```
+//! use scsir::{emulator::EmulatedDisk, trace::*, Scsi};
+//!
+//! let recorder = TraceRecorder::new();
+//! let mut scsi = Scsi::with_transport("emulated", EmulatedDisk::new(512, 2048)?);
+//! scsi.add_interceptor(recorder.clone());
+//! let capacity = scsi.read_capacity().issue_16()?;
+//!
+//! let mut file = vec![];
+//! recorder.trace().write_to(&mut file)?;
+//!
+//! let trace = Trace::read_from(&file[..])?;
+//! let scsi = Scsi::with_transport("replay", ReplayTransport::new(trace, MatchMode::Strict));
+//! let replayed = scsi.read_capacity().issue_16()?;
+//! assert_eq!(
+//!     replayed.returned_logical_block_address,
+//!     capacity.returned_logical_block_address
+//! );
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! # Format
+//!
+//! Traces are text files, version 1 looks like this:
+//!
+//! ```text
+//! scsir-trace 1
+//!
+//! command 9e100000000000000000000000200000
+//! direction from-device
+//! timeout 60000
+//! status 00
+//! duration 1000
+//! data-in 00000000000007ff000002000000000000000000000000000000000000000000
+//!
+//! command 000000000000
+//! direction none
+//! timeout 60000
+//! status 02
+//! sense 700006000000000a00000000290000000000
+//! ```
+//!
+//! The first line names the version, entries follow separated by empty lines, each line holds
+//! a key and a value. Lines starting with `#` are comments.
+//!
+//! | key | value |
+//! | --- | --- |
+//! | `command` | the command descriptor block in hex, required |
+//! | `direction` | `none`, `to-device`, `from-device`, `to-from-device` or `unknown` |
+//! | `timeout` | milliseconds |
+//! | `data-out`, `data-in`, `sense` | hex, empty if missing |
+//! | `status` | the SCSI status byte in hex |
+//! | `host-status`, `driver-status`, `auxiliary-info` | hex |
+//! | `residual`, `data-out-residual` | decimal byte counts |
+//! | `duration` | microseconds as measured by the driver, unknown if missing |
+//! | `error` | why the command couldn't be delivered, no status then |
+//!
+//! Readers ignore keys they don't know, so keys may be added without changing the version.
+//! Anything else bumps the version, and traces of a newer version are rejected.
+
+mod format;
+mod recorder;
+mod replay;
+
+pub use format::{Trace, TraceEntry};
+pub use recorder::TraceRecorder;
+pub use replay::{MatchMode, ReplayTransport};
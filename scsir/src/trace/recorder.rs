@@ -0,0 +1,50 @@
+use std::{
+    mem,
+    sync::{Arc, Mutex},
+};
+
+use crate::{interceptor::CompletedCommand, Interceptor};
+
+use super::{Trace, TraceEntry};
+
+/// Records every command of a [`Scsi`](crate::Scsi) it is added to as an interceptor, clones
+/// share the same trace. Add it last to record the commands as the other interceptors left them.
+#[derive(Clone, Debug, Default)]
+pub struct TraceRecorder {
+    trace: Arc<Mutex<Trace>>,
+}
+
+impl TraceRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A copy of the commands recorded so far.
+    pub fn trace(&self) -> Trace {
+        self.trace.lock().unwrap().clone()
+    }
+
+    /// The commands recorded so far, recording goes on into an empty trace.
+    pub fn take(&self) -> Trace {
+        mem::take(&mut *self.trace.lock().unwrap())
+    }
+}
+
+impl Interceptor for TraceRecorder {
+    fn after(&self, command: &CompletedCommand) {
+        let entry = TraceEntry {
+            command: command.command().to_vec(),
+            direction: command.direction(),
+            timeout: command.timeout(),
+            data_out: command.data_out().to_vec(),
+            data_in: command.data_in().to_vec(),
+            sense: command.sense_bytes().to_vec(),
+            response: command
+                .response()
+                .copied()
+                .map_err(|error| error.to_string()),
+        };
+
+        self.trace.lock().unwrap().entries.push(entry);
+    }
+}
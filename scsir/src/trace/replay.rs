@@ -0,0 +1,246 @@
+use std::{io, sync::Mutex};
+
+use crate::{operation_name, DataDirection, Transport, TransportRequest, TransportResponse};
+
+use super::{Trace, TraceEntry};
+
+/// How a [`ReplayTransport`] finds the recorded entry answering a command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchMode {
+    /// Commands must be issued in the recorded order, with the same command descriptor block,
+    /// direction and data-out bytes.
+    Strict,
+    /// The first entry not replayed yet with the same command descriptor block answers,
+    /// regardless of the order and the data-out bytes. Once every such entry was replayed, the
+    /// last of them answers again, e.g. for polling loops.
+    Lenient,
+}
+
+/// A [`Transport`] answering commands from a recorded [`Trace`] instead of a device.
+///
+/// Commands without an answer in the trace fail with [`io::ErrorKind::InvalidData`], or
+/// [`io::ErrorKind::UnexpectedEof`] once a strict replay went through the whole trace.
+#[derive(Debug)]
+pub struct ReplayTransport {
+    entries: Vec<TraceEntry>,
+    mode: MatchMode,
+    state: Mutex<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    is_replayed: Vec<bool>,
+    last_match: Option<usize>,
+}
+
+impl ReplayTransport {
+    pub fn new(trace: Trace, mode: MatchMode) -> Self {
+        Self {
+            state: Mutex::new(State {
+                is_replayed: vec![false; trace.entries.len()],
+                last_match: None,
+            }),
+            entries: trace.entries,
+            mode,
+        }
+    }
+
+    /// How many entries weren't replayed yet.
+    pub fn remaining(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        state.is_replayed.iter().filter(|&&b| !b).count()
+    }
+
+    fn find(&self, state: &State, request: &TransportRequest) -> io::Result<usize> {
+        let data_out = request.data_out;
+
+        match self.mode {
+            MatchMode::Strict => {
+                let index = state.last_match.map_or(0, |index| index + 1);
+                let Some(entry) = self.entries.get(index) else {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        format!(
+                            "the trace has no entry left for {}",
+                            describe(request.command)
+                        ),
+                    ));
+                };
+
+                if entry.command != request.command
+                    || entry.direction != request.direction
+                    || entry.data_out != data_out
+                {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "entry {} of the trace is {}, but {} was issued",
+                            index,
+                            describe(&entry.command),
+                            describe(request.command)
+                        ),
+                    ));
+                }
+
+                Ok(index)
+            }
+            MatchMode::Lenient => {
+                let matches = (0..self.entries.len())
+                    .filter(|&index| self.entries[index].command == request.command)
+                    .collect::<Vec<_>>();
+
+                matches
+                    .iter()
+                    .find(|&&index| !state.is_replayed[index])
+                    .or(matches.last())
+                    .copied()
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("the trace has no entry for {}", describe(request.command)),
+                        )
+                    })
+            }
+        }
+    }
+}
+
+impl Transport for ReplayTransport {
+    fn execute(&self, request: TransportRequest) -> io::Result<TransportResponse> {
+        let mut state = self.state.lock().unwrap();
+        let index = self.find(&state, &request)?;
+        state.is_replayed[index] = true;
+        state.last_match = Some(index);
+        drop(state);
+
+        let entry = &self.entries[index];
+        let mut response = match &entry.response {
+            Ok(response) => *response,
+            Err(error) => return Err(io::Error::other(error.clone())),
+        };
+
+        if matches!(
+            request.direction,
+            DataDirection::FromDevice | DataDirection::ToFromDevice
+        ) {
+            let length = entry.data_in.len().min(request.data.len());
+            request.data[..length].copy_from_slice(&entry.data_in[..length]);
+            response.residual = request.data.len() - length;
+        }
+
+        let sense_length = entry.sense.len().min(request.sense.len());
+        request.sense[..sense_length].copy_from_slice(&entry.sense[..sense_length]);
+        response.sense_length = sense_length;
+
+        Ok(response)
+    }
+}
+
+fn describe(command: &[u8]) -> String {
+    format!(
+        "{} {:02x?}",
+        operation_name(command).unwrap_or("an unknown command"),
+        command
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{emulator::EmulatedDisk, trace::TraceRecorder, Scsi};
+
+    #[test]
+    fn replay_test() {
+        let recorder = TraceRecorder::new();
+        let mut scsi = Scsi::with_transport("emulated", EmulatedDisk::new(512, 64).unwrap());
+        scsi.add_interceptor(recorder.clone());
+
+        scsi.write()
+            .logical_block_address(1)
+            .parameter(&[0xA5; 512])
+            .issue_10()
+            .unwrap();
+        let data = scsi
+            .read()
+            .logical_block_address(1)
+            .transfer_length(1)
+            .issue_10()
+            .unwrap();
+        let inquiry = scsi.inquiry().issue().unwrap();
+        // LOGICAL BLOCK ADDRESS OUT OF RANGE
+        scsi.read()
+            .logical_block_address(64)
+            .transfer_length(1)
+            .issue_10()
+            .unwrap_err();
+
+        let trace = recorder.take();
+        assert_eq!(trace.entries.len(), 4);
+        assert!(recorder.trace().entries.is_empty());
+
+        let mut file = vec![];
+        trace.write_to(&mut file).unwrap();
+        assert_eq!(Trace::read_from(&file[..]).unwrap(), trace);
+
+        let replay = Arc::new(ReplayTransport::new(trace.clone(), MatchMode::Strict));
+        let scsi = Scsi::with_transport("replay", replay.clone());
+        scsi.write()
+            .logical_block_address(1)
+            .parameter(&[0xA5; 512])
+            .issue_10()
+            .unwrap();
+        assert_eq!(
+            scsi.read()
+                .logical_block_address(1)
+                .transfer_length(1)
+                .issue_10()
+                .unwrap(),
+            data
+        );
+        // out of order
+        assert!(scsi.read_capacity().issue_16().is_err());
+        assert_eq!(scsi.inquiry().issue().unwrap(), inquiry);
+        assert!(scsi
+            .read()
+            .logical_block_address(64)
+            .transfer_length(1)
+            .issue_10()
+            .is_err());
+        assert_eq!(replay.remaining(), 0);
+        let error = scsi.inquiry().issue().unwrap_err();
+        assert!(matches!(error, crate::Error::IO(e) if e.kind() == io::ErrorKind::UnexpectedEof));
+
+        // the data written differs from the recorded one
+        let scsi = Scsi::with_transport(
+            "replay",
+            ReplayTransport::new(trace.clone(), MatchMode::Strict),
+        );
+        assert!(scsi
+            .write()
+            .logical_block_address(1)
+            .parameter(&[0x5A; 512])
+            .issue_10()
+            .is_err());
+
+        let replay = ReplayTransport::new(trace, MatchMode::Lenient);
+        let scsi = Scsi::with_transport("replay", replay);
+        assert_eq!(scsi.inquiry().issue().unwrap(), inquiry);
+        for _ in 0..2 {
+            let replayed = scsi
+                .read()
+                .logical_block_address(1)
+                .transfer_length(1)
+                .issue_10()
+                .unwrap();
+            assert_eq!(replayed, data);
+        }
+        assert!(scsi
+            .read()
+            .logical_block_address(2)
+            .transfer_length(1)
+            .issue_10()
+            .is_err());
+    }
+}
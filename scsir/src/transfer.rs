@@ -0,0 +1,259 @@
+use crate::{command::device_logical_block_size, Scsi};
+
+/// Used when the OS doesn't tell how much it takes per command.
+const DEFAULT_MAXIMUM_BYTES: u64 = 1 << 20;
+
+/// How much a single command may transfer, see [`Scsi::transfer_limits`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransferLimits {
+    pub logical_block_size: u32,
+    /// In logical blocks, from the Block Limits VPD page.
+    pub maximum_transfer_length: Option<u32>,
+    pub optimal_transfer_length: Option<u32>,
+    pub optimal_transfer_length_granularity: Option<u32>,
+    /// The most bytes the kernel takes per command, `None` if it doesn't tell.
+    pub kernel_maximum_bytes: Option<u64>,
+}
+
+impl TransferLimits {
+    /// The most logical blocks a command may transfer, honoring the device and the kernel.
+    pub fn maximum_blocks(&self) -> u32 {
+        let kernel_blocks = self.kernel_maximum_bytes.unwrap_or(DEFAULT_MAXIMUM_BYTES)
+            / self.logical_block_size.max(1) as u64;
+
+        // the command builders transfer at most u32::MAX bytes
+        let blocks = kernel_blocks
+            .min(u32::MAX as u64 / self.logical_block_size.max(1) as u64)
+            .min(self.maximum_transfer_length.unwrap_or(u32::MAX) as u64);
+
+        blocks.max(1) as u32
+    }
+
+    /// The logical blocks of a command, the optimal transfer length if allowed, rounded down to
+    /// the granularity.
+    pub fn preferred_blocks(&self) -> u32 {
+        let maximum = self.maximum_blocks();
+        let blocks = self.optimal_transfer_length.unwrap_or(maximum).min(maximum);
+
+        match self.optimal_transfer_length_granularity {
+            Some(granularity) if granularity <= blocks => blocks - blocks % granularity,
+            _ => blocks,
+        }
+    }
+
+    /// Splits `count` logical blocks starting at `logical_block_address` into legal commands,
+    /// every one but the last ending on a multiple of the granularity.
+    pub fn chunks(&self, logical_block_address: u64, count: u64) -> Chunks {
+        Chunks {
+            next: logical_block_address,
+            end: logical_block_address.saturating_add(count),
+            blocks: self.preferred_blocks(),
+            granularity: self.optimal_transfer_length_granularity.unwrap_or(1).max(1),
+        }
+    }
+}
+
+/// The `(logical_block_address, transfer_length)` of each command, see
+/// [`TransferLimits::chunks`].
+#[derive(Clone, Debug)]
+pub struct Chunks {
+    next: u64,
+    end: u64,
+    blocks: u32,
+    granularity: u32,
+}
+
+impl Iterator for Chunks {
+    type Item = (u64, u32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.end {
+            return None;
+        }
+
+        let start = self.next;
+        let mut end = start.saturating_add(self.blocks as u64).min(self.end);
+        let aligned = end - end % self.granularity as u64;
+        if end < self.end && aligned > start {
+            end = aligned;
+        }
+        self.next = end;
+
+        Some((start, (end - start) as u32))
+    }
+}
+
+impl Scsi {
+    /// Combines the Block Limits VPD page, see [`Scsi::device_profile`], with the limits of the
+    /// kernel, on Linux `SG_GET_SG_TABLESIZE`, `SG_GET_RESERVED_SIZE` and the `max_sectors_kb`
+    /// and `max_hw_sectors_kb` of the request queue.
+    pub fn transfer_limits(&self) -> crate::Result<TransferLimits> {
+        let profile = self.device_profile()?;
+
+        #[cfg(target_os = "linux")]
+        let kernel_maximum_bytes = crate::os::linux::kernel_transfer_limit(self);
+        #[cfg(not(target_os = "linux"))]
+        let kernel_maximum_bytes = None;
+
+        Ok(TransferLimits {
+            logical_block_size: device_logical_block_size(self)?,
+            maximum_transfer_length: profile.maximum_transfer_length,
+            optimal_transfer_length: profile.optimal_transfer_length,
+            optimal_transfer_length_granularity: profile
+                .optimal_transfer_length_granularity
+                .map(u32::from),
+            kernel_maximum_bytes,
+        })
+    }
+
+    /// Reads `count` logical blocks with as many commands as [`transfer_limits`]
+    /// (Self::transfer_limits) need.
+    pub fn read_blocks(&self, logical_block_address: u64, count: u64) -> crate::Result<Vec<u8>> {
+        let limits = self.transfer_limits()?;
+        let length = count
+            .checked_mul(limits.logical_block_size as u64)
+            .and_then(|length| usize::try_from(length).ok())
+            .ok_or_else(|| {
+                crate::Error::ArgumentOutOfBounds(format!(
+                    "{} logical blocks don't fit into memory.",
+                    count
+                ))
+            })?;
+
+        let mut buffer = vec![0; length];
+        self.read_chunks(&limits, logical_block_address, &mut buffer)?;
+
+        Ok(buffer)
+    }
+
+    /// Like [`read_blocks`](Self::read_blocks), but reads as many logical blocks as fit into
+    /// `buffer`.
+    pub fn read_blocks_into(
+        &self,
+        logical_block_address: u64,
+        buffer: &mut [u8],
+    ) -> crate::Result<()> {
+        let limits = self.transfer_limits()?;
+        check_length(&limits, buffer.len())?;
+
+        self.read_chunks(&limits, logical_block_address, buffer)
+    }
+
+    pub fn write_blocks(&self, logical_block_address: u64, data: &[u8]) -> crate::Result<()> {
+        let limits = self.transfer_limits()?;
+        let block_size = check_length(&limits, data.len())?;
+
+        for (lba, blocks) in limits.chunks(logical_block_address, (data.len() / block_size) as u64)
+        {
+            let offset = (lba - logical_block_address) as usize * block_size;
+            self.write()
+                .logical_block_address(lba)
+                .logical_block_size(limits.logical_block_size)
+                .parameter_borrowed(&data[offset..offset + blocks as usize * block_size])
+                .issue()?;
+        }
+
+        Ok(())
+    }
+
+    /// Verifies `count` logical blocks against the medium, without comparing any data.
+    pub fn verify_blocks(&self, logical_block_address: u64, count: u64) -> crate::Result<()> {
+        let limits = self.transfer_limits()?;
+
+        for (lba, blocks) in limits.chunks(logical_block_address, count) {
+            self.verify()
+                .logical_block_address(lba)
+                .logical_block_size(limits.logical_block_size)
+                .verification_length(blocks)
+                .issue()?;
+        }
+
+        Ok(())
+    }
+
+    fn read_chunks(
+        &self,
+        limits: &TransferLimits,
+        logical_block_address: u64,
+        buffer: &mut [u8],
+    ) -> crate::Result<()> {
+        let block_size = limits.logical_block_size as usize;
+
+        for (lba, blocks) in
+            limits.chunks(logical_block_address, (buffer.len() / block_size) as u64)
+        {
+            let offset = (lba - logical_block_address) as usize * block_size;
+            let length = blocks as usize * block_size;
+            let transferred = self
+                .read()
+                .logical_block_address(lba)
+                .logical_block_size(limits.logical_block_size)
+                .transfer_length(blocks)
+                .issue_into(&mut buffer[offset..offset + length])?;
+
+            if transferred != length {
+                return Err(crate::Error::Other(format!(
+                    "{} bytes were read at logical block {}, {} were requested.",
+                    transferred, lba, length
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the logical block size if `length` is a multiple of it.
+fn check_length(limits: &TransferLimits, length: usize) -> crate::Result<usize> {
+    let block_size = limits.logical_block_size as usize;
+    if block_size == 0 || !length.is_multiple_of(block_size) {
+        return Err(crate::Error::BadArgument(format!(
+            "buffer length {} is not a multiple of the logical block size, which is {}.",
+            length, block_size
+        )));
+    }
+
+    Ok(block_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emulator::EmulatedDisk;
+
+    #[test]
+    fn chunks_test() {
+        let limits = TransferLimits {
+            logical_block_size: 512,
+            maximum_transfer_length: Some(0x1_0000),
+            optimal_transfer_length: Some(3000),
+            optimal_transfer_length_granularity: Some(8),
+            kernel_maximum_bytes: Some(1 << 20),
+        };
+        assert_eq!(limits.maximum_blocks(), 2048);
+        assert_eq!(limits.preferred_blocks(), 2048);
+        assert_eq!(
+            limits.chunks(5, 5000).collect::<Vec<_>>(),
+            vec![(5, 2043), (2048, 2048), (4096, 909)]
+        );
+        assert_eq!(limits.chunks(5, 0).count(), 0);
+    }
+
+    #[test]
+    fn chunked_transfer_test() {
+        let scsi = Scsi::with_transport("disk", EmulatedDisk::new(512, 8192).unwrap());
+
+        let limits = scsi.transfer_limits().unwrap();
+        assert_eq!(limits.logical_block_size, 512);
+        assert_eq!(limits.kernel_maximum_bytes, None);
+        assert_eq!(limits.optimal_transfer_length_granularity, Some(8));
+
+        let data: Vec<u8> = (0..5000 * 512).map(|i| (i % 251) as u8).collect();
+        scsi.write_blocks(5, &data).unwrap();
+        assert_eq!(scsi.read_blocks(5, 5000).unwrap(), data);
+        scsi.verify_blocks(5, 5000).unwrap();
+
+        let mut buffer = vec![0; 1000];
+        assert!(scsi.read_blocks_into(0, &mut buffer).is_err());
+    }
+}
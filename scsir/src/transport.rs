@@ -0,0 +1,272 @@
+use std::{
+    fmt::Debug,
+    io::{self, IoSliceMut},
+    time::Duration,
+};
+
+use crate::DataDirection;
+
+/// Everything a transport needs to execute a single command.
+#[derive(Debug)]
+pub struct TransportRequest<'a> {
+    /// The command descriptor block.
+    pub command: &'a [u8],
+    pub direction: DataDirection,
+    /// The data-in buffer, empty for `ToDevice`.
+    pub data: &'a mut [u8],
+    /// The data-out buffer of a `ToDevice` or bidirectional command, empty for everything else.
+    /// It may be borrowed from the caller, so it must never be written.
+    pub data_out: &'a [u8],
+    /// Sense data should be written here, at most `sense.len()` bytes.
+    pub sense: &'a mut [u8],
+    pub timeout: Duration,
+}
+
+impl TransportRequest<'_> {
+    /// The length [`TransportResponse::residual`] counts from, `data_out` for `ToDevice`,
+    /// `data` otherwise.
+    pub fn transfer_length(&self) -> usize {
+        match self.direction {
+            DataDirection::ToDevice => self.data_out.len(),
+            _ => self.data.len(),
+        }
+    }
+}
+
+/// The outcome of a command that reached the device.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TransportResponse {
+    /// SCSI status byte.
+    pub status: u8,
+    /// Host adapter status, only meaningful on Linux.
+    pub host_status: u16,
+    /// Software driver status, only meaningful on Linux.
+    pub driver_status: u16,
+    /// Auxiliary information, e.g. whether direct I/O was done, only meaningful on Linux.
+    pub auxiliary_info: u32,
+    /// Number of bytes written to the sense buffer.
+    pub sense_length: usize,
+    /// Number of data bytes that were not transferred.
+    pub residual: usize,
+    /// Number of bytes of `data_out` that were not transferred.
+    pub data_out_residual: usize,
+    /// How long the command took as measured by the driver, `None` if it doesn't tell.
+    pub duration: Option<Duration>,
+}
+
+/// Delivers commands to a logical unit.
+///
+/// [`Scsi`](crate::Scsi) uses the operating system's pass-through interface by default, but any
+/// implementation can be plugged in with [`Scsi::with_transport`](crate::Scsi::with_transport),
+/// so that every command builder can run against mocks, emulators or userspace transports.
+///
+/// An `Err` return means the command couldn't be delivered at all. Command failures reported by
+/// the device, e.g. CHECK CONDITION, belong in [`TransportResponse`].
+pub trait Transport: Debug + Send + Sync {
+    fn execute(&self, request: TransportRequest) -> io::Result<TransportResponse>;
+
+    /// Executes a command whose data is scattered over `buffers`, `request.data` and, unless
+    /// the command is bidirectional, `request.data_out` are empty.
+    ///
+    /// The default implementation goes through a contiguous bounce buffer, transports able to
+    /// hand scatter-gather lists to the device should override it.
+    fn execute_vectored(
+        &self,
+        request: TransportRequest,
+        buffers: &mut [IoSliceMut],
+    ) -> io::Result<TransportResponse> {
+        let direction = request.direction;
+        let mut bounce_buffer = buffers
+            .iter()
+            .flat_map(|buffer| buffer.iter())
+            .copied()
+            .collect::<Vec<_>>();
+
+        let response = match direction {
+            DataDirection::ToDevice => self.execute(TransportRequest {
+                data_out: &bounce_buffer,
+                ..request
+            })?,
+            _ => self.execute(TransportRequest {
+                data: &mut bounce_buffer,
+                ..request
+            })?,
+        };
+
+        if !matches!(direction, DataDirection::ToDevice | DataDirection::None) {
+            let mut remaining = &bounce_buffer[..];
+            for buffer in buffers.iter_mut() {
+                let (head, tail) = remaining.split_at(buffer.len());
+                buffer.copy_from_slice(head);
+                remaining = tail;
+            }
+        }
+
+        Ok(response)
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for std::sync::Arc<T> {
+    fn execute(&self, request: TransportRequest) -> io::Result<TransportResponse> {
+        (**self).execute(request)
+    }
+
+    fn execute_vectored(
+        &self,
+        request: TransportRequest,
+        buffers: &mut [IoSliceMut],
+    ) -> io::Result<TransportResponse> {
+        (**self).execute_vectored(request, buffers)
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn execute(&self, request: TransportRequest) -> io::Result<TransportResponse> {
+        (**self).execute(request)
+    }
+
+    fn execute_vectored(
+        &self,
+        request: TransportRequest,
+        buffers: &mut [IoSliceMut],
+    ) -> io::Result<TransportResponse> {
+        (**self).execute_vectored(request, buffers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::{command::sense::SenseKey, Scsi, Status};
+
+    #[derive(Debug, Default)]
+    struct RecordingTransport {
+        commands: Arc<Mutex<Vec<Vec<u8>>>>,
+    }
+
+    impl Transport for RecordingTransport {
+        fn execute(&self, request: TransportRequest) -> io::Result<TransportResponse> {
+            self.commands.lock().unwrap().push(request.command.to_vec());
+
+            if request.command[0] == 0x00 {
+                // NOT READY, LOGICAL UNIT IS IN PROCESS OF BECOMING READY
+                let sense = [
+                    0x70, 0, 0x02, 0, 0, 0, 0, 10, 0, 0, 0, 0, 0x04, 0x01, 0, 0, 0, 0,
+                ];
+                request.sense[..sense.len()].copy_from_slice(&sense);
+                return Ok(TransportResponse {
+                    status: 0x02,
+                    sense_length: sense.len(),
+                    ..Default::default()
+                });
+            }
+
+            request.data.fill(0xA5);
+            Ok(TransportResponse {
+                residual: 512,
+                ..Default::default()
+            })
+        }
+    }
+
+    #[test]
+    fn custom_transport_test() {
+        let transport = RecordingTransport::default();
+        let commands = transport.commands.clone();
+        let scsi = Scsi::with_transport("mock", transport);
+
+        let data = scsi
+            .read()
+            .logical_block_address(0x1234)
+            .transfer_length(2)
+            .issue_10()
+            .unwrap();
+        assert_eq!(data.len(), 1024);
+        assert!(data.iter().all(|&b| b == 0xA5));
+
+        let error = scsi.test_unit_ready().issue().unwrap_err();
+        let error = error.command_error().unwrap();
+        assert_eq!(error.status(), Status::CheckCondition);
+        assert_eq!(error.sense_key(), Some(SenseKey::NotReady));
+        assert_eq!(error.asc_ascq(), Some((0x04, 0x01)));
+        assert_eq!(error.command(), &[0x00, 0, 0, 0, 0, 0]);
+        assert!(error.is_retryable());
+
+        assert_eq!(
+            *commands.lock().unwrap(),
+            vec![
+                vec![0x28, 0, 0, 0, 0x12, 0x34, 0, 0, 2, 0],
+                vec![0x00, 0, 0, 0, 0, 0],
+            ]
+        );
+    }
+
+    #[test]
+    fn io_error_test() {
+        #[derive(Debug)]
+        struct BrokenTransport;
+
+        impl Transport for BrokenTransport {
+            fn execute(&self, _: TransportRequest) -> io::Result<TransportResponse> {
+                Err(io::Error::from(io::ErrorKind::BrokenPipe))
+            }
+        }
+
+        let scsi = Scsi::with_transport("broken", BrokenTransport);
+        match scsi.test_unit_ready().issue() {
+            Err(crate::Error::IO(error)) => assert_eq!(error.kind(), io::ErrorKind::BrokenPipe),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn bidirectional_test() {
+        #[derive(Debug)]
+        struct XorTransport;
+
+        impl Transport for XorTransport {
+            fn execute(&self, request: TransportRequest) -> io::Result<TransportResponse> {
+                assert_eq!(request.direction, DataDirection::ToFromDevice);
+                for (data_in, data_out) in request.data.iter_mut().zip(request.data_out) {
+                    *data_in = data_out ^ 0xFF;
+                }
+                Ok(TransportResponse::default())
+            }
+        }
+
+        let scsi = Scsi::with_transport("xor", XorTransport);
+        let data = scsi
+            .xd_write_read()
+            .parameter(&[0x0F; 1024])
+            .issue_10()
+            .unwrap();
+        assert_eq!(data, [0xF0; 1024]);
+    }
+
+    #[test]
+    fn data_out_test() {
+        #[derive(Debug, Default)]
+        struct CapturingTransport(Mutex<Vec<Vec<u8>>>);
+
+        impl Transport for CapturingTransport {
+            fn execute(&self, request: TransportRequest) -> io::Result<TransportResponse> {
+                assert_eq!(request.direction, DataDirection::ToDevice);
+                assert!(request.data.is_empty());
+                self.0.lock().unwrap().push(request.data_out.to_vec());
+                Ok(TransportResponse::default())
+            }
+        }
+
+        let transport = Arc::new(CapturingTransport::default());
+        let scsi = Scsi::with_transport("capture", transport.clone());
+        let data = [0x5A; 1024];
+        scsi.write().parameter_borrowed(&data).issue_10().unwrap();
+        scsi.write().parameter(&data[..512]).issue_10().unwrap();
+        assert_eq!(
+            *transport.0.lock().unwrap(),
+            vec![data.to_vec(), data[..512].to_vec()]
+        );
+    }
+}